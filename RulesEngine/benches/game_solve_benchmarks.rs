@@ -1,7 +1,8 @@
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, SamplingMode};
 use std::hint::black_box;
-use RulesEngine::console_interface::parse_level;
-use RulesEngine::state_graph::{StateGraph, UniqueNode, populate_step, get_all_adjacent_nodes, PopulateResult};
+use RulesEngine::console_interface::{parse_level_with_validation, LevelValidation};
+use RulesEngine::core::ExplorationOptions;
+use RulesEngine::state_graph::{StateGraph, UniqueNode, populate_step, populate_parallel_step, populate_all, populate_winnable, trim_unwinnable, get_all_adjacent_nodes, PopulateResult};
 
 const PUZZLES: &[(&str, &str, usize, SamplingMode)] = &[
     ("puzzle_0", r#"
@@ -49,6 +50,33 @@ const PUZZLES: &[(&str, &str, usize, SamplingMode)] = &[
 "#, 10, SamplingMode::Flat),
 ];
 
+/// Pulling back from the won nodes against pushing forward from the start: a level where every
+/// push leads to a deadlock the detectors miss, as the boxes enter the targets' room beside a wall,
+/// and an open level where the detectors catch nearly every deadlock
+const WINNABLE_PUZZLES: &[(&str, &str, usize, SamplingMode)] = &[
+    ("dead_end_0", r#"
+##########
+#        #
+# $ $ $  #
+#   @    #
+#### #####
+   #    #
+   #    #
+   #... #
+   ######
+"#, 10, SamplingMode::Flat),
+    ("open_0", r#"
+#########
+#       #
+# $ $ $ #
+#   @   #
+#       #
+###. ####
+  #.. #
+  #####
+"#, 10, SamplingMode::Flat),
+];
+
 pub fn bench_game_solve_full_graph(c: &mut Criterion) {
     let mut group = c.benchmark_group("game_solve_full_graph");
 
@@ -61,7 +89,7 @@ pub fn bench_game_solve_full_graph(c: &mut Criterion) {
             |b, &puzzle| {
                 b.iter_with_setup(
                     || {
-                        let (game_state, shared) = parse_level(puzzle);
+                        let (game_state, shared) = parse_level_with_validation(puzzle, LevelValidation::PERMISSIVE).unwrap();
                         let mut state_graph = StateGraph::new();
                         let min_reachable_position = shared
                             .reachable_positions(&game_state)
@@ -73,13 +101,65 @@ pub fn bench_game_solve_full_graph(c: &mut Criterion) {
                             minimum_reachable_player_position: min_reachable_position.into(),
                         };
                         state_graph.upsert_state(first_node);
-                        (state_graph, shared)
+                        (state_graph, shared, ExplorationOptions::default())
                     },
-                    |(mut state_graph, shared)| {
+                    |(mut state_graph, shared, options)| {
                         loop {
                             let result = populate_step(
                                 black_box(&mut state_graph),
-                                black_box(&shared)
+                                black_box(&shared),
+                                black_box(&options)
+                            );
+                            if let PopulateResult::AllVisited = result {
+                                break;
+                            }
+                        }
+                        black_box(state_graph)
+                    },
+                );
+            },
+        );
+    }
+    group.finish();
+}
+
+pub fn bench_game_solve_parallel_graph(c: &mut Criterion) {
+    let mut group = c.benchmark_group("game_solve_parallel_graph");
+    let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+
+    for &(puzzle_name, puzzle, sample_size, sample_mode) in PUZZLES {
+        group.sample_size(sample_size);
+        group.sampling_mode(sample_mode);
+        group.bench_with_input(
+            BenchmarkId::new(format!("parallel_graph_{}_threads", threads), puzzle_name),
+            &puzzle,
+            |b, &puzzle| {
+                b.iter_with_setup(
+                    || {
+                        let (game_state, shared) = parse_level_with_validation(puzzle, LevelValidation::PERMISSIVE).unwrap();
+                        let mut state_graph = StateGraph::new();
+                        let min_reachable_position = shared
+                            .reachable_positions(&game_state)
+                            .into_iter()
+                            .min()
+                            .unwrap();
+                        let first_node = UniqueNode {
+                            environment: game_state.environment,
+                            minimum_reachable_player_position: min_reachable_position.into(),
+                        };
+                        state_graph.upsert_state(first_node);
+                        let options = ExplorationOptions {
+                            threads,
+                            ..ExplorationOptions::default()
+                        };
+                        (state_graph, shared, options)
+                    },
+                    |(mut state_graph, shared, options)| {
+                        loop {
+                            let result = populate_parallel_step(
+                                black_box(&mut state_graph),
+                                black_box(&shared),
+                                black_box(&options)
                             );
                             if let PopulateResult::AllVisited = result {
                                 break;
@@ -106,7 +186,7 @@ pub fn bench_game_solve_single_node(c: &mut Criterion) {
             |b, &puzzle| {
                 b.iter_with_setup(
                     || {
-                        let (game_state, shared) = parse_level(puzzle);
+                        let (game_state, shared) = parse_level_with_validation(puzzle, LevelValidation::PERMISSIVE).unwrap();
                         let min_reachable_position = shared
                             .reachable_positions(&game_state)
                             .into_iter().min().unwrap();
@@ -114,12 +194,13 @@ pub fn bench_game_solve_single_node(c: &mut Criterion) {
                             environment: game_state.environment,
                             minimum_reachable_player_position: min_reachable_position.into(),
                         };
-                        (unique_node, shared)
+                        (unique_node, shared, ExplorationOptions::default())
                     },
-                    |(unique_node, shared)| {
+                    |(unique_node, shared, options)| {
                         let adjacent_nodes = get_all_adjacent_nodes(
                             black_box(&unique_node),
-                            black_box(&shared)
+                            black_box(&shared),
+                            black_box(&options)
                         );
                         black_box(adjacent_nodes)
                     },
@@ -130,9 +211,39 @@ pub fn bench_game_solve_single_node(c: &mut Criterion) {
     group.finish();
 }
 
+pub fn bench_game_solve_winnable_graph(c: &mut Criterion) {
+    let mut group = c.benchmark_group("game_solve_winnable_graph");
+
+    for &(puzzle_name, puzzle, sample_size, sample_mode) in WINNABLE_PUZZLES {
+        group.sample_size(sample_size);
+        group.sampling_mode(sample_mode);
+        let setup = || {
+            let (game_state, shared) = parse_level_with_validation(puzzle, LevelValidation::PERMISSIVE).unwrap();
+            let mut state_graph = StateGraph::new();
+            state_graph.upsert_state(UniqueNode::from_game_state(game_state, &shared));
+            (state_graph, shared, ExplorationOptions::default())
+        };
+        group.bench_function(BenchmarkId::new("populate_winnable", puzzle_name), |b| {
+            b.iter_with_setup(setup, |(mut state_graph, shared, options)| {
+                populate_winnable(black_box(&mut state_graph), black_box(&shared), black_box(&options));
+                black_box(state_graph)
+            });
+        });
+        group.bench_function(BenchmarkId::new("populate_then_trim", puzzle_name), |b| {
+            b.iter_with_setup(setup, |(mut state_graph, shared, options)| {
+                populate_all(black_box(&mut state_graph), black_box(&shared), black_box(&options));
+                trim_unwinnable(black_box(&mut state_graph), black_box(&shared));
+                black_box(state_graph)
+            });
+        });
+    }
+    group.finish();
+}
+
 criterion_group!(
     game_solve_benches,
-    bench_game_solve_full_graph, bench_game_solve_single_node
+    bench_game_solve_full_graph, bench_game_solve_parallel_graph, bench_game_solve_single_node,
+    bench_game_solve_winnable_graph
 );
 
 criterion_main!(game_solve_benches);
\ No newline at end of file
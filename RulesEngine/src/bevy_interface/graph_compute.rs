@@ -24,6 +24,8 @@ pub struct GraphData {
 pub struct GraphNodeData {
     pub id: usize,
     pub on_targets: usize,
+    /// On the frontier of a partial graph
+    pub unexpanded: bool,
 }
 
 pub struct GraphEdgeData {
@@ -53,16 +55,17 @@ impl AllEdgeIndexes {
 impl GraphData {
     pub fn from_state_graph(graph: &StateGraph, shared: &SharedGameState) -> Self {
         let nodes: Vec<GraphNodeData> = graph.nodes.iter()
-            .map(|(state, &id)| GraphNodeData {
-                id,
+            .map(|(id, state)| GraphNodeData {
+                id: id as usize,
                 on_targets: shared.count_boxes_on_goals(&state.environment),
+                unexpanded: graph.unvisited.contains(&id),
             })
             .collect();
 
         let edges: Vec<GraphEdgeData> = graph.edges.iter()
             .map(|edge| GraphEdgeData {
-                from: edge.from,
-                to: edge.to,
+                from: edge.from as usize,
+                to: edge.to as usize,
             })
             .collect();
 
@@ -12,7 +12,7 @@ mod selected_game_navigation;
 
 use bevy::prelude::*;
 use bevy_panorbit_camera::{PanOrbitCamera, PanOrbitCameraPlugin};
-use crate::state_graph::StateGraph;
+use crate::state_graph::{NodeId, StateGraph};
 use crate::core::{Cell, SharedGameState};
 use rand::Rng;
 use std::collections::{HashMap, HashSet};
@@ -58,6 +58,8 @@ struct GraphNode {
     id: usize,
     velocity: Vec3,
     on_targets: usize,
+    /// Left on the frontier when exploration ran out of budget
+    unexpanded: bool,
 }
 
 #[derive(Resource)]
@@ -112,6 +114,17 @@ struct UserConfig {
 struct GraphVisualizationAssets {
     node_mesh: Handle<Mesh>,
     node_materials: Vec<Handle<StandardMaterial>>,
+    unexpanded_node_material: Handle<StandardMaterial>,
+}
+
+impl GraphVisualizationAssets {
+    fn material_for(&self, node: &GraphNode) -> Handle<StandardMaterial> {
+        if node.unexpanded {
+            self.unexpanded_node_material.clone()
+        } else {
+            self.node_materials[node.on_targets].clone()
+        }
+    }
 }
 
 impl UserConfig {
@@ -137,13 +150,13 @@ impl UserConfig {
 }
 
 pub fn visualize_graph(
-    initial_node_id: usize,
+    initial_node_id: NodeId,
     graph: &StateGraph,
     shared: &SharedGameState) {
     let source_data = SourceGraphData {
         graph: graph.clone(),
         shared: shared.clone(),
-        initial_node_id
+        initial_node_id: initial_node_id as usize,
     };
 
     let graph_data = GraphData::from_state_graph(graph, shared);
@@ -161,7 +174,7 @@ pub fn visualize_graph(
     let mut app = App::new();
     app.add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
-                title: "3D Force-Directed Graph".to_string(),
+                title: format!("3D Force-Directed Graph, explored in {} order", graph.search_order),
                 resolution: (1200, 800).into(),
                 ..default()
             }),
@@ -280,6 +293,8 @@ fn setup_scene(
 }
 
 const DEFAULT_NODE_SPHERE_SIZE: f32 = 0.8;
+/// Frontier nodes of a partial graph, apart from the red to blue of boxes on targets
+const UNEXPANDED_NODE_COLOR: Color = Color::srgb(1.0, 0.85, 0.0);
 
 fn on_config_changed(
     trigger: On<ConfigChangedEvent>,
@@ -335,10 +350,16 @@ fn setup_shared_meshes(
         })
     })
         .collect::<Vec<_>>();
+    let unexpanded_node_material = materials.add(StandardMaterial {
+        base_color: UNEXPANDED_NODE_COLOR,
+        unlit: true,
+        ..default()
+    });
 
     commands.insert_resource(GraphVisualizationAssets {
         node_mesh,
         node_materials,
+        unexpanded_node_material,
     });
 }
 
@@ -364,19 +385,22 @@ fn setup_graph_from_data(
             rng.random_range(-15.0..15.0),
         );
 
+        let node = GraphNode {
+            id: node_data.id,
+            velocity: Vec3::ZERO,
+            on_targets: node_data.on_targets,
+            unexpanded: node_data.unexpanded,
+        };
+        let material = graph_assets.material_for(&node);
         let mut entity = commands.spawn((
             Transform::from_translation(position),
-            GraphNode {
-                id: node_data.id,
-                velocity: Vec3::ZERO,
-                on_targets: node_data.on_targets,
-            },
+            node,
         ));
 
         if RENDER_NODES {
             entity.insert((
                 Mesh3d(graph_assets.node_mesh.clone()),
-                MeshMaterial3d(graph_assets.node_materials[node_data.on_targets].clone()),
+                MeshMaterial3d(material),
                 Visibility::Visible,
             ));
         }
@@ -481,7 +505,7 @@ fn visualize_playing_games(
     // TODO: ideally, we would not iterate -every- node -every- time -any- game playing changed.
     let all_games = all_playing.iter()
         .filter_map(|(graph_node, playing_game_state)| {
-            let Some(selected_game_state) = source_graph_data.graph.nodes.get_by_right(&graph_node.id) else {
+            let Some(selected_game_state) = source_graph_data.graph.nodes.get(graph_node.id as NodeId) else {
                 eprintln!("Node not found {}", graph_node.id);
                 return None;
             };
@@ -642,7 +666,7 @@ fn on_space_pressed_start_playing_random_node(
     let mut rng = rand::rng();
 
     let (to_play, graph_node) = unplaying_nodes.iter().choose(&mut rng).expect("all nodes already being played?");
-    let unique_node = graph_data.graph.nodes.get_by_right(&graph_node.id).expect("node id not found");
+    let unique_node = graph_data.graph.nodes.get(graph_node.id as NodeId).expect("node id not found");
     commands.entity(to_play).insert(PlayingGameState::new_playing_state(unique_node));
 }
 
@@ -741,6 +765,6 @@ fn interpolate_color(on_targets: usize, max_on_targets: usize) -> Color {
 
 fn select_node(commands: &mut Commands, graph_data: &SourceGraphData, node_id: &usize, node_entity: Entity) {
     println!("Selecting {}", node_id);
-    let unique_node = graph_data.graph.nodes.get_by_right(&node_id).expect("node id not found");
+    let unique_node = graph_data.graph.nodes.get(*node_id as NodeId).expect("node id not found");
     commands.entity(node_entity).insert(PlayingGameState::new_playing_state(unique_node));
 }
\ No newline at end of file
@@ -62,7 +62,6 @@ fn when_unselected_handler(
             return;
         };
 
-        let new_material = external_visualization_assets.node_materials[node.on_targets].clone();
-        material.0 = new_material;
+        material.0 = external_visualization_assets.material_for(node);
     })
 }
\ No newline at end of file
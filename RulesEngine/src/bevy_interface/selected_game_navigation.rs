@@ -4,7 +4,7 @@ use bevy::prelude::*;
 use crate::bevy_interface::{GraphNode, SourceGraphData};
 use crate::bevy_interface::graph_compute::GraphComputeCache;
 use crate::core::{step, Direction, GameChangeType, GameState, GameUpdate, SharedGameState, UserAction};
-use crate::state_graph::UniqueNode;
+use crate::state_graph::{NodeId, UniqueNode};
 
 /// Placed on any Node which is currently being played, to represent the unique
 /// state not already captured by that node ?? ? ? ??
@@ -83,7 +83,7 @@ fn process_game_input(
     let shared = &game_graph_data.shared;
 
     for (entity, mut playing_game_state, node) in play_states.iter_mut() {
-        let game_node = game_graph_data.graph.nodes.get_by_right(&node.id).expect("game node not found!");
+        let game_node = game_graph_data.graph.nodes.get(node.id as NodeId).expect("game node not found!");
         let game_state = playing_game_state.apply_to_node(game_node.clone());
         let update = step(shared, &game_state, action);
 
@@ -96,7 +96,7 @@ fn process_game_input(
             }
             GameUpdate::NextState(game_state, GameChangeType::PlayerAndBoxMove) => {
                 let (new_playing, new_node) = PlayingGameState::extract_from_state(game_state, shared);
-                let new_game_id = game_graph_data.graph.nodes.get_by_left(&new_node);
+                let new_game_id = game_graph_data.graph.nodes.get_id(&new_node).map(|id| id as usize);
                 let Some(new_game_id) = new_game_id else {
                     // if the game does not exist in the graph, we abort the move. the game will remain.
                     println!("Action would end game. Aborting for game {:}", node.id);
@@ -105,7 +105,7 @@ fn process_game_input(
                 
                 commands.entity(entity).remove::<PlayingGameState>();
 
-                let Some(&entity) = graph_entity_lookup.get_entity(new_game_id) else {
+                let Some(&entity) = graph_entity_lookup.get_entity(&new_game_id) else {
                     eprintln!("Could not find game entity for game ID {:?}", new_game_id);
                     continue;
                 };
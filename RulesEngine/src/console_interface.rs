@@ -1,5 +1,5 @@
 use std::hint::black_box;
-use crate::core::{Direction, GameState, GameStateEnvironment, SharedGameState, UserAction, Vec2GameLogicAdapter, DEDUPLICATE_BOXES};
+use crate::core::{Direction, GameState, GameStateEnvironment, SharedGameState, UserAction, Vec2GameLogicAdapter};
 use crate::models::Cell::{
     Floor, Target, Wall,
 };
@@ -15,14 +15,65 @@ use ratatui::{
 use std::io;
 use bevy::math::IVec2;
 
-pub fn parse_level(s: &str) -> (GameState, SharedGameState) {
+/// Position within a level string, 1-based. Lines are counted from the start of the string,
+/// including any leading blank lines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LevelPosition {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LevelParseError {
+    /// No '@' or '+' in the level. Points at the first line of the map.
+    MissingPlayer { at: LevelPosition },
+    DuplicatePlayer { at: LevelPosition, first: LevelPosition },
+    /// Points at the first box which does not fit in the environment
+    TooManyBoxes { at: LevelPosition, count: usize, max: usize },
+    /// Points at the first box or target without a counterpart
+    BoxTargetMismatch { at: LevelPosition, boxes: usize, targets: usize },
+    /// The player can walk off the edge of the map. Points at the first open edge cell found.
+    NotEnclosed { at: LevelPosition },
+    /// The cell does not fit in the i8 coordinates of Vec2
+    CoordinateOverflow { at: LevelPosition },
+    UnknownCharacter { at: LevelPosition, character: char },
+}
+
+/// Which optional checks to apply when parsing a level. Levels which are structurally broken,
+/// such as missing a player, are always rejected.
+#[derive(Clone, Copy, Debug)]
+pub struct LevelValidation {
+    pub require_enclosed: bool,
+    pub require_box_per_target: bool,
+}
+
+impl LevelValidation {
+    pub const STRICT: LevelValidation = LevelValidation {
+        require_enclosed: true,
+        require_box_per_target: true,
+    };
+    /// Allows partial maps, useful for small test fixtures
+    pub const PERMISSIVE: LevelValidation = LevelValidation {
+        require_enclosed: false,
+        require_box_per_target: false,
+    };
+}
+
+pub fn parse_level(s: &str) -> Result<(GameState, SharedGameState), LevelParseError> {
+    parse_level_with_validation(s, LevelValidation::STRICT)
+}
+
+pub fn parse_level_with_validation(s: &str, validation: LevelValidation) -> Result<(GameState, SharedGameState), LevelParseError> {
     let mut grid: Vec<Vec<Cell>> = Vec::new();
-    let mut player = IVec2 { x: 0, y: 0 };
-    let mut boxes: Vec<IVec2> = Vec::new();
-    let max_width = s.lines().map(|line| line.len()).max().unwrap_or(0);
+    // the line number of each row in the grid, used to report errors
+    let mut row_lines: Vec<usize> = Vec::new();
+    let mut player: Option<(IVec2, LevelPosition)> = None;
+    let mut boxes: Vec<(IVec2, LevelPosition)> = Vec::new();
+    let mut targets: Vec<LevelPosition> = Vec::new();
+    let max_width = s.lines().map(|line| line.chars().count()).max().unwrap_or(0);
 
     let mut y = 0;
-    for line in s.lines() {
+    for (line_index, line) in s.lines().enumerate() {
         let line = line.trim_matches('\n');
         if line.len() == 0 {
             continue;
@@ -30,28 +81,40 @@ pub fn parse_level(s: &str) -> (GameState, SharedGameState) {
 
         let mut row = Vec::new();
         for (x, ch) in line.chars().enumerate() {
+            let at = LevelPosition { line: line_index + 1, column: x + 1 };
+            if x >= i8::MAX as usize || y >= i8::MAX as i32 {
+                return Err(LevelParseError::CoordinateOverflow { at });
+            }
             let pos = IVec2 { y, x: x as i32, };
             let c = match ch {
                 '#' => Wall,
-                ' ' => Floor,
-                '.' => Target,
+                ' ' | '-' | '_' => Floor,
+                '.' => {
+                    targets.push(at);
+                    Target
+                },
                 '$' => {
-                    boxes.push(pos);
+                    boxes.push((pos, at));
                     Floor
                 },
                 '*' => {
-                    boxes.push(pos);
+                    boxes.push((pos, at));
+                    targets.push(at);
                     Target
                 },
-                '@' => {
-                    player = pos;
-                    Floor
-                }
-                '+' => {
-                    player = pos;
-                    Target
+                '@' | '+' => {
+                    if let Some((_, first)) = player {
+                        return Err(LevelParseError::DuplicatePlayer { at, first });
+                    }
+                    player = Some((pos, at));
+                    if ch == '+' {
+                        targets.push(at);
+                        Target
+                    } else {
+                        Floor
+                    }
                 }
-                _ => Floor,
+                character => return Err(LevelParseError::UnknownCharacter { at, character }),
             };
             row.push(c);
         }
@@ -60,20 +123,108 @@ pub fn parse_level(s: &str) -> (GameState, SharedGameState) {
             row.push(Floor);
         }
         grid.push(row);
+        row_lines.push(line_index + 1);
         y += 1;
     }
 
-    (
+    let Some((player, _)) = player else {
+        let line = row_lines.first().copied().unwrap_or(1);
+        return Err(LevelParseError::MissingPlayer { at: LevelPosition { line, column: 1 } });
+    };
+
+    if boxes.len() > GameStateEnvironment::MAX_BOXES {
+        return Err(LevelParseError::TooManyBoxes {
+            at: boxes[GameStateEnvironment::MAX_BOXES].1,
+            count: boxes.len(),
+            max: GameStateEnvironment::MAX_BOXES,
+        });
+    }
+
+    if validation.require_box_per_target && boxes.len() != targets.len() {
+        let at = if boxes.len() > targets.len() {
+            boxes[targets.len()].1
+        } else {
+            targets[boxes.len()]
+        };
+        return Err(LevelParseError::BoxTargetMismatch { at, boxes: boxes.len(), targets: targets.len() });
+    }
+
+    if validation.require_enclosed && let Some(open) = find_open_edge(&grid, s, player) {
+        let at = LevelPosition { line: row_lines[open.y as usize], column: open.x as usize + 1 };
+        return Err(LevelParseError::NotEnclosed { at });
+    }
+
+    Ok((
         GameState {
             player: player.into(),
-            environment: GameStateEnvironment::new(boxes),
+            environment: GameStateEnvironment::new(boxes.into_iter().map(|(pos, _)| pos).collect()),
         },
-        SharedGameState {
-            grid,
+        SharedGameState::new(grid),
+    ))
+}
+
+/// Walks out from the player, ignoring boxes, and finds the first cell on the edge of the map
+/// or past the end of its line. Any such cell means the player could walk off the map.
+fn find_open_edge(grid: &[Vec<Cell>], s: &str, player: IVec2) -> Option<IVec2> {
+    let line_lengths: Vec<usize> = s.lines()
+        .map(|line| line.trim_matches('\n'))
+        .filter(|line| !line.is_empty())
+        .map(|line| line.chars().count())
+        .collect();
+    let height = grid.len() as i32;
+    let is_open_edge = |pos: &IVec2| {
+        let width = line_lengths[pos.y as usize] as i32;
+        pos.y == 0 || pos.y == height - 1 || pos.x == 0 || pos.x >= width - 1
+    };
+
+    let mut visited = vec![vec![false; grid[0].len()]; grid.len()];
+    let mut stack = vec![player];
+    while let Some(pos) = stack.pop() {
+        if visited[pos.y as usize][pos.x as usize] {
+            continue;
+        }
+        visited[pos.y as usize][pos.x as usize] = true;
+        if is_open_edge(&pos) {
+            return Some(pos);
+        }
+        for next in pos.neighbors() {
+            if grid[next.y as usize][next.x as usize] != Wall {
+                stack.push(next);
+            }
         }
-    )
+    }
+    None
 }
 
+impl std::fmt::Display for LevelPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+impl std::fmt::Display for LevelParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LevelParseError::MissingPlayer { at } =>
+                write!(f, "{}: level has no player, add a '@' or '+'", at),
+            LevelParseError::DuplicatePlayer { at, first } =>
+                write!(f, "{}: second player found, the first is at {}", at, first),
+            LevelParseError::TooManyBoxes { at, count, max } =>
+                write!(f, "{}: level has {} boxes, at most {} are supported", at, count, max),
+            LevelParseError::BoxTargetMismatch { at, boxes, targets } =>
+                write!(f, "{}: level has {} boxes but {} targets", at, boxes, targets),
+            LevelParseError::NotEnclosed { at } =>
+                write!(f, "{}: the player can walk off the map here, it must be enclosed by walls", at),
+            LevelParseError::CoordinateOverflow { at } =>
+                write!(f, "{}: level is too large, at most {} rows and columns are supported", at, i8::MAX),
+            LevelParseError::UnknownCharacter { at, character } =>
+                write!(f, "{}: unknown character '{}'", at, character),
+        }
+    }
+}
+
+impl std::error::Error for LevelParseError {}
+
 pub fn setup_terminal() -> Result<Terminal<CrosstermBackend<io::Stdout>>, Box<dyn std::error::Error>>
 {
     crossterm::terminal::enable_raw_mode()?;
@@ -0,0 +1,80 @@
+/// Solves the assignment problem with the Hungarian method, in O(rows² × columns).
+///
+/// Every row is assigned a distinct column, so there must be at least as many columns as rows.
+/// `cost(row, column)` is None where a row may not take a column. Returns the lowest total cost,
+/// or None when no assignment avoids the forbidden pairs.
+pub fn min_cost_assignment(rows: usize, columns: usize, cost: impl Fn(usize, usize) -> Option<u32>) -> Option<u32> {
+    if rows > columns {
+        return None;
+    }
+    if rows == 0 {
+        return Some(0);
+    }
+
+    // forbidden pairs cost more than any assignment of allowed pairs could, so they are only
+    // used when there is no other choice
+    const FORBIDDEN: i64 = 1 << 40;
+    let cost_of = |row: usize, column: usize| cost(row, column).map(|c| c as i64).unwrap_or(FORBIDDEN);
+
+    // 1-based, with row and column 0 as the virtual start of each augmenting path
+    let mut row_potential = vec![0i64; rows + 1];
+    let mut column_potential = vec![0i64; columns + 1];
+    let mut column_owner = vec![0usize; columns + 1];
+    let mut way = vec![0usize; columns + 1];
+
+    for row in 1..=rows {
+        column_owner[0] = row;
+        let mut column = 0;
+        let mut min_slack = vec![i64::MAX; columns + 1];
+        let mut used = vec![false; columns + 1];
+        loop {
+            used[column] = true;
+            let owner = column_owner[column];
+            let mut delta = i64::MAX;
+            let mut next_column = 0;
+            for j in 1..=columns {
+                if used[j] {
+                    continue;
+                }
+                let slack = cost_of(owner - 1, j - 1) - row_potential[owner] - column_potential[j];
+                if slack < min_slack[j] {
+                    min_slack[j] = slack;
+                    way[j] = column;
+                }
+                if min_slack[j] < delta {
+                    delta = min_slack[j];
+                    next_column = j;
+                }
+            }
+            for j in 0..=columns {
+                if used[j] {
+                    row_potential[column_owner[j]] += delta;
+                    column_potential[j] -= delta;
+                } else {
+                    min_slack[j] -= delta;
+                }
+            }
+            column = next_column;
+            if column_owner[column] == 0 {
+                break;
+            }
+        }
+        loop {
+            let previous = way[column];
+            column_owner[column] = column_owner[previous];
+            column = previous;
+            if column == 0 {
+                break;
+            }
+        }
+    }
+
+    let mut total = 0u32;
+    for (column, &row) in column_owner.iter().enumerate().skip(1) {
+        if row == 0 {
+            continue;
+        }
+        total += cost(row - 1, column - 1)?;
+    }
+    Some(total)
+}
@@ -0,0 +1,243 @@
+use crate::core::models::Vec2;
+
+/// Maps level positions onto bit indexes. Each row has one spare column at the end which is never
+/// part of any board, so shifting a board left or right cannot wrap from one row into the next.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitBoardLayout {
+    width: i8,
+    height: i8,
+    stride: usize,
+    words: usize,
+}
+
+/// A set of cells in a level, one bit per cell. Boards are only meaningful when combined with
+/// other boards sharing the same [`BitBoardLayout`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BitBoard {
+    words: Vec<u64>,
+}
+
+impl BitBoardLayout {
+    pub fn new(size: Vec2) -> BitBoardLayout {
+        let stride = size.j as usize + 1;
+        let bits = stride * size.i as usize;
+        BitBoardLayout {
+            width: size.j,
+            height: size.i,
+            stride,
+            words: bits.div_ceil(64).max(1),
+        }
+    }
+
+    pub fn index_of(&self, pos: Vec2) -> Option<usize> {
+        if pos.i < 0 || pos.j < 0 || pos.i >= self.height || pos.j >= self.width {
+            return None;
+        }
+        Some(pos.i as usize * self.stride + pos.j as usize)
+    }
+
+    pub fn position_of(&self, index: usize) -> Vec2 {
+        Vec2 {
+            i: (index / self.stride) as i8,
+            j: (index % self.stride) as i8,
+        }
+    }
+
+    /// Length of a table indexed like the boards
+    pub fn index_count(&self) -> usize {
+        self.words * 64
+    }
+
+    pub fn empty(&self) -> BitBoard {
+        BitBoard {
+            words: vec![0; self.words],
+        }
+    }
+
+    pub fn from_positions<'a>(&self, positions: impl Iterator<Item=&'a Vec2>) -> BitBoard {
+        let mut board = self.empty();
+        for &pos in positions {
+            if let Some(index) = self.index_of(pos) {
+                board.set(index);
+            }
+        }
+        board
+    }
+
+    /// Fills `reached` with every cell in `open` connected to `start`. `scratch` is used as working
+    /// space, passing it in lets callers in hot loops avoid allocating.
+    /// `start` itself is always reached, even when it is not open.
+    pub fn flood_fill_into(&self, open: &BitBoard, start: usize, reached: &mut BitBoard, scratch: &mut BitBoard) {
+        reached.clear();
+        reached.set(start);
+        while self.grow_into(open, reached, scratch) {
+            std::mem::swap(reached, scratch);
+        }
+    }
+
+    /// Steps from `start` to every cell in `open` connected to it, indexed like the boards.
+    /// Cells out of reach are None.
+    pub fn walk_distances(&self, open: &BitBoard, start: usize) -> Vec<Option<u16>> {
+        let mut distances = vec![None; self.words * 64];
+        distances[start] = Some(0);
+        let mut reached = self.empty();
+        reached.set(start);
+        let mut grown = self.empty();
+        let mut steps = 0;
+        while self.grow_into(open, &reached, &mut grown) {
+            steps += 1;
+            for word in 0..self.words {
+                let mut added = grown.words[word] & !reached.words[word];
+                while added != 0 {
+                    distances[word * 64 + added.trailing_zeros() as usize] = Some(steps);
+                    added &= added - 1;
+                }
+            }
+            std::mem::swap(&mut reached, &mut grown);
+        }
+        distances
+    }
+
+    /// Sets `grown` to `reached` plus every cell in `open` one step away from it.
+    /// Returns whether any cell was added.
+    fn grow_into(&self, open: &BitBoard, reached: &BitBoard, grown: &mut BitBoard) -> bool {
+        let mut changed = false;
+        for word in 0..self.words {
+            let current = reached.words[word];
+            let neighbours = shifted_up_word(&reached.words, word, 1)
+                | shifted_down_word(&reached.words, word, 1)
+                | shifted_up_word(&reached.words, word, self.stride)
+                | shifted_down_word(&reached.words, word, self.stride);
+            let next = current | (neighbours & open.words[word]);
+            changed |= next != current;
+            grown.words[word] = next;
+        }
+        changed
+    }
+
+    pub fn flood_fill(&self, open: &BitBoard, start: usize) -> BitBoard {
+        let mut reached = self.empty();
+        let mut scratch = self.empty();
+        self.flood_fill_into(open, start, &mut reached, &mut scratch);
+        reached
+    }
+}
+
+/// The word at `word` of the board, after moving every bit `shift` indexes higher
+fn shifted_up_word(words: &[u64], word: usize, shift: usize) -> u64 {
+    let word_shift = shift / 64;
+    let bit_shift = shift % 64;
+    if word < word_shift {
+        return 0;
+    }
+    let source = word - word_shift;
+    let mut result = words[source] << bit_shift;
+    if bit_shift > 0 && source > 0 {
+        result |= words[source - 1] >> (64 - bit_shift);
+    }
+    result
+}
+
+/// The word at `word` of the board, after moving every bit `shift` indexes lower
+fn shifted_down_word(words: &[u64], word: usize, shift: usize) -> u64 {
+    let word_shift = shift / 64;
+    let bit_shift = shift % 64;
+    let source = word + word_shift;
+    if source >= words.len() {
+        return 0;
+    }
+    let mut result = words[source] >> bit_shift;
+    if bit_shift > 0 && source + 1 < words.len() {
+        result |= words[source + 1] << (64 - bit_shift);
+    }
+    result
+}
+
+impl BitBoard {
+    pub fn contains(&self, index: usize) -> bool {
+        self.words[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    pub fn set(&mut self, index: usize) {
+        self.words[index / 64] |= 1 << (index % 64);
+    }
+
+    pub fn unset(&mut self, index: usize) {
+        self.words[index / 64] &= !(1 << (index % 64));
+    }
+
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    /// Copies `other` into this board, without allocating
+    pub fn copy_from(&mut self, other: &BitBoard) {
+        self.words.copy_from_slice(&other.words);
+    }
+
+    /// Sets this board to the cells in `a` but not in `b`, without allocating
+    pub fn set_difference_of(&mut self, a: &BitBoard, b: &BitBoard) {
+        for ((out, a), b) in self.words.iter_mut().zip(&a.words).zip(&b.words) {
+            *out = a & !b;
+        }
+    }
+
+    pub fn first_index(&self) -> Option<usize> {
+        self.words.iter().enumerate()
+            .find(|(_, word)| **word != 0)
+            .map(|(i, word)| i * 64 + word.trailing_zeros() as usize)
+    }
+
+    pub fn count(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Number of cells in both boards, without allocating
+    pub fn count_common(&self, other: &BitBoard) -> usize {
+        self.words.iter().zip(&other.words).map(|(a, b)| (a & b).count_ones() as usize).sum()
+    }
+
+    pub fn iter_indexes(&self) -> impl Iterator<Item=usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(i, &word)| {
+            let mut remaining = word;
+            std::iter::from_fn(move || {
+                if remaining == 0 {
+                    return None;
+                }
+                let bit = remaining.trailing_zeros() as usize;
+                remaining &= remaining - 1;
+                Some(i * 64 + bit)
+            })
+        })
+    }
+}
+
+impl std::ops::BitAnd for &BitBoard {
+    type Output = BitBoard;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        BitBoard {
+            words: self.words.iter().zip(&rhs.words).map(|(a, b)| a & b).collect(),
+        }
+    }
+}
+
+impl std::ops::BitOr for &BitBoard {
+    type Output = BitBoard;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        BitBoard {
+            words: self.words.iter().zip(&rhs.words).map(|(a, b)| a | b).collect(),
+        }
+    }
+}
+
+impl std::ops::Sub for &BitBoard {
+    type Output = BitBoard;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        BitBoard {
+            words: self.words.iter().zip(&rhs.words).map(|(a, b)| a & !b).collect(),
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::{HashSet, VecDeque};
+use bevy::math::IVec2;
+use crate::core::{is_winnable_after_push_on, BitBoard, Direction, GameState, SharedGameState, Vec2GameLogicAdapter, WinnableState};
+use crate::core::model_helpers::VisitationState;
+use crate::core::models::Vec2;
+
+/// Most states searched for a single corral, beyond that the corral is assumed to be solvable
+const MAX_CORRAL_SEARCH_STATES: usize = 1000;
+
+/// Floor the player cannot reach, fenced off by boxes and walls
+#[derive(Debug)]
+pub struct Corral {
+    /// Every cell inside the corral that is not covered by a box
+    pub interior: Vec<Vec2>,
+    /// Every box next to the interior, both the fence and the boxes inside
+    pub boxes: Vec<Vec2>,
+}
+
+/// Checks every corral of the state for a corral deadlock, see [`is_corral_deadlocked`]
+pub fn is_winnable_with_corrals(shared: &SharedGameState, game: &GameState) -> WinnableState {
+    if shared.spare_boxes(game.environment.box_count()) > 0 {
+        return WinnableState::WinMaybePossible;
+    }
+
+    // most states have no corral at all, which the bitboards tell quickly
+    let boxes = shared.box_board(&game.environment);
+    let reachable = shared.reachable_board(game.player, &boxes);
+    let unreached = &(shared.walkable_board() - &boxes) - &reachable;
+    if unreached.first_index().is_none() {
+        return WinnableState::WinMaybePossible;
+    }
+
+    if find_corrals(shared, game).iter().any(|corral| is_corral_deadlocked(shared, game, corral)) {
+        WinnableState::WinImpossible
+    } else {
+        WinnableState::WinMaybePossible
+    }
+}
+
+/// Splits the floor the player cannot reach into corrals, in row-major order of their first cell
+pub fn find_corrals(shared: &SharedGameState, game: &GameState) -> Vec<Corral> {
+    let visitation = shared.reachable_positions_visitation(game);
+    let mut assigned: HashSet<IVec2> = HashSet::new();
+    let mut corrals = Vec::new();
+
+    for y in 0..shared.height() as i32 {
+        for x in 0..shared.width() as i32 {
+            let start = IVec2 { x, y };
+            if visitation[&start] != VisitationState::Walkable || !shared[start].is_walkable() || assigned.contains(&start) {
+                continue;
+            }
+
+            let mut interior = Vec::new();
+            let mut boxes = Vec::new();
+            let mut stack = vec![start];
+            assigned.insert(start);
+            while let Some(pos) = stack.pop() {
+                interior.push(pos.into());
+                for next in pos.neighbors() {
+                    let Some(&state) = visitation.get(&next) else {
+                        continue;
+                    };
+                    if state == VisitationState::Blocked {
+                        let box_pos: Vec2 = next.into();
+                        if !boxes.contains(&box_pos) {
+                            boxes.push(box_pos);
+                        }
+                    } else if state == VisitationState::Walkable && shared[next].is_walkable() && assigned.insert(next) {
+                        stack.push(next);
+                    }
+                }
+            }
+
+            // floor walled off from the start, such as outside the level, is not a corral
+            if boxes.is_empty() {
+                continue;
+            }
+            interior.sort_unstable();
+            boxes.sort_unstable();
+            corrals.push(Corral { interior, boxes });
+        }
+    }
+
+    corrals
+}
+
+/// A corral is deadlocked when its boxes cannot all be pushed onto targets, even with every other
+/// box taken off the level. Removing boxes only ever frees up pushes, so this never prunes a
+/// winnable state, as long as every box needs a target of its own.
+///
+/// Searches the pushes of the corral boxes, pruning dead squares and freeze deadlocks, until
+/// either every corral box is on a target or the player gets inside the corral. Both mean the
+/// corral may be solvable. Gives up and assumes the same after [`MAX_CORRAL_SEARCH_STATES`] states.
+pub fn is_corral_deadlocked(shared: &SharedGameState, game: &GameState, corral: &Corral) -> bool {
+    if shared.spare_boxes(game.environment.box_count()) > 0 {
+        return false;
+    }
+
+    let layout = shared.bit_layout();
+    let walkable = shared.walkable_board();
+    let targets = layout.from_positions(shared.push_distances().targets().iter());
+    let Some(player) = layout.index_of(game.player) else {
+        return false;
+    };
+
+    // reused for every state, to avoid allocating inside the loop
+    let mut open = layout.empty();
+    let mut reached = layout.empty();
+    let mut next_reached = layout.empty();
+    let mut scratch = layout.empty();
+
+    let interior = layout.from_positions(corral.interior.iter());
+    let start_boxes = layout.from_positions(corral.boxes.iter());
+    let mut seen: HashSet<(BitBoard, usize)> = HashSet::new();
+    let mut queue = VecDeque::new();
+    seen.insert((start_boxes.clone(), player));
+    queue.push_back((start_boxes, player));
+
+    while let Some((boxes, player)) = queue.pop_front() {
+        if (&boxes - &targets).first_index().is_none() {
+            return false;
+        }
+        if seen.len() > MAX_CORRAL_SEARCH_STATES {
+            return false;
+        }
+
+        open.set_difference_of(walkable, &boxes);
+        layout.flood_fill_into(&open, player, &mut reached, &mut scratch);
+        // once the player is inside, the corral is open and the full search takes over
+        if reached.count_common(&interior) > 0 {
+            return false;
+        }
+
+        for box_index in boxes.iter_indexes() {
+            let box_pos = layout.position_of(box_index);
+            for direction in Direction::ALL {
+                let Some(player_index) = layout.index_of(box_pos - direction.offset()) else {
+                    continue;
+                };
+                let destination = box_pos + direction.offset();
+                let Some(destination_index) = layout.index_of(destination) else {
+                    continue;
+                };
+                if !reached.contains(player_index) || !walkable.contains(destination_index) ||
+                    boxes.contains(destination_index) || shared.dead_squares().contains(destination_index) {
+                    continue;
+                }
+
+                let mut next_boxes = boxes.clone();
+                next_boxes.unset(box_index);
+                next_boxes.set(destination_index);
+                if is_winnable_after_push_on(shared, &next_boxes, destination) == WinnableState::WinImpossible {
+                    continue;
+                }
+
+                open.set_difference_of(walkable, &next_boxes);
+                layout.flood_fill_into(&open, box_index, &mut next_reached, &mut scratch);
+                let next_player = next_reached.first_index()
+                    .expect("the player always reaches their own cell");
+                let state = (next_boxes, next_player);
+                if seen.insert(state.clone()) {
+                    queue.push_back(state);
+                }
+            }
+        }
+    }
+
+    true
+}
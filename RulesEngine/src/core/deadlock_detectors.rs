@@ -0,0 +1,292 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use crate::core::{is_winnable, is_winnable_after_push_on, is_winnable_with_corrals, push_lower_bound, BitBoard, GameState, GameStateEnvironment, SharedGameState, WinnableState};
+use crate::core::models::Vec2;
+
+/// When a detector runs while generating the pushes from a state
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DetectorStage {
+    /// Once per state, before any of its pushes are generated
+    State,
+    /// Once per push, on the state the push leads to
+    Push,
+}
+
+/// The state right after a push, where only the pushed box moved
+pub struct PushedState<'a> {
+    pub environment: &'a GameStateEnvironment,
+    /// The same boxes as `environment`, as a board
+    pub boxes: &'a BitBoard,
+    pub moved_box: Vec2,
+}
+
+/// A rule recognising states that can no longer be won. Detectors must never prune a winnable
+/// state, the states they prune are left out of the graph entirely.
+pub trait DeadlockDetector: Send + Sync {
+    /// Short name, used to pick detectors on the command line and in statistics
+    fn name(&self) -> &'static str;
+
+    fn stage(&self) -> DetectorStage;
+
+    /// Only called for [`DetectorStage::State`] detectors
+    fn is_state_deadlocked(&self, _shared: &SharedGameState, _game: &GameState) -> bool {
+        false
+    }
+
+    /// Only called for [`DetectorStage::Push`] detectors
+    fn is_push_deadlocked(&self, _shared: &SharedGameState, _pushed: &PushedState) -> bool {
+        false
+    }
+}
+
+/// Boxes stuck in a corner off a target, see [`is_winnable`]
+pub struct TrappedBoxDetector;
+
+/// More boxes on dead squares than the level can spare, see [`SharedGameState::dead_squares`]
+pub struct DeadSquareDetector;
+
+/// The pushed box froze along with its neighbours, see [`crate::core::is_winnable_after_push`]
+pub struct FreezeDetector;
+
+/// No way to give every target its own box, see [`push_lower_bound`]
+pub struct MatchingDetector;
+
+/// Boxes fenced off from the player that cannot be solved, see [`is_winnable_with_corrals`]
+pub struct CorralDetector;
+
+impl DeadlockDetector for TrappedBoxDetector {
+    fn name(&self) -> &'static str {
+        "trapped"
+    }
+
+    fn stage(&self) -> DetectorStage {
+        DetectorStage::State
+    }
+
+    fn is_state_deadlocked(&self, shared: &SharedGameState, game: &GameState) -> bool {
+        is_winnable(shared, game) == WinnableState::WinImpossible
+    }
+}
+
+impl DeadlockDetector for DeadSquareDetector {
+    fn name(&self) -> &'static str {
+        "dead-squares"
+    }
+
+    fn stage(&self) -> DetectorStage {
+        DetectorStage::Push
+    }
+
+    fn is_push_deadlocked(&self, shared: &SharedGameState, pushed: &PushedState) -> bool {
+        let spare_boxes = shared.spare_boxes(pushed.environment.box_count());
+        pushed.boxes.count_common(shared.dead_squares()) > spare_boxes
+    }
+}
+
+impl DeadlockDetector for FreezeDetector {
+    fn name(&self) -> &'static str {
+        "freeze"
+    }
+
+    fn stage(&self) -> DetectorStage {
+        DetectorStage::Push
+    }
+
+    fn is_push_deadlocked(&self, shared: &SharedGameState, pushed: &PushedState) -> bool {
+        is_winnable_after_push_on(shared, pushed.boxes, pushed.moved_box) == WinnableState::WinImpossible
+    }
+}
+
+impl DeadlockDetector for MatchingDetector {
+    fn name(&self) -> &'static str {
+        "matching"
+    }
+
+    fn stage(&self) -> DetectorStage {
+        DetectorStage::State
+    }
+
+    fn is_state_deadlocked(&self, shared: &SharedGameState, game: &GameState) -> bool {
+        push_lower_bound(shared, &game.environment).is_none()
+    }
+}
+
+impl DeadlockDetector for CorralDetector {
+    fn name(&self) -> &'static str {
+        "corral"
+    }
+
+    fn stage(&self) -> DetectorStage {
+        DetectorStage::State
+    }
+
+    fn is_state_deadlocked(&self, shared: &SharedGameState, game: &GameState) -> bool {
+        is_winnable_with_corrals(shared, game) == WinnableState::WinImpossible
+    }
+}
+
+/// The built-in detector with that name, see [`BUILTIN_DETECTOR_NAMES`]
+pub fn builtin_detector(name: &str) -> Option<Box<dyn DeadlockDetector>> {
+    match name {
+        "dead-squares" => Some(Box::new(DeadSquareDetector)),
+        "freeze" => Some(Box::new(FreezeDetector)),
+        "trapped" => Some(Box::new(TrappedBoxDetector)),
+        "matching" => Some(Box::new(MatchingDetector)),
+        "corral" => Some(Box::new(CorralDetector)),
+        _ => None,
+    }
+}
+
+/// Every built-in detector, in the order [`DeadlockDetectors::all`] runs them
+pub const BUILTIN_DETECTOR_NAMES: [&str; 5] = ["dead-squares", "freeze", "trapped", "matching", "corral"];
+
+/// What a detector did so far
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DetectorStats {
+    pub name: &'static str,
+    /// States or pushes the detector looked at
+    pub checked: u64,
+    pub pruned: u64,
+    pub elapsed: Duration,
+}
+
+#[derive(Default)]
+struct DetectorCounters {
+    checked: AtomicU64,
+    pruned: AtomicU64,
+    nanos: AtomicU64,
+}
+
+/// An ordered chain of detectors. Each stage runs its detectors in order, and stops at the
+/// first one that prunes, so cheap detectors should come first.
+///
+/// Counters are atomic, so one chain can be shared between threads exploring the same graph.
+#[derive(Default)]
+pub struct DeadlockDetectors {
+    detectors: Vec<(Box<dyn DeadlockDetector>, DetectorCounters)>,
+}
+
+impl DeadlockDetectors {
+    /// No pruning at all, the graph holds every reachable state
+    pub fn none() -> DeadlockDetectors {
+        DeadlockDetectors::default()
+    }
+
+    /// [`DeadSquareDetector`], [`FreezeDetector`] and [`TrappedBoxDetector`], the pruning
+    /// exploration does unless other detectors are picked. Pushes onto dead squares and pushes
+    /// freezing a box off a target are never added.
+    pub fn defaults() -> DeadlockDetectors {
+        DeadlockDetectors::none()
+            .with(DeadSquareDetector)
+            .with(FreezeDetector)
+            .with(TrappedBoxDetector)
+    }
+
+    /// Every built-in detector, cheapest first
+    pub fn all() -> DeadlockDetectors {
+        BUILTIN_DETECTOR_NAMES.iter()
+            .fold(DeadlockDetectors::none(), |chain, name| {
+                chain.with_boxed(builtin_detector(name).expect("builtin names are valid"))
+            })
+    }
+
+    /// Built-in detectors from a comma separated list of names, in that order.
+    /// "all" and "none" are accepted on their own.
+    pub fn from_names(names: &str) -> Result<DeadlockDetectors, String> {
+        match names.trim() {
+            "all" => return Ok(DeadlockDetectors::all()),
+            "none" | "" => return Ok(DeadlockDetectors::none()),
+            _ => {}
+        }
+        names.split(',')
+            .map(str::trim)
+            .try_fold(DeadlockDetectors::none(), |chain, name| {
+                let detector = builtin_detector(name).ok_or_else(|| format!(
+                    "unknown deadlock detector '{}', expected one of {}", name, BUILTIN_DETECTOR_NAMES.join(", ")))?;
+                Ok(chain.with_boxed(detector))
+            })
+    }
+
+    /// Appends a detector to the end of the chain
+    pub fn with(self, detector: impl DeadlockDetector + 'static) -> DeadlockDetectors {
+        self.with_boxed(Box::new(detector))
+    }
+
+    pub fn with_boxed(mut self, detector: Box<dyn DeadlockDetector>) -> DeadlockDetectors {
+        self.detectors.push((detector, DetectorCounters::default()));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.detectors.is_empty()
+    }
+
+    /// The detectors in chain order, to run them on their own without touching the counters
+    pub fn iter(&self) -> impl Iterator<Item=&dyn DeadlockDetector> {
+        self.detectors.iter().map(|(detector, _)| detector.as_ref())
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.detectors.iter().map(|(detector, _)| detector.name()).collect()
+    }
+
+    pub fn is_state_deadlocked(&self, shared: &SharedGameState, game: &GameState) -> bool {
+        self.run(DetectorStage::State, |detector| detector.is_state_deadlocked(shared, game))
+    }
+
+    pub fn is_push_deadlocked(&self, shared: &SharedGameState, pushed: &PushedState) -> bool {
+        self.run(DetectorStage::Push, |detector| detector.is_push_deadlocked(shared, pushed))
+    }
+
+    fn run(&self, stage: DetectorStage, is_deadlocked: impl Fn(&dyn DeadlockDetector) -> bool) -> bool {
+        for (detector, counters) in &self.detectors {
+            if detector.stage() != stage {
+                continue;
+            }
+            let start = Instant::now();
+            let pruned = is_deadlocked(detector.as_ref());
+            counters.nanos.fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+            counters.checked.fetch_add(1, Ordering::Relaxed);
+            if pruned {
+                counters.pruned.fetch_add(1, Ordering::Relaxed);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Counters of every detector, in chain order
+    pub fn stats(&self) -> Vec<DetectorStats> {
+        self.detectors.iter()
+            .map(|(detector, counters)| DetectorStats {
+                name: detector.name(),
+                checked: counters.checked.load(Ordering::Relaxed),
+                pruned: counters.pruned.load(Ordering::Relaxed),
+                elapsed: Duration::from_nanos(counters.nanos.load(Ordering::Relaxed)),
+            })
+            .collect()
+    }
+
+    pub fn reset_stats(&self) {
+        for (_, counters) in &self.detectors {
+            counters.checked.store(0, Ordering::Relaxed);
+            counters.pruned.store(0, Ordering::Relaxed);
+            counters.nanos.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+impl std::str::FromStr for DeadlockDetectors {
+    type Err = String;
+
+    fn from_str(names: &str) -> Result<Self, Self::Err> {
+        DeadlockDetectors::from_names(names)
+    }
+}
+
+impl std::fmt::Display for DetectorStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let percentage = if self.checked == 0 { 0.0 } else { self.pruned as f64 * 100.0 / self.checked as f64 };
+        write!(f, "{}: pruned {} of {} checked ({:.1}%) in {:?}", self.name, self.pruned, self.checked, percentage, self.elapsed)
+    }
+}
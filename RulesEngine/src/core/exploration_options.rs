@@ -0,0 +1,138 @@
+use std::time::Duration;
+use crate::core::DeadlockDetectors;
+
+/// Which unvisited node the state graph expands next
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SearchOrder {
+    /// Oldest first, the graph grows one push deeper at a time
+    #[default]
+    BreadthFirst,
+    /// Newest first, follows a single line of pushes as deep as it goes
+    DepthFirst,
+    /// Lowest [`crate::core::push_lower_bound`] first, heads for states close to a win
+    BestFirst,
+    /// Uniformly random, the same seed picks the same nodes
+    Random(u64),
+}
+
+/// A limit on exploring, see [`ExplorationOptions::exhausted_budget`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Budget {
+    Nodes,
+    Edges,
+    Memory,
+    Time,
+}
+
+/// Everything that changes how a level is explored. Chosen at runtime, so a single binary can run
+/// comparative experiments.
+pub struct ExplorationOptions {
+    /// Keeps the boxes of every environment sorted, so states that only differ in which box sits
+    /// where are the same node
+    pub deduplicate_boxes: bool,
+    /// Removes every state that cannot reach a win, once exploration is done
+    pub trim_unwinnable: bool,
+    /// Prunes states while exploring, see [`DeadlockDetectors`]
+    pub detectors: DeadlockDetectors,
+    /// Stops exploring once the graph holds this many nodes
+    pub max_nodes: Option<usize>,
+    /// Stops exploring once the graph holds this many edges
+    pub max_edges: Option<usize>,
+    /// Stops exploring once the graph takes about this many bytes, see
+    /// [`crate::state_graph::StateGraph::memory_bytes`]
+    pub max_memory: Option<usize>,
+    /// Stops exploring after this long
+    pub max_time: Option<Duration>,
+    /// Ignored by [`crate::state_graph::DiskExplorer`], which always goes breadth first
+    pub search_order: SearchOrder,
+    /// Threads expanding the frontier, see [`crate::state_graph::populate_parallel_step`]
+    pub threads: usize,
+    /// Stores one node for every group of states which are mirrors or rotations of each other,
+    /// see [`crate::core::SharedGameState::symmetries`]
+    pub reduce_symmetry: bool,
+}
+
+impl Default for ExplorationOptions {
+    fn default() -> Self {
+        ExplorationOptions {
+            deduplicate_boxes: true,
+            trim_unwinnable: true,
+            detectors: DeadlockDetectors::defaults(),
+            max_nodes: None,
+            max_edges: None,
+            max_memory: None,
+            max_time: None,
+            search_order: SearchOrder::BreadthFirst,
+            threads: 1,
+            reduce_symmetry: false,
+        }
+    }
+}
+
+impl ExplorationOptions {
+    /// Every reachable state, with nothing pruned or trimmed
+    pub fn exhaustive() -> ExplorationOptions {
+        ExplorationOptions {
+            trim_unwinnable: false,
+            detectors: DeadlockDetectors::none(),
+            ..ExplorationOptions::default()
+        }
+    }
+
+    /// The first budget used up by a graph of this size, None while exploring may go on
+    pub fn exhausted_budget(&self, nodes: usize, edges: usize, memory_bytes: usize, elapsed: Duration) -> Option<Budget> {
+        if self.max_nodes.is_some_and(|max| nodes >= max) {
+            Some(Budget::Nodes)
+        } else if self.max_edges.is_some_and(|max| edges >= max) {
+            Some(Budget::Edges)
+        } else if self.max_memory.is_some_and(|max| memory_bytes >= max) {
+            Some(Budget::Memory)
+        } else if self.max_time.is_some_and(|max| elapsed >= max) {
+            Some(Budget::Time)
+        } else {
+            None
+        }
+    }
+}
+
+impl std::str::FromStr for SearchOrder {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "bfs" | "breadth-first" => Ok(SearchOrder::BreadthFirst),
+            "dfs" | "depth-first" => Ok(SearchOrder::DepthFirst),
+            "best" | "best-first" => Ok(SearchOrder::BestFirst),
+            "random" => Ok(SearchOrder::Random(0)),
+            _ => {
+                if let Some(seed) = name.strip_prefix("random:") {
+                    return seed.parse().map(SearchOrder::Random)
+                        .map_err(|_| format!("invalid random seed '{}'", seed));
+                }
+                Err(format!("unknown search order '{}', expected bfs, dfs, best or random:<seed>", name))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for SearchOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SearchOrder::BreadthFirst => write!(f, "bfs"),
+            SearchOrder::DepthFirst => write!(f, "dfs"),
+            SearchOrder::BestFirst => write!(f, "best"),
+            SearchOrder::Random(seed) => write!(f, "random:{}", seed),
+        }
+    }
+}
+
+impl std::fmt::Display for Budget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Budget::Nodes => write!(f, "node"),
+            Budget::Edges => write!(f, "edge"),
+            Budget::Memory => write!(f, "memory"),
+            Budget::Time => write!(f, "time"),
+        }
+    }
+}
@@ -1,76 +1,148 @@
 ﻿use std::hash::{Hash, Hasher};
-use std::hint::black_box;
 use bevy::prelude::IVec2;
-use crate::core::DEDUPLICATE_BOXES;
+use crate::core::ExplorationOptions;
 use crate::core::models::Vec2;
 
 #[derive(Clone, Debug)]
 pub struct GameStateEnvironment {
-    boxes: [Vec2; BOX_COUNT],
+    boxes: BoxStore,
 }
 
-const BOX_COUNT: usize = 8;
+/// Storage for the boxes, picked by the box count when the environment is created.
+/// Small levels keep the boxes inline, which keeps cloning and comparison as cheap as a memcpy.
+/// The box count never changes after creation, so an environment never switches variants.
+///
+/// `sorted` is true while the boxes are in order, so lookups can binary search them. It fits in
+/// the padding of the enum, and is left out of comparison and hashing.
+#[derive(Clone, Debug)]
+enum BoxStore {
+    /// Unused slots hold EMPTY_BOX, and always sort to the end
+    Inline { boxes: [Vec2; INLINE_BOX_COUNT], sorted: bool },
+    Spilled { boxes: Box<[Vec2]>, sorted: bool },
+}
+
+const INLINE_BOX_COUNT: usize = 8;
 const EMPTY_BOX: Vec2 = Vec2 { i: i8::MAX, j: i8::MAX };
 
 impl GameStateEnvironment {
+    /// Practical upper bound on boxes, far beyond what the state graph can explore
+    pub const MAX_BOXES: usize = u8::MAX as usize;
+
     pub fn new(boxes: Vec<IVec2>) -> GameStateEnvironment {
-        // TODO: use something like SmallVec to get best of both worlds?
-        assert!(boxes.len() <= 15, "boxes length should be less than 15");
-        let mut boxes_fixed = [EMPTY_BOX; BOX_COUNT];
-        for (i, &b) in boxes.iter().enumerate() {
-            boxes_fixed[i] = b.into();
-        }
-        let mut result = GameStateEnvironment {
-            boxes: boxes_fixed,
-        };
-        result.complete_moves();
+        let mut result = Self::from_ordered(boxes);
+        result.complete_moves(&ExplorationOptions::default());
         result
     }
 
+    /// Keeps the boxes in the order given, such as boxes already ordered by [`Self::complete_moves`]
+    pub fn from_ordered(boxes: Vec<IVec2>) -> GameStateEnvironment {
+        assert!(boxes.len() <= Self::MAX_BOXES, "boxes length should be at most {}", Self::MAX_BOXES);
+        let boxes = if boxes.len() <= INLINE_BOX_COUNT {
+            let mut boxes_fixed = [EMPTY_BOX; INLINE_BOX_COUNT];
+            for (i, &b) in boxes.iter().enumerate() {
+                boxes_fixed[i] = b.into();
+            }
+            BoxStore::Inline { sorted: boxes_fixed.is_sorted(), boxes: boxes_fixed }
+        } else {
+            let boxes: Box<[Vec2]> = boxes.into_iter().map(Vec2::from).collect();
+            BoxStore::Spilled { sorted: boxes.is_sorted(), boxes }
+        };
+        GameStateEnvironment {
+            boxes,
+        }
+    }
+
     pub fn new_empty() -> GameStateEnvironment {
         GameStateEnvironment {
-            boxes: [EMPTY_BOX; BOX_COUNT],
+            boxes: BoxStore::Inline { boxes: [EMPTY_BOX; INLINE_BOX_COUNT], sorted: true },
         }
     }
 
     pub fn iter_boxes(&self) -> impl Iterator<Item=&Vec2> {
-        self.boxes.iter().take_while(|&&b| b != EMPTY_BOX)
+        self.boxes_slice().iter().take_while(|&&b| b != EMPTY_BOX)
+    }
+
+    pub fn box_count(&self) -> usize {
+        self.iter_boxes().count()
     }
 
     pub fn has_box_at(&self, position: &Vec2) -> bool {
-        assert_ne!(position, &EMPTY_BOX, "position cannot be empty box special value");
-        self.iter_boxes().any(|b| b == position)
+        self.index_of_box_at(position).is_some()
     }
 
+    /// Binary searches the boxes while they are sorted, which they are whenever exploration
+    /// deduplicates boxes. Falls back to a scan after [`Self::set_box`] or without deduplication.
     pub fn index_of_box_at(&self, position: &Vec2) -> Option<usize> {
         assert_ne!(position, &EMPTY_BOX, "position cannot be empty box special value");
-        self.iter_boxes().position(|b| b == position)
+        let (boxes, sorted) = match &self.boxes {
+            BoxStore::Inline { boxes, sorted } => (&boxes[..], *sorted),
+            BoxStore::Spilled { boxes, sorted } => (&boxes[..], *sorted),
+        };
+        if sorted {
+            // empty slots sort after every position, so the search can run over all of them
+            boxes.binary_search(position).ok()
+        } else {
+            self.iter_boxes().position(|b| b == position)
+        }
     }
 
     pub fn set_box(&mut self, box_index: usize, position: &Vec2) {
         assert_ne!(position, &EMPTY_BOX, "position cannot be empty box special value");
-        self.boxes[box_index] = *position;
+        let (boxes, sorted) = self.boxes_mut();
+        boxes[box_index] = *position;
+        *sorted = false;
     }
 
-    pub fn complete_moves(&mut self) {
-        if black_box(DEDUPLICATE_BOXES) {
-            self.boxes.sort_unstable()
+    /// Call after moving boxes with [`Self::set_box`], before comparing or hashing the environment
+    pub fn complete_moves(&mut self, options: &ExplorationOptions) {
+        if options.deduplicate_boxes {
+            let (boxes, sorted) = self.boxes_mut();
+            boxes.sort_unstable();
+            *sorted = true;
+        }
+    }
+
+    fn boxes_slice(&self) -> &[Vec2] {
+        match &self.boxes {
+            BoxStore::Inline { boxes, .. } => boxes,
+            BoxStore::Spilled { boxes, .. } => boxes,
+        }
+    }
+
+    fn boxes_mut(&mut self) -> (&mut [Vec2], &mut bool) {
+        match &mut self.boxes {
+            BoxStore::Inline { boxes, sorted } => (boxes, sorted),
+            BoxStore::Spilled { boxes, sorted } => (boxes, sorted),
         }
     }
 }
 
 impl Hash for GameStateEnvironment {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.iter_boxes().for_each(|b| {
-            b.hash(state)
-        });
+        match &self.boxes {
+            // one write for the whole array, like comparing inline boxes compares whole arrays
+            BoxStore::Inline { boxes, .. } => {
+                let mut bytes = [0u8; INLINE_BOX_COUNT * 2];
+                for (k, b) in boxes.iter().enumerate() {
+                    bytes[k * 2] = b.i as u8;
+                    bytes[k * 2 + 1] = b.j as u8;
+                }
+                state.write_u128(u128::from_ne_bytes(bytes));
+            }
+            BoxStore::Spilled { .. } => self.iter_boxes().for_each(|b| {
+                b.hash(state)
+            }),
+        }
     }
 }
 
 impl Eq for GameStateEnvironment{}
 impl PartialEq<Self> for GameStateEnvironment {
     fn eq(&self, other: &Self) -> bool {
-        self.iter_boxes().eq(other.iter_boxes())
+        match (&self.boxes, &other.boxes) {
+            (BoxStore::Inline { boxes: a, .. }, BoxStore::Inline { boxes: b, .. }) => a == b,
+            _ => self.iter_boxes().eq(other.iter_boxes()),
+        }
     }
 }
 
@@ -94,4 +166,44 @@ mod test {
         assert!(!environment.has_box_at(&Vec2 { i: 1, j: 1 }));
         assert!(!environment.has_box_at(&Vec2 { i: -1, j: -1 }));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_more_boxes_than_inline_capacity() {
+        let boxes: Vec<IVec2> = (0..(INLINE_BOX_COUNT as i32 + 5))
+            .map(|x| IVec2 { x, y: 3 })
+            .rev()
+            .collect();
+
+        let mut environment = GameStateEnvironment::new(boxes.clone());
+        assert_eq!(boxes.len(), environment.box_count());
+        for b in &boxes {
+            assert!(environment.has_box_at(&(*b).into()));
+        }
+
+        let original = environment.clone();
+        let last_index = environment.index_of_box_at(&Vec2 { i: 3, j: 12 }).unwrap();
+        environment.set_box(last_index, &Vec2 { i: 4, j: 12 });
+        environment.complete_moves(&ExplorationOptions::default());
+        assert_ne!(original, environment);
+        assert!(environment.has_box_at(&Vec2 { i: 4, j: 12 }));
+        assert!(!environment.has_box_at(&Vec2 { i: 3, j: 12 }));
+    }
+
+    #[test]
+    fn test_finds_boxes_in_any_order() {
+        let boxes = vec![IVec2 { x: 5, y: 2 }, IVec2 { x: 1, y: 2 }, IVec2 { x: 3, y: 0 }];
+        let mut environment = GameStateEnvironment::from_ordered(boxes);
+        assert_eq!(environment.index_of_box_at(&Vec2 { i: 2, j: 1 }), Some(1));
+        assert_eq!(environment.index_of_box_at(&Vec2 { i: 0, j: 3 }), Some(2));
+
+        environment.set_box(1, &Vec2 { i: 4, j: 4 });
+        assert_eq!(environment.index_of_box_at(&Vec2 { i: 4, j: 4 }), Some(1));
+        assert!(!environment.has_box_at(&Vec2 { i: 2, j: 1 }));
+
+        environment.complete_moves(&ExplorationOptions::default());
+        assert_eq!(environment.index_of_box_at(&Vec2 { i: 0, j: 3 }), Some(0));
+        assert_eq!(environment.index_of_box_at(&Vec2 { i: 4, j: 4 }), Some(2));
+        assert!(environment.has_box_at(&Vec2 { i: 2, j: 5 }));
+        assert!(!environment.has_box_at(&Vec2 { i: 2, j: 1 }));
+    }
+}
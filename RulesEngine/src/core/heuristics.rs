@@ -1,5 +1,4 @@
-﻿use petgraph::visit::IntoNeighbors;
-use crate::core::{GameState, SharedGameState};
+﻿use crate::core::{min_cost_assignment, BitBoard, GameState, GameStateEnvironment, SharedGameState};
 use crate::core::Cell::Target;
 use crate::core::models::Vec2;
 
@@ -38,6 +37,105 @@ pub fn is_winnable(
     }
 }
 
+/// Checks the neighbourhood of the box that was just pushed to `moved_box` for a freeze deadlock.
+/// Only the moved box can have become frozen, so the rest of the level is not looked at.
+///
+/// A box is frozen when it is blocked both horizontally and vertically. Along an axis it is
+/// blocked by a wall on either side, by dead squares on both sides, or by a neighbouring box that
+/// is frozen itself. While a box is being checked it counts as a wall for its neighbours, which
+/// catches boxes side by side against a wall, 2x2 blocks of boxes and walls, and longer chains.
+/// The win is impossible when more frozen boxes are off a target than the level can spare.
+pub fn is_winnable_after_push(
+    shared: &SharedGameState,
+    environment: &GameStateEnvironment,
+    moved_box: Vec2) -> WinnableState {
+    is_winnable_after_push_on(shared, &shared.box_board(environment), moved_box)
+}
+
+/// Same as [`is_winnable_after_push`], with the boxes already on a board
+pub(crate) fn is_winnable_after_push_on(
+    shared: &SharedGameState,
+    boxes: &BitBoard,
+    moved_box: Vec2) -> WinnableState {
+
+    let check = FreezeCheck {
+        shared,
+        boxes,
+        spare_boxes: shared.spare_boxes(boxes.count()),
+    };
+    let mut checking = Vec::new();
+    let mut frozen = Vec::new();
+    if !check.is_frozen(moved_box, &mut checking, &mut frozen) {
+        return WinnableState::WinMaybePossible;
+    }
+
+    let frozen_off_target = frozen.iter().filter(|&&b| shared[b] != Target).count();
+    if frozen_off_target > check.spare_boxes {
+        WinnableState::WinImpossible
+    } else {
+        WinnableState::WinMaybePossible
+    }
+}
+
+struct FreezeCheck<'a> {
+    shared: &'a SharedGameState,
+    boxes: &'a BitBoard,
+    spare_boxes: usize,
+}
+
+impl FreezeCheck<'_> {
+    /// `checking` holds the boxes currently being checked, which count as walls.
+    /// On success `frozen` gains this box and every neighbour found frozen along the way.
+    fn is_frozen(&self, position: Vec2, checking: &mut Vec<Vec2>, frozen: &mut Vec<Vec2>) -> bool {
+        let frozen_before = frozen.len();
+        checking.push(position);
+        let is_frozen = self.is_blocked_along(position, Vec2 { i: 0, j: 1 }, checking, frozen)
+            && self.is_blocked_along(position, Vec2 { i: 1, j: 0 }, checking, frozen);
+        checking.pop();
+
+        if is_frozen {
+            frozen.push(position);
+        } else {
+            // neighbours were only frozen on the condition that this box could not move
+            frozen.truncate(frozen_before);
+        }
+        is_frozen
+    }
+
+    fn is_blocked_along(&self, position: Vec2, axis: Vec2, checking: &mut Vec<Vec2>, frozen: &mut Vec<Vec2>) -> bool {
+        let sides = [position + axis, position - axis];
+        if sides.iter().any(|&side| self.is_wall(side) || checking.contains(&side)) {
+            return true;
+        }
+        // pushing along this axis is allowed, but leaves the box stuck on a dead square
+        if self.spare_boxes == 0 && sides.iter().all(|&side| self.shared.is_dead_square(side)) {
+            return true;
+        }
+        sides.iter().any(|&side| self.has_box_at(side) && self.is_frozen(side, checking, frozen))
+    }
+
+    fn has_box_at(&self, position: Vec2) -> bool {
+        self.shared.bit_layout().index_of(position).is_some_and(|index| self.boxes.contains(index))
+    }
+
+    fn is_wall(&self, position: Vec2) -> bool {
+        let layout = self.shared.bit_layout();
+        layout.index_of(position).is_none_or(|index| !self.shared.walkable_board().contains(index))
+    }
+}
+
+/// Lower bound on the pushes needed to win, from matching every target to a different box at the
+/// lowest total push distance. Each distance ignores the other boxes and where the player is,
+/// so the bound never overestimates.
+/// None when there is no way to match every target with a box that can reach it, a deadlock.
+pub fn push_lower_bound(shared: &SharedGameState, environment: &GameStateEnvironment) -> Option<u32> {
+    let distances = shared.push_distances();
+    let boxes: Vec<Vec2> = environment.iter_boxes().copied().collect();
+    min_cost_assignment(distances.targets().len(), boxes.len(), |target, box_index| {
+        distances.distance(target, boxes[box_index]).map(u32::from)
+    })
+}
+
 /// a box is trapped if the player can never move it, and it is not on a target
 fn is_box_trapped(shared: &SharedGameState, game_box: Vec2) -> bool {
     let is_target = shared[game_box] == Target;
@@ -2,14 +2,27 @@ mod model_helpers;
 mod models;
 mod update;
 mod bounded_grid;
-mod consts;
 mod bounds;
 mod game_state_environment;
 mod heuristics;
+mod bitboard;
+mod push_distances;
+mod assignment;
+mod corral;
+mod deadlock_detectors;
+mod exploration_options;
+mod symmetry;
 
-pub use models::{Cell, Direction, UserAction, SharedGameState, GameState, GameUpdate, GameChangeType};
-pub use consts::*;
+pub use models::{Vec2, Cell, Direction, UserAction, SharedGameState, GameState, GameUpdate, GameChangeType};
 pub use game_state_environment::{GameStateEnvironment};
-pub use model_helpers::Vec2GameLogicAdapter;
-pub use update::step;
-pub use heuristics::{is_winnable, WinnableState};
+pub use model_helpers::{Vec2GameLogicAdapter, WonCheckHelper};
+pub use update::{step, step_with_box_board};
+pub use heuristics::{is_winnable, is_winnable_after_push, push_lower_bound, WinnableState};
+pub(crate) use heuristics::is_winnable_after_push_on;
+pub use push_distances::PushDistances;
+pub use assignment::min_cost_assignment;
+pub use bitboard::{BitBoard, BitBoardLayout};
+pub use corral::{find_corrals, is_corral_deadlocked, is_winnable_with_corrals, Corral};
+pub use deadlock_detectors::*;
+pub use exploration_options::{Budget, ExplorationOptions, SearchOrder};
+pub use symmetry::{detect_symmetries, Symmetry};
@@ -1,22 +1,111 @@
 use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
 use bevy::math::IVec2;
-use crate::core::{Cell, Direction, GameChangeType, GameState, SharedGameState, UserAction};
+use crate::core::{detect_symmetries, Cell, Direction, GameChangeType, GameState, SharedGameState, Symmetry, UserAction};
+use crate::core::bitboard::{BitBoard, BitBoardLayout};
 use crate::core::bounded_grid::BoundedGrid;
+use crate::core::push_distances::PushDistances;
 use crate::core::bounds::BoundsOriginRoot;
 use crate::core::game_state_environment::GameStateEnvironment;
 use crate::core::models::Vec2;
 
 pub struct WonCheckHelper {
-    target_positions_sorted: Vec<Vec2>,
+    layout: BitBoardLayout,
+    targets: BitBoard,
+    target_count: usize,
 }
 
 impl WonCheckHelper {
     pub fn is_won(&self, game_state: &GameStateEnvironment) -> bool {
-        self.target_positions_sorted.iter().all(|p| game_state.has_box_at(p))
+        let boxes = self.layout.from_positions(game_state.iter_boxes());
+        boxes.count_common(&self.targets) == self.target_count
     }
 }
 
 impl SharedGameState {
+    pub fn new(grid: Vec<Vec<Cell>>) -> SharedGameState {
+        let size = Vec2 {
+            i: grid.len() as i8,
+            j: grid.first().map(|row| row.len()).unwrap_or(0) as i8,
+        };
+        let layout = BitBoardLayout::new(size);
+        let mut walkable = layout.empty();
+        for (i, row) in grid.iter().enumerate() {
+            for (j, c) in row.iter().enumerate() {
+                if c.is_walkable() && let Some(index) = layout.index_of(Vec2 { i: i as i8, j: j as i8 }) {
+                    walkable.set(index);
+                }
+            }
+        }
+        let push_distances = PushDistances::new(&grid);
+        let mut dead_squares = layout.empty();
+        for index in walkable.iter_indexes() {
+            if !push_distances.reaches_any_target(layout.position_of(index)) {
+                dead_squares.set(index);
+            }
+        }
+        let symmetries = detect_symmetries(&grid);
+        SharedGameState {
+            grid,
+            layout,
+            walkable,
+            push_distances,
+            dead_squares,
+            symmetries,
+        }
+    }
+
+    pub fn bit_layout(&self) -> &BitBoardLayout {
+        &self.layout
+    }
+
+    /// Every cell which is not a wall
+    pub fn walkable_board(&self) -> &BitBoard {
+        &self.walkable
+    }
+
+    pub fn push_distances(&self) -> &PushDistances {
+        &self.push_distances
+    }
+
+    /// Floor cells a lone box can never be pushed from onto any target
+    pub fn dead_squares(&self) -> &BitBoard {
+        &self.dead_squares
+    }
+
+    /// Mirrors and rotations mapping the walls and targets onto themselves, besides the identity.
+    /// States which are mirrors or rotations of each other under these play out the same.
+    pub fn symmetries(&self) -> &[Symmetry] {
+        &self.symmetries
+    }
+
+    pub fn is_dead_square(&self, pos: Vec2) -> bool {
+        self.layout.index_of(pos).is_some_and(|index| self.dead_squares.contains(index))
+    }
+
+    /// How many boxes may sit on dead squares before the level can no longer be won.
+    /// Zero unless there are more boxes than targets.
+    pub fn spare_boxes(&self, box_count: usize) -> usize {
+        box_count.saturating_sub(self.push_distances.targets().len())
+    }
+
+    pub fn box_board(&self, environment: &GameStateEnvironment) -> BitBoard {
+        self.layout.from_positions(environment.iter_boxes())
+    }
+
+    /// Every cell the player can walk to from `player` without pushing any box in `boxes`
+    pub fn reachable_board(&self, player: Vec2, boxes: &BitBoard) -> BitBoard {
+        let start = self.layout.index_of(player).expect("player must be inside the level");
+        self.layout.flood_fill(&(&self.walkable - boxes), start)
+    }
+
+    /// Steps from `player` to every cell they can walk to without pushing any box in `boxes`,
+    /// indexed like the boards. Cells out of reach are None.
+    pub fn walk_steps(&self, player: Vec2, boxes: &BitBoard) -> Vec<Option<u16>> {
+        let start = self.layout.index_of(player).expect("player must be inside the level");
+        self.layout.walk_distances(&(&self.walkable - boxes), start)
+    }
+
     pub fn height(&self) -> i8 {
         self.grid.len() as i8
     }
@@ -43,32 +132,16 @@ impl SharedGameState {
     }
 
     pub fn get_won_check_helper(&self) -> WonCheckHelper {
-        let mut target_positions = Vec::new();
-        for (i, row) in self.grid.iter().enumerate() {
-            for (j, &c) in row.iter().enumerate() {
-                if c == Cell::Target {
-                    target_positions.push(Vec2 { i: i as i8, j: j as i8 });
-                }
-            }
-        }
-        target_positions.sort();
+        let targets = self.layout.from_positions(self.push_distances.targets().iter());
         WonCheckHelper {
-            target_positions_sorted: target_positions,
+            layout: self.layout,
+            target_count: targets.count(),
+            targets,
         }
     }
 
     pub fn is_won(&self, game_state: &GameState) -> bool {
-        for (i, row) in self.grid.iter().enumerate() {
-            for (j, &c) in row.iter().enumerate() {
-                if c == Cell::Target {
-                    let pos = Vec2 { i: i as i8, j: j as i8 };
-                    if !game_state.environment.has_box_at(&pos) {
-                        return false;
-                    }
-                }
-            }
-        }
-        true
+        self.get_won_check_helper().is_won(&game_state.environment)
     }
 
     pub fn count_boxes_on_goals(&self, environment: &GameStateEnvironment) -> usize {
@@ -93,22 +166,78 @@ impl SharedGameState {
     }
 
     pub fn reachable_positions(&self, game_state: &GameState) -> Vec<Vec2> {
-        let mut reachable = Vec::<Vec2>::new();
-        self.visit_all_reachable_position(game_state, |pos| {
-            reachable.push((*pos).into());
-        });
-        reachable
+        let boxes = self.box_board(&game_state.environment);
+        self.reachable_board(game_state.player, &boxes)
+            .iter_indexes()
+            .map(|index| self.layout.position_of(index))
+            .collect()
     }
 
     pub fn min_reachable_position(&self, game_state: &GameState) -> Vec2 {
-        let mut min_reachable = Vec2 { i: i8::MAX, j: i8::MAX };
-        self.visit_all_reachable_position(game_state, |&pos| {
-            let pos: Vec2 = pos.into();
-            if pos < min_reachable {
-                min_reachable = pos;
+        let boxes = self.box_board(&game_state.environment);
+        let reachable = self.reachable_board(game_state.player, &boxes);
+        // bit indexes are laid out row by row, so the lowest index is also the lowest position
+        let first = reachable.first_index().expect("the player always reaches their own cell");
+        self.layout.position_of(first)
+    }
+
+    /// Shortest walk for the player to `to` without pushing any box, as the directions to move in
+    pub fn walk_path(&self, game_state: &GameState, to: Vec2) -> Option<Vec<Direction>> {
+        let mut came_from: HashMap<Vec2, (Vec2, Direction)> = HashMap::new();
+        let mut queue = VecDeque::from([game_state.player]);
+        let size = self.size();
+
+        while let Some(pos) = queue.pop_front() {
+            if pos == to {
+                let mut path = Vec::new();
+                let mut current = pos;
+                while let Some(&(previous, direction)) = came_from.get(&current) {
+                    path.push(direction);
+                    current = previous;
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            for direction in Direction::ALL {
+                let next = pos + direction.offset();
+                if !next.inside(&size) ||
+                    !self[next].is_walkable() ||
+                    game_state.environment.has_box_at(&next) ||
+                    next == game_state.player ||
+                    came_from.contains_key(&next) {
+                    continue;
+                }
+                came_from.insert(next, (pos, direction));
+                queue.push_back(next);
+            }
+        }
+
+        None
+    }
+
+    /// Number of steps for the player to walk to each cell without pushing any box,
+    /// None where the player cannot walk
+    pub fn walk_distances(&self, game_state: &GameState) -> BoundedGrid<Option<u16>> {
+        let mut distances = BoundedGrid::<Option<u16>>::new(self.bounds(), None);
+        let start: IVec2 = game_state.player.into();
+        distances[&start] = Some(0);
+        let mut queue = VecDeque::from([start]);
+
+        while let Some(pos) = queue.pop_front() {
+            let next_distance = distances[&pos].map(|d| d + 1);
+            for next in pos.neighbors() {
+                if distances.get(&next) != Some(&None) ||
+                    !self[next].is_walkable() ||
+                    game_state.environment.has_box_at(&next.into()) {
+                    continue;
+                }
+                distances[&next] = next_distance;
+                queue.push_back(next);
             }
-        });
-        min_reachable
+        }
+
+        distances
     }
 
     pub fn reachable_positions_visitation(&self, game_state: &GameState) -> BoundedGrid<VisitationState> {
@@ -192,6 +321,34 @@ impl UserAction {
     }
 }
 
+impl Direction {
+    pub const ALL: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+    pub fn offset(&self) -> Vec2 {
+        match self {
+            Direction::Up => Vec2 { i: -1, j: 0 },
+            Direction::Down => Vec2 { i: 1, j: 0 },
+            Direction::Left => Vec2 { i: 0, j: -1 },
+            Direction::Right => Vec2 { i: 0, j: 1 },
+        }
+    }
+
+    pub fn from_offset(offset: Vec2) -> Option<Direction> {
+        Direction::ALL.into_iter().find(|d| d.offset() == offset)
+    }
+
+    /// The move in LURD notation: lowercase for a walk, uppercase for a push
+    pub fn lurd_char(&self, push: bool) -> char {
+        let c = match self {
+            Direction::Up => 'u',
+            Direction::Down => 'd',
+            Direction::Left => 'l',
+            Direction::Right => 'r',
+        };
+        if push { c.to_ascii_uppercase() } else { c }
+    }
+}
+
 impl Default for Vec2 {
     fn default() -> Self {
         Vec2 { i: 0, j: 0 }
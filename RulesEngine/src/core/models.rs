@@ -1,4 +1,7 @@
+use crate::core::bitboard::{BitBoard, BitBoardLayout};
+use crate::core::push_distances::PushDistances;
 use crate::core::game_state_environment::GameStateEnvironment;
+use crate::core::symmetry::Symmetry;
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Cell {
@@ -18,6 +21,7 @@ pub struct Vec2 {
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[derive(PartialOrd, Ord)]
 pub enum Direction {
     Up,
     Down,
@@ -33,6 +37,14 @@ pub enum UserAction {
 #[derive(Clone)]
 pub struct SharedGameState {
     pub grid: Vec<Vec<Cell>>,
+    // precomputed from the grid, for the search hot path
+    pub(crate) layout: BitBoardLayout,
+    pub(crate) walkable: BitBoard,
+    pub(crate) push_distances: PushDistances,
+    /// Floor cells a lone box can never be pushed from onto any target
+    pub(crate) dead_squares: BitBoard,
+    /// Mirrors and rotations mapping the grid onto itself, besides the identity
+    pub(crate) symmetries: Vec<Symmetry>,
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
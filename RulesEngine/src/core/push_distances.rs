@@ -0,0 +1,89 @@
+use std::collections::VecDeque;
+use crate::core::Cell;
+use crate::core::models::Vec2;
+
+/// For every target, the fewest pushes a lone box needs to reach it from each cell, on the level
+/// without any other boxes. The player is assumed to reach any side of the box, so these are
+/// lower bounds on the real push counts.
+#[derive(Clone, Debug)]
+pub struct PushDistances {
+    width: usize,
+    targets: Vec<Vec2>,
+    /// One grid per target, indexed by `i * width + j`. u16::MAX where the target cannot be reached.
+    distances: Vec<Vec<u16>>,
+}
+
+const UNREACHABLE: u16 = u16::MAX;
+
+const PUSH_DIRECTIONS: [Vec2; 4] = [
+    Vec2 { i: -1, j: 0 },
+    Vec2 { i: 1, j: 0 },
+    Vec2 { i: 0, j: -1 },
+    Vec2 { i: 0, j: 1 },
+];
+
+impl PushDistances {
+    pub fn new(grid: &[Vec<Cell>]) -> PushDistances {
+        let height = grid.len();
+        let width = grid.first().map(|row| row.len()).unwrap_or(0);
+        let is_floor = |pos: Vec2| {
+            pos.i >= 0 && pos.j >= 0 && (pos.i as usize) < height && (pos.j as usize) < width &&
+                grid[pos.i as usize][pos.j as usize].is_walkable()
+        };
+
+        let mut targets = Vec::new();
+        for (i, row) in grid.iter().enumerate() {
+            for (j, &c) in row.iter().enumerate() {
+                if c == Cell::Target {
+                    targets.push(Vec2 { i: i as i8, j: j as i8 });
+                }
+            }
+        }
+
+        // search backwards from each target, pulling the box away from it
+        let distances = targets.iter().map(|&target| {
+            let mut distance = vec![UNREACHABLE; width * height];
+            distance[target.i as usize * width + target.j as usize] = 0;
+            let mut queue = VecDeque::from([target]);
+            while let Some(pos) = queue.pop_front() {
+                let next_distance = distance[pos.i as usize * width + pos.j as usize] + 1;
+                for dir in PUSH_DIRECTIONS {
+                    // a push in `dir` moves the box from `from` to `pos`, with the player behind it
+                    let from = pos - dir;
+                    let player = from - dir;
+                    if !is_floor(from) || !is_floor(player) {
+                        continue;
+                    }
+                    let index = from.i as usize * width + from.j as usize;
+                    if distance[index] != UNREACHABLE {
+                        continue;
+                    }
+                    distance[index] = next_distance;
+                    queue.push_back(from);
+                }
+            }
+            distance
+        }).collect();
+
+        PushDistances {
+            width,
+            targets,
+            distances,
+        }
+    }
+
+    pub fn targets(&self) -> &[Vec2] {
+        &self.targets
+    }
+
+    /// Fewest pushes to move a lone box at `from` onto the target at `target_index`
+    pub fn distance(&self, target_index: usize, from: Vec2) -> Option<u16> {
+        let distance = self.distances[target_index][from.i as usize * self.width + from.j as usize];
+        (distance != UNREACHABLE).then_some(distance)
+    }
+
+    /// Whether a lone box at `from` can reach any target
+    pub fn reaches_any_target(&self, from: Vec2) -> bool {
+        (0..self.targets.len()).any(|t| self.distance(t, from).is_some())
+    }
+}
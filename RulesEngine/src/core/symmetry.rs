@@ -0,0 +1,124 @@
+use std::collections::HashSet;
+use crate::core::{Cell, Direction, Vec2};
+
+/// A mirror or rotation of the level grid, one of the eight symmetries of a square
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Symmetry {
+    Identity,
+    /// Upside down
+    MirrorRows,
+    /// Left to right
+    MirrorColumns,
+    Rotate180,
+    /// Mirrors along the main diagonal, only for square grids
+    Transpose,
+    /// Mirrors along the other diagonal, only for square grids
+    AntiTranspose,
+    /// Clockwise, only for square grids
+    Rotate90,
+    /// Counterclockwise, only for square grids
+    Rotate270,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::MirrorRows,
+        Symmetry::MirrorColumns,
+        Symmetry::Rotate180,
+        Symmetry::Transpose,
+        Symmetry::AntiTranspose,
+        Symmetry::Rotate90,
+        Symmetry::Rotate270,
+    ];
+
+    /// Whether the symmetry swaps rows and columns, so only fits square grids
+    pub fn swaps_axes(self) -> bool {
+        matches!(self, Symmetry::Transpose | Symmetry::AntiTranspose | Symmetry::Rotate90 | Symmetry::Rotate270)
+    }
+
+    pub fn inverse(self) -> Symmetry {
+        match self {
+            Symmetry::Rotate90 => Symmetry::Rotate270,
+            Symmetry::Rotate270 => Symmetry::Rotate90,
+            other => other,
+        }
+    }
+
+    /// Where `position` ends up in a grid of `size`
+    pub fn apply(self, position: Vec2, size: Vec2) -> Vec2 {
+        let Vec2 { i, j } = position;
+        let (last_i, last_j) = (size.i - 1, size.j - 1);
+        match self {
+            Symmetry::Identity => Vec2 { i, j },
+            Symmetry::MirrorRows => Vec2 { i: last_i - i, j },
+            Symmetry::MirrorColumns => Vec2 { i, j: last_j - j },
+            Symmetry::Rotate180 => Vec2 { i: last_i - i, j: last_j - j },
+            Symmetry::Transpose => Vec2 { i: j, j: i },
+            Symmetry::AntiTranspose => Vec2 { i: last_j - j, j: last_i - i },
+            Symmetry::Rotate90 => Vec2 { i: j, j: last_i - i },
+            Symmetry::Rotate270 => Vec2 { i: last_j - j, j: i },
+        }
+    }
+
+    pub fn apply_direction(self, direction: Direction) -> Direction {
+        let Vec2 { i, j } = direction.offset();
+        let offset = match self {
+            Symmetry::Identity => Vec2 { i, j },
+            Symmetry::MirrorRows => Vec2 { i: -i, j },
+            Symmetry::MirrorColumns => Vec2 { i, j: -j },
+            Symmetry::Rotate180 => Vec2 { i: -i, j: -j },
+            Symmetry::Transpose => Vec2 { i: j, j: i },
+            Symmetry::AntiTranspose => Vec2 { i: -j, j: -i },
+            Symmetry::Rotate90 => Vec2 { i: j, j: -i },
+            Symmetry::Rotate270 => Vec2 { i: -j, j: i },
+        };
+        Direction::from_offset(offset).expect("symmetries map unit offsets onto unit offsets")
+    }
+}
+
+/// Every symmetry other than the identity which maps the grid onto itself, walls and targets
+/// included. Only the walkable cells connected to a target count, the player pushes nowhere
+/// else, so sealed pockets and the floor padding ragged levels get do not break a symmetry.
+pub fn detect_symmetries(grid: &[Vec<Cell>]) -> Vec<Symmetry> {
+    let size = Vec2 {
+        i: grid.len() as i8,
+        j: grid.first().map_or(0, |row| row.len()) as i8,
+    };
+    let cell_at = |position: Vec2| grid.get(position.i as usize)
+        .and_then(|row| row.get(position.j as usize))
+        .copied()
+        .unwrap_or(Cell::Wall);
+    let played = played_cells(grid, size);
+
+    Symmetry::ALL.into_iter()
+        .filter(|&symmetry| symmetry != Symmetry::Identity)
+        .filter(|symmetry| !symmetry.swaps_axes() || size.i == size.j)
+        .filter(|&symmetry| played.iter().all(|&position| {
+            let mapped = symmetry.apply(position, size);
+            played.contains(&mapped) && cell_at(position) == cell_at(mapped)
+        }))
+        .collect()
+}
+
+/// Walkable cells connected to a target
+fn played_cells(grid: &[Vec<Cell>], size: Vec2) -> HashSet<Vec2> {
+    let walkable = |position: Vec2| position.i >= 0 && position.j >= 0
+        && grid.get(position.i as usize)
+            .and_then(|row| row.get(position.j as usize))
+            .is_some_and(|cell| cell.is_walkable());
+    let mut played: HashSet<Vec2> = (0..size.i)
+        .flat_map(|i| (0..size.j).map(move |j| Vec2 { i, j }))
+        .filter(|position| grid[position.i as usize][position.j as usize] == Cell::Target)
+        .collect();
+    let mut queue: Vec<Vec2> = played.iter().copied().collect();
+    while let Some(position) = queue.pop() {
+        for direction in [Direction::Up, Direction::Down, Direction::Left, Direction::Right] {
+            let next = position + direction.offset();
+            if walkable(next) && played.insert(next) {
+                queue.push(next);
+            }
+        }
+    }
+    played
+}
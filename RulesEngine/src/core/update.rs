@@ -1,57 +1,54 @@
-use std::hint::black_box;
-use crate::core::Cell::{Wall};
-use crate::core::{Direction, GameChangeType, GameState, GameStateEnvironment, GameUpdate, SharedGameState, UserAction, DEDUPLICATE_BOXES};
-use crate::core::models::Vec2;
+use crate::core::bitboard::BitBoard;
+use crate::core::{ExplorationOptions, GameChangeType, GameState, GameUpdate, SharedGameState, UserAction};
 
 pub fn step(
     shared: &SharedGameState,
     game: &GameState,
     action: UserAction) -> GameUpdate {
-    let h = shared.height();
-    let w = shared.width();
+    let boxes = shared.box_board(&game.environment);
+    step_with_box_board(shared, game, &boxes, action, &ExplorationOptions::default())
+}
+
+/// Same as [`step`], but takes the boxes of `game` as an already built board. Callers stepping the
+/// same environment many times can build the board once.
+pub fn step_with_box_board(
+    shared: &SharedGameState,
+    game: &GameState,
+    boxes: &BitBoard,
+    action: UserAction,
+    options: &ExplorationOptions) -> GameUpdate {
+    let layout = shared.bit_layout();
+    let walkable = shared.walkable_board();
 
     let dir = match action {
-        UserAction::Move(d) => vec_from_dir(d),
+        UserAction::Move(d) => d.offset(),
     };
 
-    let ni = game.player.i + dir.i;
-    let nj = game.player.j + dir.j;
-    let dest_pos = Vec2 {
-        i: ni,
-        j: nj,
-    };
-    if ni < 0 || nj < 0 || ni >= h || nj >= w {
+    let dest_pos = game.player + dir;
+    let Some(dest) = layout.index_of(dest_pos) else {
         return GameUpdate::Error("Cannot move out of bounds".to_string());
-    }
-
-    let dest = shared.grid[ni as usize][nj as usize];
+    };
 
-    let pushing = game.environment.index_of_box_at(&dest_pos);
+    let pushing = boxes.contains(dest);
     let mut new_environment = game.environment.clone();
-    if let Some(pushed_box_index) = pushing {
-        let bi = ni + dir.i;
-        let bj = nj + dir.j;
-        let new_box_pos = Vec2{
-            i: bi,
-            j: bj,
-        };
-        if bi < 0 || bj < 0 || bi >= h || bj >= w {
+    if pushing {
+        let new_box_pos = dest_pos + dir;
+        let Some(beyond) = layout.index_of(new_box_pos) else {
             return GameUpdate::Error("Cannot push block out of bounds".to_string());
-        }
-        let beyond = shared.grid[bi as usize][bj as usize];
-        if beyond == Wall {
+        };
+        if !walkable.contains(beyond) {
             return GameUpdate::Error("Cannot push block into wall".to_string());
         }
-        if game.environment.has_box_at(&new_box_pos) {
+        if boxes.contains(beyond) {
             return GameUpdate::Error("Cannot push block into another block".to_string());
         }
 
+        let pushed_box_index = game.environment.index_of_box_at(&dest_pos)
+            .expect("box board must match the environment");
         new_environment.set_box(pushed_box_index, &new_box_pos);
-        new_environment.complete_moves();
-    } else {
-        if dest == Wall {
-            return GameUpdate::Error("Cannot walk into a wall".to_string());
-        }
+        new_environment.complete_moves(options);
+    } else if !walkable.contains(dest) {
+        return GameUpdate::Error("Cannot walk into a wall".to_string());
     }
 
     GameUpdate::NextState(
@@ -59,19 +56,10 @@ pub fn step(
             player: dest_pos,
             environment: new_environment,
         },
-        if pushing.is_some() {
+        if pushing {
             GameChangeType::PlayerAndBoxMove
         } else {
             GameChangeType::PlayerMove
         },
     )
 }
-
-fn vec_from_dir(dir: Direction) -> Vec2 {
-    match dir {
-        Direction::Up => Vec2 { i: -1, j: 0 },
-        Direction::Down => Vec2 { i: 1, j: 0 },
-        Direction::Left => Vec2 { i: 0, j: -1 },
-        Direction::Right => Vec2 { i: 0, j: 1 },
-    }
-}
@@ -0,0 +1,118 @@
+use std::path::Path;
+
+/// A single level read from a level file, along with any metadata headers attached to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LevelDefinition {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    /// The raw map, in the same format accepted by [`crate::console_interface::parse_level`]
+    pub map: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LevelSelector {
+    /// 0-based position of the level in the file
+    Index(usize),
+    Title(String),
+}
+
+impl LevelSelector {
+    /// Parses a command line selector. Numbers are treated as 1-based positions in the file,
+    /// as that is how level collections are conventionally numbered. Anything else is a title.
+    pub fn parse(s: &str) -> LevelSelector {
+        match s.trim().parse::<usize>() {
+            Ok(position) if position > 0 => LevelSelector::Index(position - 1),
+            _ => LevelSelector::Title(s.trim().to_string()),
+        }
+    }
+}
+
+/// Parses a level file containing one or more levels.
+///
+/// Levels are separated by blank lines. Lines of the form `Key: value` are metadata headers,
+/// and lines starting with `;` are comments. Headers written in the same block as a map belong
+/// to that map, headers in a block of their own belong to the next map in the file.
+pub fn parse_level_collection(s: &str) -> Vec<LevelDefinition> {
+    let mut levels = Vec::new();
+    let mut pending = LevelDefinition::empty();
+    let mut map_lines: Vec<&str> = Vec::new();
+
+    for line in s.lines().chain(std::iter::once("")) {
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.trim().is_empty() {
+            if !map_lines.is_empty() {
+                pending.map = map_lines.join("\n");
+                levels.push(std::mem::replace(&mut pending, LevelDefinition::empty()));
+                map_lines.clear();
+            }
+            continue;
+        }
+
+        if is_map_line(line) {
+            map_lines.push(line);
+            continue;
+        }
+
+        let line = line.trim();
+        if line.starts_with(';') {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            let value = value.trim().to_string();
+            match key.trim().to_ascii_lowercase().as_str() {
+                "title" => pending.title = Some(value),
+                "author" => pending.author = Some(value),
+                _ => {}
+            }
+        }
+    }
+
+    levels
+}
+
+fn is_map_line(line: &str) -> bool {
+    line.contains('#') && line.chars().all(|c| matches!(c, '#' | '@' | '+' | '$' | '*' | '.' | ' ' | '-' | '_'))
+}
+
+impl LevelDefinition {
+    fn empty() -> LevelDefinition {
+        LevelDefinition {
+            title: None,
+            author: None,
+            map: String::new(),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match (&self.title, &self.author) {
+            (Some(title), Some(author)) => format!("'{}' by {}", title, author),
+            (Some(title), None) => format!("'{}'", title),
+            (None, Some(author)) => format!("untitled level by {}", author),
+            (None, None) => "untitled level".to_string(),
+        }
+    }
+}
+
+pub fn select_level(levels: Vec<LevelDefinition>, selector: &LevelSelector) -> Result<LevelDefinition, String> {
+    let total = levels.len();
+    let found = match selector {
+        LevelSelector::Index(index) => levels.into_iter().nth(*index),
+        LevelSelector::Title(title) => levels.into_iter()
+            .find(|level| level.title.as_deref().is_some_and(|t| t.eq_ignore_ascii_case(title))),
+    };
+    found.ok_or_else(|| match selector {
+        LevelSelector::Index(index) => format!("Level {} not found, file contains {} levels", index + 1, total),
+        LevelSelector::Title(title) => format!("No level titled '{}' found among {} levels", title, total),
+    })
+}
+
+/// Reads a level file, picking a level by the selector. Without a selector the first level is used.
+pub fn load_level_file(path: &Path, selector: Option<&LevelSelector>) -> Result<LevelDefinition, String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Could not read level file {}: {}", path.display(), e))?;
+    let levels = parse_level_collection(&contents);
+    if levels.is_empty() {
+        return Err(format!("No levels found in {}", path.display()));
+    }
+    select_level(levels, selector.unwrap_or(&LevelSelector::Index(0)))
+}
@@ -3,4 +3,6 @@ mod models;
 pub mod bevy_interface;
 pub mod core;
 pub mod state_graph;
+pub mod level_collection;
+pub mod solver;
 mod test;
\ No newline at end of file
@@ -1,5 +1,20 @@
 // Simple CLI Sokoban with ratatui
 // Controls: W/A/S/D or arrow keys (immediate response). Q to quit.
+// Usage: <interactive|graph|view|solve|check|audit|estimate> [level file] [level index or title] [graph file] [--resume]
+// 'graph' saves the populated graph to the graph file, 'view' loads it instead of populating again.
+// 'solve' prints a solution with the fewest pushes, and one with the fewest moves.
+// 'check' searches for a solution without building the state graph, using A*, IDA* with --ida, or with --bidirectional
+// pushing from the start and pulling from the won states until the two meet.
+// Exploring and searching give up after --max-nodes=<count> nodes or --max-seconds=<seconds>.
+// Exploring also stops at --max-edges=<count> edges or --max-memory-mb=<megabytes>, and 'graph' then shows the partial graph.
+// 'audit' checks the deadlock detectors against the fully explored graph, reporting any winnable state they prune.
+// 'estimate' predicts how many states 'graph' would explore, from --probes=<count> random probes seeded by --seed=<seed>.
+// --detectors=<names> picks the deadlock detectors pruning the search, comma separated, 'all' or 'none'. 'dead-squares,freeze,trapped' by default.
+// --search-order=<bfs|dfs|best|random:<seed>>, --deduplicate-boxes=<true|false> and --trim-unwinnable=<true|false> change how the graph is explored.
+// --reduce-symmetry=true stores one state for each group of mirrored or rotated states, on symmetric levels.
+// 'graph' with --reverse pulls boxes back from the won states, then pushes forward only through states that can win.
+// 'graph' with --disk=<directory> keeps the visited states on disk instead, for levels larger than memory.
+// While populating, 'graph' checkpoints next to the graph file. Q stops early, and --resume continues from the checkpoint.
 // Tiles: '#' wall, '@' player, '$' box, '.' target, '*' box on target, '+' player on target, ' ' floor.
 
 mod console_interface;
@@ -8,99 +23,113 @@ mod models;
 mod state_graph;
 mod test;
 mod bevy_interface;
+mod level_collection;
+mod solver;
 
 use crate::console_interface::ConsoleInput::*;
 use crate::console_interface::{
     cleanup_terminal, handle_input, parse_level, render_game, setup_terminal,
 };
-use crate::core::{step, GameState, GameUpdate, SharedGameState, TRIM_UNWINNABLE};
+use crate::core::{step, DeadlockDetectors, ExplorationOptions, GameState, GameUpdate, SharedGameState};
+use crate::level_collection::{load_level_file, LevelSelector};
+use crate::solver::{solve_a_star, solve_bidirectional, solve_ida_star, MatchingBound, SolveOutcome, SolverLimits};
 use crate::models::GameRenderState;
-use crate::state_graph::{get_graph_info, get_json_data, populate_step, render_graph, trim_unwinnable, GraphRenderState, PopulateResult, StateGraph, UniqueNode};
+use crate::state_graph::{add_start, audit_heuristics, estimate_state_count, expand_symmetric_graph, find_move_optimal_solution, get_graph_info, get_json_data, load_state_graph, populate_all, populate_parallel_step, populate_step, populate_winnable, render_graph, push_optimal_solution, save_state_graph, solution_to_lurd, trim_unwinnable, DiskExplorer, GraphInfo, GraphRenderState, NodeId, DEFAULT_PROBES, PopulateResult, StateGraph, UniqueNode};
 use ratatui::Terminal;
 use ratatui::backend::CrosstermBackend;
 use std::io;
 use std::io::Write;
+use std::path::Path;
+
+const DEFAULT_LEVEL_PATH: &str = "levels/default.txt";
+const DEFAULT_GRAPH_PATH: &str = "exports/state_graph.bin";
+const CHECKPOINT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+/// Nodes held in memory by each sorted run of a disk exploration, unless --disk-records is given
+const DEFAULT_DISK_RECORDS: usize = 4_000_000;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let switch = std::env::args().nth(1).unwrap_or("interactive".to_string());
-
-    let level = r#"
-       ####
-########  ##
-#          ###
-# @$$ ##   ..#
-# $$   ##  ..#
-#         ####
-###########
-"#;
-    //         let level = r#"
-    // ########
-    // # @$  .#
-    // # $  $ #
-    // # .# $ #
-    // #..#   #
-    // ########
-    //     "#;
-//     let level = r#"
-//  ########
-//  #  ##  ####
-//  # @$   #  ####
-//  # ##$ $   #  #
-//  # ##  #$ $   ###
-//  # #####  #$ $  #
-//  # #   ####  #$ #
-//  # ##     ####  #
-// ## .##       # ##
-// #.  .##      # #
-// #..  .##     # ##
-// ###   .#######  #
-//   #  # .        #
-//   ###############
-// "#;
-//     let level = r#"
-//    ######
-// ####..$@#
-// #   #..*#
-// #    #* #
-// # $#$ ..#
-// # $ $ $ #
-// #      ##
-// ########
-// "#;
-//     let level = r#"
-//     #####
-// #####@. #
-// #   #.**#
-// #  $ #..#
-// #  #   .#
-// # $$$$ *#
-// #    #  #
-// #########
-// "#;
-//     let level = r#"
-//  ### ###
-// #   #  .#
-// #   # . #
-// ##$     #
-//  # $.* #
-//   # $##
-//    #@#
-//     #
-// "#;
-
-    let (game_state, shared) = parse_level(level);
+    let resume = std::env::args().any(|arg| arg == "--resume");
+    let args: Vec<String> = std::env::args().filter(|arg| !arg.starts_with("--")).collect();
+    let switch = args.get(1).cloned().unwrap_or("interactive".to_string());
+
+    let level_path = args.get(2).cloned().unwrap_or(DEFAULT_LEVEL_PATH.to_string());
+    let level_selector = args.get(3).map(|s| LevelSelector::parse(s));
+    let graph_path = args.get(4).cloned().unwrap_or(DEFAULT_GRAPH_PATH.to_string());
+    let level = load_level_file(Path::new(&level_path), level_selector.as_ref())?;
+    println!("Loaded {} from {}", level.describe(), level_path);
+
+    let (game_state, shared) = parse_level(&level.map)
+        .map_err(|e| format!("Invalid level {}: {}", level.describe(), e))?;
+    let options = exploration_options()?;
+
+    if switch == "view" {
+        return view_saved_graph(&shared, &game_state, Path::new(&graph_path), &options);
+    }
+    if switch == "solve" {
+        return run_solve(&shared, &game_state, &options);
+    }
+    if switch == "audit" {
+        return run_audit(&shared, &game_state, &options.detectors);
+    }
+    if switch == "estimate" {
+        let probes = flag_value("--probes")?.unwrap_or(DEFAULT_PROBES);
+        if probes == 0 {
+            return Err("--probes must be at least 1".into());
+        }
+        let seed = flag_value("--seed")?.unwrap_or(0);
+        print!("{}", estimate_state_count(&shared, &game_state, &options, probes, seed));
+        return Ok(());
+    }
+    if switch == "check" {
+        let limits = SolverLimits {
+            max_nodes_expanded: options.max_nodes,
+            max_time: options.max_time,
+        };
+        let search = if std::env::args().any(|arg| arg == "--ida") {
+            CheckSearch::IdaStar
+        } else if std::env::args().any(|arg| arg == "--bidirectional") {
+            CheckSearch::Bidirectional
+        } else {
+            CheckSearch::AStar
+        };
+        return run_check(&shared, &game_state, &options, limits, search);
+    }
+
+    if switch == "graph" && let Some(directory) = flag_value::<String>("--disk")? {
+        if resume {
+            // the explorer starts by clearing its directory, so there would be nothing left to resume
+            return Err("--resume cannot continue a --disk exploration, it starts over".into());
+        }
+        let records_in_memory = flag_value("--disk-records")?.unwrap_or(DEFAULT_DISK_RECORDS);
+        let mut terminal = setup_terminal()?;
+        return run_disk_graph(&shared, &options, game_state, Path::new(&directory), records_in_memory, &mut terminal);
+    }
+
+    if switch == "graph" && std::env::args().any(|arg| arg == "--reverse") {
+        if resume {
+            return Err("--resume cannot continue a --reverse exploration".into());
+        }
+        return run_reverse_graph(&shared, &options, game_state, Path::new(&graph_path));
+    }
+
+    let graph_start = if switch == "graph" {
+        Some(start_state_graph(&shared, &game_state, &options, Path::new(&graph_path), resume)?)
+    } else {
+        None
+    };
+
     let mut terminal = setup_terminal()?;
 
-    match switch.as_str() {
-        "graph" => {
-            run_state_graph(&shared, game_state, &mut terminal)?;
+    match (switch.as_str(), graph_start) {
+        ("graph", Some((state_graph, first_state_id))) => {
+            run_state_graph(&shared, &options, &game_state, state_graph, first_state_id, Path::new(&graph_path), &mut terminal)?;
         }
-        "interactive" => {
+        ("interactive", _) => {
             run_interactive(&shared, game_state, &mut terminal)?;
         }
         _ => {
             println!(
-                "Unknown mode: {}. Use 'interactive' or 'graph'. defaulting to interactive",
+                "Unknown mode: {}. Use 'interactive', 'graph', 'view', 'solve', 'check', 'audit' or 'estimate'. defaulting to interactive",
                 switch
             );
             run_interactive(&shared, game_state, &mut terminal)?;
@@ -110,14 +139,80 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Parses a `--name=value` flag, None when it is not given
+fn flag_value<T: std::str::FromStr>(name: &str) -> Result<Option<T>, String>
+where
+    T::Err: std::fmt::Display,
+{
+    let prefix = format!("{}=", name);
+    let Some(value) = std::env::args().find_map(|arg| arg.strip_prefix(&prefix).map(str::to_string)) else {
+        return Ok(None);
+    };
+    value.parse().map(Some).map_err(|e| format!("Invalid value for {}: {}: {}", name, value, e))
+}
+
+/// Options from the command line, the defaults for anything not given
+fn exploration_options() -> Result<ExplorationOptions, String> {
+    let defaults = ExplorationOptions::default();
+    Ok(ExplorationOptions {
+        deduplicate_boxes: flag_value("--deduplicate-boxes")?.unwrap_or(defaults.deduplicate_boxes),
+        trim_unwinnable: flag_value("--trim-unwinnable")?.unwrap_or(defaults.trim_unwinnable),
+        detectors: flag_value("--detectors")?.unwrap_or(defaults.detectors),
+        max_nodes: flag_value("--max-nodes")?,
+        max_edges: flag_value("--max-edges")?,
+        max_memory: flag_value::<usize>("--max-memory-mb")?.map(|megabytes| megabytes * 1_000_000),
+        max_time: flag_value("--max-seconds")?.map(std::time::Duration::from_secs),
+        search_order: flag_value("--search-order")?.unwrap_or(defaults.search_order),
+        threads: flag_value("--threads")?.unwrap_or(defaults.threads),
+        reduce_symmetry: flag_value("--reduce-symmetry")?.unwrap_or(defaults.reduce_symmetry),
+    })
+}
+
+fn print_detector_stats(detectors: &DeadlockDetectors) {
+    if detectors.is_empty() {
+        println!("No deadlock detectors");
+    }
+    for stats in detectors.stats() {
+        println!("{}", stats);
+    }
+}
+
+fn checkpoint_path(graph_path: &Path) -> std::path::PathBuf {
+    graph_path.with_extension("checkpoint")
+}
+
+/// Either a fresh graph holding only the initial state, or the graph from the last checkpoint
+fn start_state_graph(
+    shared: &SharedGameState,
+    game_state: &GameState,
+    options: &ExplorationOptions,
+    graph_path: &Path,
+    resume: bool,
+) -> Result<(StateGraph, NodeId), Box<dyn std::error::Error>> {
+    if resume {
+        let checkpoint_path = checkpoint_path(graph_path);
+        let saved = load_state_graph(&checkpoint_path, shared, game_state)
+            .map_err(|e| format!("Could not resume from {}: {}", checkpoint_path.display(), e))?;
+        println!("Resuming from {}", checkpoint_path.display());
+        return Ok((saved.graph, saved.initial_id));
+    }
+
+    let mut state_graph = StateGraph::new();
+    let first_state_id = add_start(&mut state_graph, shared, game_state, options);
+    Ok((state_graph, first_state_id))
+}
+
 fn run_state_graph(
     shared: &SharedGameState,
-    game_state: GameState,
+    options: &ExplorationOptions,
+    game_state: &GameState,
+    mut state_graph: StateGraph,
+    first_state_id: NodeId,
+    graph_path: &Path,
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let mut state_graph = StateGraph::new();
-    let first_node = UniqueNode::from_game_state(game_state, shared);
-    let first_state_id = state_graph.upsert_state(first_node);
+    let checkpoint_path = checkpoint_path(graph_path);
+    let mut last_checkpoint_time = std::time::Instant::now();
 
     let start_time = std::time::Instant::now();
     let mut last_render_time = start_time;
@@ -130,7 +225,8 @@ fn run_state_graph(
         .open("exports/solve_log.log")?;
 
     render_graph(terminal, &mut log_out, GraphRenderState {
-        graph: &state_graph,
+        graph: GraphInfo::new(&state_graph),
+        disk: None,
         processed_since_last_render,
         start_time,
         last_render_time,
@@ -139,16 +235,28 @@ fn run_state_graph(
 
     'outer: loop {
         let stop_time = std::time::Instant::now() + std::time::Duration::from_millis(1000);
+        let mut exhausted = None;
         while std::time::Instant::now() < stop_time {
-            let PopulateResult::Populated = populate_step(&mut state_graph, shared) else {
+            exhausted = state_graph.exhausted_budget(options, start_time.elapsed());
+            if exhausted.is_some() {
+                break;
+            }
+            let visited_before = state_graph.nodes.len() - state_graph.unvisited.len();
+            let result = if options.threads > 1 {
+                populate_parallel_step(&mut state_graph, shared, options)
+            } else {
+                populate_step(&mut state_graph, shared, options)
+            };
+            let PopulateResult::Populated = result else {
                 break 'outer;
             };
-            processed_since_last_render += 1;
+            processed_since_last_render += state_graph.nodes.len() - state_graph.unvisited.len() - visited_before;
         }
 
         let current_time = std::time::Instant::now();
         render_graph(terminal, &mut log_out, GraphRenderState {
-            graph: &state_graph,
+            graph: GraphInfo::new(&state_graph),
+            disk: None,
             processed_since_last_render,
             start_time,
             last_render_time,
@@ -157,12 +265,167 @@ fn run_state_graph(
 
         last_render_time = current_time;
         processed_since_last_render = 0;
+
+        if let Some(budget) = exhausted {
+            // the partial graph is still worth looking at, with its frontier left unexpanded
+            state_graph.compact_edges();
+            save_state_graph(&checkpoint_path, &state_graph, shared, first_state_id)?;
+            cleanup_terminal()?;
+            print_detector_stats(&options.detectors);
+            println!("Exploration {} budget used up. Checkpoint saved to {}, continue with --resume", budget, checkpoint_path.display());
+            return analyze_and_visualize(first_state_id, state_graph, shared, game_state, options);
+        }
+        if matches!(handle_input(), Ok(Quit)) {
+            save_state_graph(&checkpoint_path, &state_graph, shared, first_state_id)?;
+            cleanup_terminal()?;
+            print_detector_stats(&options.detectors);
+            println!("Stopped early. Checkpoint saved to {}, continue with --resume", checkpoint_path.display());
+            return Ok(());
+        }
+
+        if current_time - last_checkpoint_time >= CHECKPOINT_INTERVAL {
+            save_state_graph(&checkpoint_path, &state_graph, shared, first_state_id)?;
+            last_checkpoint_time = std::time::Instant::now();
+        }
     }
 
     cleanup_terminal()?;
+    print_detector_stats(&options.detectors);
 
+    // saved before trimming, so the file holds the complete graph
+    save_state_graph(graph_path, &state_graph, shared, first_state_id)?;
+    println!("State graph saved to {}", graph_path.display());
+    // the complete graph supersedes the checkpoint
+    let _ = std::fs::remove_file(&checkpoint_path);
+
+    analyze_and_visualize(first_state_id, state_graph, shared, game_state, options)
+}
+
+/// Finds the winnable states by pulling back from the won states, skipping every unwinnable state
+fn run_reverse_graph(
+    shared: &SharedGameState,
+    options: &ExplorationOptions,
+    game_state: GameState,
+    graph_path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state_graph = StateGraph::new();
+    let first_state_id = add_start(&mut state_graph, shared, &game_state, options);
+
+    let start_time = std::time::Instant::now();
+    if let PopulateResult::BudgetExhausted(budget) = populate_winnable(&mut state_graph, shared, options) {
+        return Err(format!("Exploration {} budget used up while pulling back from the won states", budget).into());
+    }
+    println!("Populated {} winnable nodes in {:?}", state_graph.nodes.len(), start_time.elapsed());
+    print_detector_stats(&options.detectors);
+    if state_graph.nodes.is_empty() {
+        println!("The level cannot be won");
+        return Ok(());
+    }
+
+    save_state_graph(graph_path, &state_graph, shared, first_state_id)?;
+    println!("State graph saved to {}", graph_path.display());
+    analyze_and_visualize(first_state_id, state_graph, shared, &game_state, options)
+}
+
+/// Explores with the visited set on disk, for levels with more states than fit in memory.
+/// Leaves the edge list in `directory`, the graph is too large to visualize.
+fn run_disk_graph(
+    shared: &SharedGameState,
+    options: &ExplorationOptions,
+    game_state: GameState,
+    directory: &Path,
+    records_in_memory: usize,
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let first_node = UniqueNode::from_game_state(game_state, shared);
+    let mut explorer = DiskExplorer::new(directory, shared, first_node, records_in_memory)?;
+
+    let start_time = std::time::Instant::now();
+    let mut last_render_time = start_time;
+    let mut expanded_at_last_render = 0;
+
+    let mut log_out = std::fs::OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .create(true)
+        .open("exports/solve_log.log")?;
+
+    let mut stopped = None;
+    loop {
+        let stop_time = std::time::Instant::now() + std::time::Duration::from_millis(1000);
+        let mut all_visited = false;
+        let layer = explorer.stats().layer;
+        while std::time::Instant::now() < stop_time {
+            if let PopulateResult::AllVisited = explorer.populate_step(options)? {
+                all_visited = true;
+                break;
+            }
+            if explorer.stats().layer != layer {
+                break;
+            }
+        }
+
+        let stats = explorer.stats();
+        let current_time = std::time::Instant::now();
+        render_graph(terminal, &mut log_out, GraphRenderState {
+            graph: GraphInfo::from_disk(&stats),
+            disk: Some(&stats),
+            processed_since_last_render: (stats.expanded - expanded_at_last_render) as usize,
+            start_time,
+            last_render_time,
+            current_time,
+        })?;
+        last_render_time = current_time;
+        expanded_at_last_render = stats.expanded;
+
+        if all_visited {
+            break;
+        }
+        // limits apply between layers, where the exploration can be loaded as a graph
+        if stats.layer != layer && explorer.exhausted_budget(options, start_time.elapsed()).is_some() {
+            stopped = Some("Exploration budget used up");
+            break;
+        }
+        if matches!(handle_input(), Ok(Quit)) {
+            stopped = Some("Stopped early");
+            break;
+        }
+    }
+
+    cleanup_terminal()?;
+    print_detector_stats(&options.detectors);
+    let stats = explorer.stats();
+    if let Some(reason) = stopped {
+        println!("{} at push depth {}", reason, stats.layer);
+    }
+    println!("Explored {} nodes and {} edges in {:?}, reading {} and writing {} bytes",
+             stats.nodes, stats.edges, start_time.elapsed(), stats.bytes_read, stats.bytes_written);
+    println!("Edge list saved to {}", explorer.edge_list_path().display());
+    Ok(())
+}
+
+fn view_saved_graph(
+    shared: &SharedGameState,
+    game_state: &GameState,
+    graph_path: &Path,
+    options: &ExplorationOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let saved = load_state_graph(graph_path, shared, game_state)
+        .map_err(|e| format!("Could not load {}: {}", graph_path.display(), e))?;
+    println!("State graph loaded from {}", graph_path.display());
+
+    analyze_and_visualize(saved.initial_id, saved.graph, &saved.shared, game_state, options)
+}
+
+fn analyze_and_visualize(
+    first_state_id: NodeId,
+    mut state_graph: StateGraph,
+    shared: &SharedGameState,
+    game_state: &GameState,
+    options: &ExplorationOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
     println!("{}", get_graph_info(&state_graph));
-    if TRIM_UNWINNABLE {
+    if options.trim_unwinnable {
         let trimmed_stats = trim_unwinnable(&mut state_graph, shared);
         println!("Trimmed to only winnable states: {:?}", trimmed_stats  );
         println!("Trimmed {} ({:.1}%) nodes  and {} ({:.1}%) edges",
@@ -170,6 +433,7 @@ fn run_state_graph(
                  trimmed_stats.edges_removed(), trimmed_stats.edges_removed_percentage());
         println!("{}", get_graph_info(&state_graph));
     }
+    let (first_state_id, state_graph) = expand_symmetries(first_state_id, state_graph, shared, game_state, options);
 
 
     // let json_data = get_json_data(&state_graph, shared);
@@ -191,6 +455,114 @@ fn run_state_graph(
     Ok(())
 }
 
+fn run_solve(
+    shared: &SharedGameState,
+    game_state: &GameState,
+    options: &ExplorationOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state_graph = StateGraph::new();
+    let first_state_id = add_start(&mut state_graph, shared, game_state, options);
+    let complete = matches!(populate_all(&mut state_graph, shared, options), PopulateResult::AllVisited);
+    println!("{}", get_graph_info(&state_graph));
+    print_detector_stats(&options.detectors);
+    if !complete {
+        println!("Exploration limit reached, solutions may be missing or longer than optimal");
+    }
+
+    let (first_state_id, state_graph) = expand_symmetries(first_state_id, state_graph, shared, game_state, options);
+
+    let Some(push_optimal) = push_optimal_solution(&state_graph, shared, game_state, first_state_id) else {
+        println!("No solution found");
+        return Ok(());
+    };
+    println!("Push-optimal: {} pushes and {} moves:", push_optimal.push_count(), push_optimal.move_count());
+    println!("{}", push_optimal.moves);
+
+    let move_optimal = find_move_optimal_solution(&state_graph, shared, game_state, first_state_id)
+        .ok_or("No move-optimal solution found, but a push-optimal solution exists")?;
+    println!("Move-optimal: {} pushes and {} moves:", move_optimal.push_count(), move_optimal.move_count());
+    println!("{}", move_optimal.moves);
+
+    Ok(())
+}
+
+/// The full graph of a graph explored with symmetry reduction, see [`StateGraph::reduce_symmetry`],
+/// the same graph otherwise. The graph holds the representative of `game_state`, the full graph
+/// starts from `game_state` itself.
+fn expand_symmetries(
+    first_state_id: NodeId,
+    state_graph: StateGraph,
+    shared: &SharedGameState,
+    game_state: &GameState,
+    options: &ExplorationOptions,
+) -> (NodeId, StateGraph) {
+    if !state_graph.reduce_symmetry || state_graph.get_state(first_state_id).is_none() {
+        return (first_state_id, state_graph);
+    }
+    let first_node = UniqueNode::from_game_state(game_state.clone(), shared);
+    let expanded = expand_symmetric_graph(&state_graph, shared, &first_node, options);
+    println!("Expanded {} symmetries: {} nodes and {} edges from {} nodes and {} edges",
+             shared.symmetries().len(), expanded.nodes.len(), expanded.edges.len(),
+             state_graph.nodes.len(), state_graph.edges.len());
+    (0, expanded)
+}
+
+/// The search 'check' runs
+enum CheckSearch {
+    AStar,
+    IdaStar,
+    Bidirectional,
+}
+
+fn run_check(
+    shared: &SharedGameState,
+    game_state: &GameState,
+    options: &ExplorationOptions,
+    limits: SolverLimits,
+    search: CheckSearch,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bound = MatchingBound::new(shared);
+    let result = match search {
+        CheckSearch::AStar => solve_a_star(shared, game_state, &bound, options, limits),
+        CheckSearch::IdaStar => solve_ida_star(shared, game_state, &bound, options, limits),
+        CheckSearch::Bidirectional => solve_bidirectional(shared, game_state, options, limits),
+    };
+
+    println!("{} after expanding {} nodes, generating {}, in {:?}",
+             result.outcome, result.stats.nodes_expanded, result.stats.nodes_generated, result.stats.elapsed);
+    print_detector_stats(&options.detectors);
+    if let SolveOutcome::Solved(nodes) = &result.outcome {
+        let moves = solution_to_lurd(shared, game_state, nodes)
+            .ok_or("Solution could not be expanded into moves")?;
+        println!("{}", moves);
+    }
+
+    Ok(())
+}
+
+fn run_audit(
+    shared: &SharedGameState,
+    game_state: &GameState,
+    detectors: &DeadlockDetectors,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let audit = audit_heuristics(shared, game_state, detectors);
+    print!("{}", audit);
+
+    for detector in audit.detectors.iter().filter(|detector| !detector.is_sound()) {
+        println!("Winnable states {} prunes:", detector.name);
+        for node in detector.false_positives.iter().take(5) {
+            let boxes: Vec<_> = node.environment.iter_boxes().collect();
+            println!("  boxes {:?}, player at {:?}", boxes, node.minimum_reachable_player_position);
+        }
+    }
+
+    if audit.is_sound() {
+        Ok(())
+    } else {
+        Err("Some deadlock detectors prune winnable states".into())
+    }
+}
+
 fn run_interactive(
     shared: &SharedGameState,
     game_state: GameState,
@@ -0,0 +1,102 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::time::Instant;
+use crate::core::{ExplorationOptions, GameState, SharedGameState};
+use crate::solver::{LowerBound, SolveOutcome, SolverLimits, SolverResult, SolverStats};
+use crate::state_graph::{expand_symmetric_path, get_all_adjacent_nodes, UniqueNode};
+
+/// Searches for a push-optimal solution with A*, counting one step per push.
+///
+/// Only the nodes the search touches are kept, unlike a full state graph. That is still every
+/// node generated, so memory grows with the search, see [`crate::solver::solve_ida_star`] for
+/// a search with bounded memory.
+pub fn solve_a_star(
+    shared: &SharedGameState,
+    start: &GameState,
+    bound: &impl LowerBound,
+    options: &ExplorationOptions,
+    limits: SolverLimits,
+) -> SolverResult {
+    let start_time = Instant::now();
+    let win_checker = shared.get_won_check_helper();
+    let mut stats = SolverStats::default();
+
+    let start_node = UniqueNode::from_game_state(start.clone(), shared);
+    let Some(start_estimate) = bound.estimate(&start_node) else {
+        stats.elapsed = start_time.elapsed();
+        return SolverResult { outcome: SolveOutcome::Unsolvable, stats };
+    };
+
+    // every node seen, indexed by the order it was first generated
+    let mut nodes: Vec<UniqueNode> = vec![start_node.clone()];
+    let mut parents: Vec<usize> = vec![0];
+    let mut costs: Vec<u32> = vec![0];
+    let mut closed: Vec<bool> = vec![false];
+    let mut indexes: HashMap<UniqueNode, usize> = HashMap::from([(start_node, 0)]);
+
+    // ties go to the deeper node, which tends to reach a win sooner
+    let mut open = BinaryHeap::new();
+    open.push(Reverse((start_estimate, Reverse(0u32), 0usize)));
+
+    let outcome = loop {
+        let Some(Reverse((_, Reverse(cost), index))) = open.pop() else {
+            break SolveOutcome::Unsolvable;
+        };
+        if closed[index] || cost > costs[index] {
+            continue;
+        }
+        if win_checker.is_won(&nodes[index].environment) {
+            break SolveOutcome::Solved(expand_symmetric_path(shared, &trace_path(&nodes, &parents, index), options));
+        }
+        if let Some(limit) = limits.check(&stats, start_time) {
+            break SolveOutcome::LimitReached(limit);
+        }
+
+        closed[index] = true;
+        stats.nodes_expanded += 1;
+
+        let next_cost = cost + 1;
+        for child in get_all_adjacent_nodes(&nodes[index], shared, options) {
+            stats.nodes_generated += 1;
+            let child_index = match indexes.get(&child) {
+                Some(&existing) => {
+                    if closed[existing] || costs[existing] <= next_cost {
+                        continue;
+                    }
+                    parents[existing] = index;
+                    costs[existing] = next_cost;
+                    existing
+                }
+                None => {
+                    let new_index = nodes.len();
+                    nodes.push(child.clone());
+                    parents.push(index);
+                    costs.push(next_cost);
+                    closed.push(false);
+                    indexes.insert(child, new_index);
+                    new_index
+                }
+            };
+            let Some(estimate) = bound.estimate(&nodes[child_index]) else {
+                // can never be won, so never worth expanding
+                closed[child_index] = true;
+                continue;
+            };
+            open.push(Reverse((next_cost + estimate, Reverse(next_cost), child_index)));
+        }
+    };
+
+    stats.elapsed = start_time.elapsed();
+    SolverResult { outcome, stats }
+}
+
+fn trace_path(nodes: &[UniqueNode], parents: &[usize], end: usize) -> Vec<UniqueNode> {
+    let mut path = vec![nodes[end].clone()];
+    let mut current = end;
+    while current != 0 {
+        current = parents[current];
+        path.push(nodes[current].clone());
+    }
+    path.reverse();
+    path
+}
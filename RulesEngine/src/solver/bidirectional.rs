@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::time::Instant;
+use crate::core::{Budget, ExplorationOptions, GameState, SharedGameState};
+use crate::solver::{LimitReached, SolveOutcome, SolverLimits, SolverResult, SolverStats};
+use crate::state_graph::{expand_symmetric_path, get_all_adjacent_nodes, get_all_pulls, goal_nodes, UniqueNode};
+
+/// Searches for a push-optimal solution by pushing forward from the start and pulling backward
+/// from every won node, one whole layer at a time, until the two searches meet. Always grows the
+/// side with the smaller frontier, so levels with few states near the win are mostly searched
+/// from the win.
+///
+/// Pulling needs no detectors, every pulled node can reach a win. Like
+/// [`crate::solver::solve_a_star`], every node generated is kept.
+pub fn solve_bidirectional(
+    shared: &SharedGameState,
+    start: &GameState,
+    options: &ExplorationOptions,
+    limits: SolverLimits,
+) -> SolverResult {
+    let start_time = Instant::now();
+    let mut stats = SolverStats::default();
+
+    let start_node = UniqueNode::from_game_state(start.clone(), shared);
+    if shared.get_won_check_helper().is_won(&start_node.environment) {
+        stats.elapsed = start_time.elapsed();
+        return SolverResult { outcome: SolveOutcome::Solved(vec![start_node]), stats };
+    }
+
+    let mut forward = SearchSide::new(vec![start_node.clone()]);
+    let goals = match goal_nodes(shared, &start.environment, options) {
+        Ok(goals) => goals,
+        Err(budget) => {
+            stats.elapsed = start_time.elapsed();
+            let limit = if budget == Budget::Time { LimitReached::Time } else { LimitReached::Nodes };
+            return SolverResult { outcome: SolveOutcome::LimitReached(limit), stats };
+        }
+    };
+    let mut backward = SearchSide::new(goals);
+
+    let outcome = loop {
+        if forward.frontier.is_empty() || backward.frontier.is_empty() {
+            break SolveOutcome::Unsolvable;
+        }
+        let grow_forward = forward.frontier.len() <= backward.frontier.len();
+        let (growing, other) = if grow_forward {
+            (&mut forward, &backward)
+        } else {
+            (&mut backward, &forward)
+        };
+
+        let mut meeting: Option<(u32, usize)> = None;
+        let frontier = std::mem::take(&mut growing.frontier);
+        for index in frontier {
+            if let Some(limit) = limits.check(&stats, start_time) {
+                stats.elapsed = start_time.elapsed();
+                return SolverResult { outcome: SolveOutcome::LimitReached(limit), stats };
+            }
+            stats.nodes_expanded += 1;
+
+            let node = growing.nodes[index].clone();
+            let next = if grow_forward {
+                get_all_adjacent_nodes(&node, shared, options)
+            } else {
+                get_all_pulls(&node, shared, options).into_iter().map(|(_, parent)| parent).collect()
+            };
+            for child in next {
+                stats.nodes_generated += 1;
+                let Some(child_index) = growing.insert(child, index) else {
+                    continue;
+                };
+                let Some(&other_index) = other.indexes.get(&growing.nodes[child_index]) else {
+                    continue;
+                };
+                let length = growing.depths[child_index] + other.depths[other_index];
+                if meeting.is_none_or(|(shortest, _)| length < shortest) {
+                    meeting = Some((length, child_index));
+                }
+            }
+        }
+
+        // the whole layer is done, so the shortest meeting in it is a shortest solution
+        if let Some((_, index)) = meeting {
+            let meeting_node = growing.nodes[index].clone();
+            let forward_index = forward.indexes[&meeting_node];
+            let backward_index = backward.indexes[&meeting_node];
+            let mut path = forward.path_to_root(forward_index);
+            path.reverse();
+            path.extend(backward.path_to_root(backward_index).into_iter().skip(1));
+            break SolveOutcome::Solved(expand_symmetric_path(shared, &path, options));
+        }
+    };
+
+    stats.elapsed = start_time.elapsed();
+    SolverResult { outcome, stats }
+}
+
+/// The nodes one direction of the search has generated, indexed by the order they were first
+/// generated. Roots are their own parent.
+struct SearchSide {
+    nodes: Vec<UniqueNode>,
+    parents: Vec<usize>,
+    depths: Vec<u32>,
+    indexes: HashMap<UniqueNode, usize>,
+    /// The deepest layer, expanded next
+    frontier: Vec<usize>,
+}
+
+impl SearchSide {
+    fn new(roots: Vec<UniqueNode>) -> SearchSide {
+        let mut side = SearchSide {
+            nodes: Vec::new(),
+            parents: Vec::new(),
+            depths: Vec::new(),
+            indexes: HashMap::new(),
+            frontier: Vec::new(),
+        };
+        for root in roots {
+            if side.indexes.contains_key(&root) {
+                continue;
+            }
+            let index = side.nodes.len();
+            side.nodes.push(root.clone());
+            side.parents.push(index);
+            side.depths.push(0);
+            side.indexes.insert(root, index);
+            side.frontier.push(index);
+        }
+        side
+    }
+
+    /// Adds `node` to the next layer, None when it was generated before
+    fn insert(&mut self, node: UniqueNode, parent: usize) -> Option<usize> {
+        if self.indexes.contains_key(&node) {
+            return None;
+        }
+        let index = self.nodes.len();
+        self.nodes.push(node.clone());
+        self.parents.push(parent);
+        self.depths.push(self.depths[parent] + 1);
+        self.indexes.insert(node, index);
+        self.frontier.push(index);
+        Some(index)
+    }
+
+    /// From the node at `index` back to the root it was generated from
+    fn path_to_root(&self, index: usize) -> Vec<UniqueNode> {
+        let mut path = vec![self.nodes[index].clone()];
+        let mut current = index;
+        while self.parents[current] != current {
+            current = self.parents[current];
+            path.push(self.nodes[current].clone());
+        }
+        path
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::time::Instant;
+use crate::core::{ExplorationOptions, GameState, SharedGameState, WonCheckHelper};
+use crate::solver::{LimitReached, LowerBound, SolveOutcome, SolverLimits, SolverResult, SolverStats};
+use crate::state_graph::{expand_symmetric_path, get_all_adjacent_nodes, UniqueNode};
+
+/// Upper bound on remembered nodes. Past this IDA* keeps searching, but may revisit nodes.
+const TRANSPOSITION_CAPACITY: usize = 1 << 20;
+
+/// Searches for a push-optimal solution with iterative deepening A*.
+///
+/// Memory stays bounded: only the current path is required, plus a transposition table of at
+/// most [`TRANSPOSITION_CAPACITY`] nodes used to skip nodes already searched this iteration.
+/// The price is repeating work each time the threshold grows.
+pub fn solve_ida_star(
+    shared: &SharedGameState,
+    start: &GameState,
+    bound: &impl LowerBound,
+    options: &ExplorationOptions,
+    limits: SolverLimits,
+) -> SolverResult {
+    let start_node = UniqueNode::from_game_state(start.clone(), shared);
+    let mut search = IdaSearch {
+        shared,
+        bound,
+        options,
+        limits,
+        win_checker: shared.get_won_check_helper(),
+        start_time: Instant::now(),
+        stats: SolverStats::default(),
+        path: vec![],
+        transpositions: HashMap::new(),
+    };
+
+    let outcome = match bound.estimate(&start_node) {
+        None => SolveOutcome::Unsolvable,
+        Some(mut threshold) => loop {
+            search.transpositions.clear();
+            search.path.push(start_node.clone());
+            let step = search.search(0, threshold);
+            match step {
+                Step::Found => break SolveOutcome::Solved(expand_symmetric_path(shared, &search.path, options)),
+                Step::Limit(limit) => break SolveOutcome::LimitReached(limit),
+                Step::Exceeded(None) => break SolveOutcome::Unsolvable,
+                Step::Exceeded(Some(next_threshold)) => {
+                    search.path.clear();
+                    threshold = next_threshold;
+                }
+            }
+        },
+    };
+
+    let mut stats = search.stats;
+    stats.elapsed = search.start_time.elapsed();
+    SolverResult { outcome, stats }
+}
+
+enum Step {
+    Found,
+    /// Nothing found under the threshold. Holds the smallest cost seen over it, if any.
+    Exceeded(Option<u32>),
+    Limit(LimitReached),
+}
+
+struct IdaSearch<'a, B: LowerBound> {
+    shared: &'a SharedGameState,
+    bound: &'a B,
+    options: &'a ExplorationOptions,
+    limits: SolverLimits,
+    win_checker: WonCheckHelper,
+    start_time: Instant,
+    stats: SolverStats,
+    /// From the start node to the node being searched
+    path: Vec<UniqueNode>,
+    /// Lowest cost each node has been searched from, this iteration
+    transpositions: HashMap<UniqueNode, u32>,
+}
+
+impl<B: LowerBound> IdaSearch<'_, B> {
+    fn search(&mut self, cost: u32, threshold: u32) -> Step {
+        let node = self.path.last().expect("path always holds the node being searched");
+        let Some(estimate) = self.bound.estimate(node) else {
+            return Step::Exceeded(None);
+        };
+        if cost + estimate > threshold {
+            return Step::Exceeded(Some(cost + estimate));
+        }
+        if self.win_checker.is_won(&node.environment) {
+            return Step::Found;
+        }
+        if let Some(limit) = self.limits.check(&self.stats, self.start_time) {
+            return Step::Limit(limit);
+        }
+
+        match self.transpositions.get(node) {
+            Some(&seen_cost) if seen_cost <= cost => return Step::Exceeded(None),
+            _ => {
+                if self.transpositions.len() < TRANSPOSITION_CAPACITY || self.transpositions.contains_key(node) {
+                    self.transpositions.insert(node.clone(), cost);
+                }
+            }
+        }
+
+        self.stats.nodes_expanded += 1;
+        let children = get_all_adjacent_nodes(node, self.shared, self.options);
+        let mut smallest_exceeded: Option<u32> = None;
+        for child in children {
+            self.stats.nodes_generated += 1;
+            if self.path.contains(&child) {
+                continue;
+            }
+            self.path.push(child);
+            match self.search(cost + 1, threshold) {
+                Step::Found => return Step::Found,
+                Step::Limit(limit) => return Step::Limit(limit),
+                Step::Exceeded(exceeded) => {
+                    smallest_exceeded = match (smallest_exceeded, exceeded) {
+                        (Some(a), Some(b)) => Some(a.min(b)),
+                        (a, b) => a.or(b),
+                    };
+                }
+            }
+            self.path.pop();
+        }
+        Step::Exceeded(smallest_exceeded)
+    }
+}
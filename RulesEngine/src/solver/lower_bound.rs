@@ -0,0 +1,73 @@
+use crate::core::{push_lower_bound, Cell, SharedGameState, Vec2};
+use crate::state_graph::UniqueNode;
+
+/// Estimates how many pushes are left before a node is won.
+///
+/// Solvers only promise optimal solutions when the estimate never exceeds the real number
+/// of pushes left.
+pub trait LowerBound {
+    /// None when the node can never be won
+    fn estimate(&self, node: &UniqueNode) -> Option<u32>;
+}
+
+/// No estimate at all, which makes A* a breadth first search
+pub struct ZeroBound;
+
+impl LowerBound for ZeroBound {
+    fn estimate(&self, _node: &UniqueNode) -> Option<u32> {
+        Some(0)
+    }
+}
+
+/// Sum of the distances from each box to its nearest target, ignoring walls. Only the boxes
+/// closest to a target count, one per target, as spare boxes never need to move.
+/// Every push moves one box one cell, so this never overestimates.
+pub struct BoxDistanceBound {
+    targets: Vec<Vec2>,
+}
+
+impl BoxDistanceBound {
+    pub fn new(shared: &SharedGameState) -> BoxDistanceBound {
+        let mut targets = Vec::new();
+        for (i, row) in shared.grid.iter().enumerate() {
+            for (j, &c) in row.iter().enumerate() {
+                if c == Cell::Target {
+                    targets.push(Vec2 { i: i as i8, j: j as i8 });
+                }
+            }
+        }
+        BoxDistanceBound { targets }
+    }
+}
+
+impl LowerBound for BoxDistanceBound {
+    fn estimate(&self, node: &UniqueNode) -> Option<u32> {
+        let mut nearest = Vec::new();
+        for b in node.environment.iter_boxes() {
+            nearest.push(self.targets.iter()
+                .map(|t| (b.i - t.i).unsigned_abs() as u32 + (b.j - t.j).unsigned_abs() as u32)
+                .min()?);
+        }
+        nearest.sort_unstable();
+        Some(nearest.iter().take(self.targets.len()).sum())
+    }
+}
+
+/// Matches every target to a different box at the lowest total push distance, see
+/// [`push_lower_bound`]. Much tighter than [`BoxDistanceBound`], as it accounts for walls and
+/// for boxes sharing a nearest target. None marks a deadlock.
+pub struct MatchingBound<'a> {
+    shared: &'a SharedGameState,
+}
+
+impl MatchingBound<'_> {
+    pub fn new(shared: &SharedGameState) -> MatchingBound<'_> {
+        MatchingBound { shared }
+    }
+}
+
+impl LowerBound for MatchingBound<'_> {
+    fn estimate(&self, node: &UniqueNode) -> Option<u32> {
+        push_lower_bound(self.shared, &node.environment)
+    }
+}
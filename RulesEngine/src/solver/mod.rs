@@ -0,0 +1,11 @@
+mod models;
+mod lower_bound;
+mod a_star;
+mod ida_star;
+mod bidirectional;
+
+pub use models::*;
+pub use lower_bound::{LowerBound, ZeroBound, BoxDistanceBound, MatchingBound};
+pub use a_star::solve_a_star;
+pub use ida_star::solve_ida_star;
+pub use bidirectional::solve_bidirectional;
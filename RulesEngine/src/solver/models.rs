@@ -0,0 +1,75 @@
+use std::time::{Duration, Instant};
+use crate::state_graph::UniqueNode;
+
+/// When to give up on a search. Unset limits never stop the search.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SolverLimits {
+    pub max_nodes_expanded: Option<usize>,
+    pub max_time: Option<Duration>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LimitReached {
+    Nodes,
+    Time,
+}
+
+#[derive(Debug)]
+pub enum SolveOutcome {
+    /// Push-optimal when the lower bound is admissible. Starts with the initial node and ends
+    /// with a won node. Under symmetry reduction the nodes are still the states played, not
+    /// their representatives.
+    Solved(Vec<UniqueNode>),
+    /// Every reachable state was searched without finding a win
+    Unsolvable,
+    LimitReached(LimitReached),
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SolverStats {
+    pub nodes_expanded: usize,
+    pub nodes_generated: usize,
+    pub elapsed: Duration,
+}
+
+#[derive(Debug)]
+pub struct SolverResult {
+    pub outcome: SolveOutcome,
+    pub stats: SolverStats,
+}
+
+impl SolverLimits {
+    pub fn unlimited() -> SolverLimits {
+        SolverLimits::default()
+    }
+
+    pub(crate) fn check(&self, stats: &SolverStats, start_time: Instant) -> Option<LimitReached> {
+        if self.max_nodes_expanded.is_some_and(|max| stats.nodes_expanded >= max) {
+            return Some(LimitReached::Nodes);
+        }
+        if self.max_time.is_some_and(|max| start_time.elapsed() >= max) {
+            return Some(LimitReached::Time);
+        }
+        None
+    }
+}
+
+impl SolveOutcome {
+    pub fn solution(&self) -> Option<&[UniqueNode]> {
+        match self {
+            SolveOutcome::Solved(nodes) => Some(nodes),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for SolveOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolveOutcome::Solved(nodes) => write!(f, "solved in {} pushes", nodes.len() - 1),
+            SolveOutcome::Unsolvable => write!(f, "unsolvable"),
+            SolveOutcome::LimitReached(LimitReached::Nodes) => write!(f, "gave up, node limit reached"),
+            SolveOutcome::LimitReached(LimitReached::Time) => write!(f, "gave up, time limit reached"),
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use std::fs::File;
-use crate::state_graph::StateGraph;
+use crate::core::SearchOrder;
+use crate::state_graph::{DiskExplorationStats, StateGraph};
 use ratatui::Terminal;
 use ratatui::backend::CrosstermBackend;
 use ratatui::layout::Alignment;
@@ -9,7 +10,9 @@ use std::io;
 use std::io::Write;
 
 pub struct GraphRenderState<'a> {
-    pub graph: &'a StateGraph,
+    pub graph: GraphInfo,
+    /// Set when exploring on disk, see [`crate::state_graph::DiskExplorer`]
+    pub disk: Option<&'a DiskExplorationStats>,
     pub processed_since_last_render: usize,
     pub start_time: std::time::Instant,
     pub last_render_time: std::time::Instant,
@@ -22,7 +25,7 @@ pub fn render_graph(
     render: GraphRenderState,
 ) -> Result<(), Box<dyn std::error::Error>> {
 
-    let graph_info = GraphInfo::new(render.graph);
+    let graph_info = render.graph;
 
     let visited_per_second = if render.current_time == render.last_render_time {
         0.0
@@ -45,7 +48,16 @@ pub fn render_graph(
 
     file_out.write(graph_info.to_log_string().as_bytes())?;
 
-    let description = format!("{}\n{}", graph_info.to_human_string(), time_description);
+    let mut description = format!("{}\n{}", graph_info.to_human_string(), time_description);
+    if let Some(disk) = render.disk {
+        let elapsed = (render.current_time - render.start_time).as_secs_f64();
+        let megabytes = |bytes: u64| bytes as f64 / 1_000_000.0;
+        let disk_per_second = if elapsed == 0.0 { 0.0 } else { megabytes(disk.bytes_read + disk.bytes_written) / elapsed };
+        description += &format!(
+            "\nLayer {}. Disk read {:.1} MB, written {:.1} MB. Total {:.1} MB/sec.",
+            disk.layer, megabytes(disk.bytes_read), megabytes(disk.bytes_written), disk_per_second
+        );
+    }
 
     terminal.draw(|f| {
         let size = f.area();
@@ -69,14 +81,19 @@ pub struct GraphInfo {
     pub edges: usize,
     pub visited: usize,
     pub percent_visited: f64,
+    pub search_order: SearchOrder,
 }
 
 impl GraphInfo {
     pub fn new(graph: &StateGraph) -> Self {
-        let nodes = graph.nodes.len();
-        let edges = graph.edges.len();
-        let unvisited = graph.unvisited.len();
-        let visited = nodes - unvisited;
+        GraphInfo::from_counts(graph.nodes.len(), graph.edges.len(), graph.nodes.len() - graph.unvisited.len(), graph.search_order)
+    }
+
+    pub fn from_disk(stats: &DiskExplorationStats) -> Self {
+        GraphInfo::from_counts(stats.nodes as usize, stats.edges as usize, stats.expanded as usize, SearchOrder::BreadthFirst)
+    }
+
+    fn from_counts(nodes: usize, edges: usize, visited: usize, search_order: SearchOrder) -> Self {
         let percent_visited = if nodes > 0 {
             (visited as f64 / nodes as f64) * 100.0
         } else {
@@ -87,20 +104,21 @@ impl GraphInfo {
             edges,
             visited,
             percent_visited,
+            search_order,
         }
     }
 
     pub fn to_human_string(&self) -> String {
         format!(
-            "Graph has {} nodes, {} edges, {} visited nodes ({:.1}%), {} unvisited.",
-            self.nodes, self.edges, self.visited, self.percent_visited, self.nodes - self.visited
+            "Graph has {} nodes, {} edges, {} visited nodes ({:.1}%), {} unvisited. Explored in {} order.",
+            self.nodes, self.edges, self.visited, self.percent_visited, self.nodes - self.visited, self.search_order
         )
     }
 
     pub fn to_log_string(&self) -> String {
         format!(
-            "nodes: {}, edges: {}, visited: {}\n",
-            self.nodes, self.edges, self.visited
+            "nodes: {}, edges: {}, visited: {}, order: {}\n",
+            self.nodes, self.edges, self.visited, self.search_order
         )
     }
 }
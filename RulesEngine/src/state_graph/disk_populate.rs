@@ -0,0 +1,505 @@
+use std::cell::Cell;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use bevy::math::IVec2;
+use crate::core::{Budget, ExplorationOptions, GameStateEnvironment, SharedGameState};
+use crate::state_graph::graph_file::{direction_from_byte, direction_to_byte};
+use crate::state_graph::{get_all_adjacent_pushes, Edge, NodeId, PopulateResult, Push, StateGraph, UniqueNode};
+
+/// Frontier nodes expanded by one call to [`DiskExplorer::populate_step`]
+const EXPAND_BATCH: usize = 1024;
+
+/// Bytes of a push: pushed box (i8, i8), direction u8, walk before it u16
+const PUSH_RECORD_LEN: usize = 5;
+/// Bytes of an edge in the edge list: from u32, to u32, then the push
+const EDGE_RECORD_LEN: usize = 8 + PUSH_RECORD_LEN;
+
+/// Explores breadth first with the visited set and the frontier on disk, so the graph is bounded
+/// by disk space rather than memory.
+///
+/// Each breadth first layer is expanded from a sorted file. The children are not looked up as
+/// they are found, they go to sorted runs of at most `records_in_memory` nodes instead. Once the
+/// layer is done, the runs are merged against the sorted visited file in a single pass, which
+/// finds the new nodes all at once. This is delayed duplicate detection.
+///
+/// Ids are assigned layer by layer, in sorted order within a layer. The edges go to an edge list
+/// on disk, see [`read_edge_list`]. The search order of the options is ignored, and limits only
+/// apply between layers.
+pub struct DiskExplorer<'a> {
+    shared: &'a SharedGameState,
+    directory: PathBuf,
+    /// Bytes of an encoded node
+    key_len: usize,
+    records_in_memory: usize,
+    next_id: NodeId,
+    /// Nodes of the layer being expanded, opened when the first of them is expanded
+    frontier: Option<RecordReader>,
+    frontier_len: u64,
+    /// Children of the expanded frontier nodes, as encoded nodes
+    candidates: RunWriter,
+    /// Edges from the expanded frontier nodes: encoded child, from id, push and walk
+    pending_edges: RunWriter,
+    stats: DiskExplorationStats,
+    io: Rc<IoCounters>,
+}
+
+/// Progress of a [`DiskExplorer`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DiskExplorationStats {
+    pub nodes: u64,
+    /// Edges of the finished layers, the edges of the layer being expanded are not counted yet
+    pub edges: u64,
+    pub expanded: u64,
+    /// Pushes from the initial state to the layer being expanded
+    pub layer: usize,
+    pub bytes_read: u64,
+    pub bytes_written: u64,
+}
+
+#[derive(Default)]
+struct IoCounters {
+    read: Cell<u64>,
+    written: Cell<u64>,
+}
+
+impl<'a> DiskExplorer<'a> {
+    /// Starts an exploration from `start` in `directory`, replacing any exploration there.
+    /// At most `records_in_memory` children are held in memory at once.
+    pub fn new(directory: &Path, shared: &'a SharedGameState, start: UniqueNode, records_in_memory: usize) -> io::Result<DiskExplorer<'a>> {
+        std::fs::create_dir_all(directory)?;
+        let io = Rc::new(IoCounters::default());
+        let key_len = encoded_len(start.environment.box_count());
+        let mut record = encode_node(&start);
+        record.extend_from_slice(&0u32.to_le_bytes());
+
+        for path in [visited_path(directory), layer_path(directory, 0)] {
+            let mut writer = RecordWriter::create(&path, io.clone())?;
+            writer.write(&record)?;
+            writer.finish()?;
+        }
+        RecordWriter::create(&edge_list_path(directory), io.clone())?.finish()?;
+
+        Ok(DiskExplorer {
+            shared,
+            directory: directory.to_path_buf(),
+            key_len,
+            records_in_memory: records_in_memory.max(1),
+            next_id: 1,
+            frontier: None,
+            frontier_len: 1,
+            candidates: RunWriter::new(directory, "candidates", key_len, records_in_memory, io.clone()),
+            pending_edges: RunWriter::new(directory, "edges", key_len + 4 + PUSH_RECORD_LEN, records_in_memory, io.clone()),
+            stats: DiskExplorationStats {
+                nodes: 1,
+                ..DiskExplorationStats::default()
+            },
+            io,
+        })
+    }
+
+    pub fn stats(&self) -> DiskExplorationStats {
+        DiskExplorationStats {
+            bytes_read: self.io.read.get(),
+            bytes_written: self.io.written.get(),
+            ..self.stats.clone()
+        }
+    }
+
+    /// The edge list written so far, see [`read_edge_list`]
+    pub fn edge_list_path(&self) -> PathBuf {
+        edge_list_path(&self.directory)
+    }
+
+    /// Expands a batch of frontier nodes. Returns early after finishing a layer, so the caller can
+    /// check limits between layers.
+    pub fn populate_step(&mut self, options: &ExplorationOptions) -> io::Result<PopulateResult> {
+        for _ in 0..EXPAND_BATCH {
+            if self.frontier_len == 0 {
+                return Ok(PopulateResult::AllVisited);
+            }
+            if self.frontier.is_none() {
+                let path = layer_path(&self.directory, self.stats.layer);
+                self.frontier = Some(RecordReader::open(&path, self.key_len + 4, self.io.clone())?);
+            }
+            let frontier = self.frontier.as_mut().expect("opened above");
+            match frontier.next_record()? {
+                Some(record) => self.expand(&record, options)?,
+                None => {
+                    self.finish_layer()?;
+                    return Ok(PopulateResult::Populated);
+                }
+            }
+        }
+        Ok(PopulateResult::Populated)
+    }
+
+    /// Populates until every node is visited, or until a budget of `options` is used up at the
+    /// end of a layer. The memory budget does not apply, memory is bounded by the records kept
+    /// in memory.
+    pub fn populate_all(&mut self, options: &ExplorationOptions) -> io::Result<PopulateResult> {
+        let start_time = Instant::now();
+        let mut layer = self.stats.layer;
+        loop {
+            if let PopulateResult::AllVisited = self.populate_step(options)? {
+                return Ok(PopulateResult::AllVisited);
+            }
+            if self.stats.layer != layer {
+                layer = self.stats.layer;
+                if let Some(budget) = self.exhausted_budget(options, start_time.elapsed()) {
+                    return Ok(PopulateResult::BudgetExhausted(budget));
+                }
+            }
+        }
+    }
+
+    /// The first budget of `options` used up, see [`Self::populate_all`]
+    pub fn exhausted_budget(&self, options: &ExplorationOptions, elapsed: Duration) -> Option<Budget> {
+        options.exhausted_budget(self.stats.nodes as usize, self.stats.edges as usize, 0, elapsed)
+    }
+
+    fn expand(&mut self, record: &[u8], options: &ExplorationOptions) -> io::Result<()> {
+        let node = decode_node(&record[..self.key_len]);
+        let from = &record[self.key_len..];
+        for (push, walk, child) in get_all_adjacent_pushes(&node, self.shared, options) {
+            let key = encode_node(&child);
+            self.candidates.push(&key)?;
+            let mut edge = key;
+            edge.extend_from_slice(from);
+            edge.extend_from_slice(&encode_push(push, walk));
+            self.pending_edges.push(&edge)?;
+        }
+        self.stats.expanded += 1;
+        Ok(())
+    }
+
+    /// Merges the children of the layer into the visited set, which makes the new ones the next
+    /// layer, then resolves the edges of the layer to ids
+    fn finish_layer(&mut self) -> io::Result<()> {
+        self.frontier = None;
+        let id_len = self.key_len + 4;
+        let layer = self.stats.layer;
+        let next_layer_path = layer_path(&self.directory, layer + 1);
+        let merged_path = self.directory.join("visited.tmp");
+
+        let fresh_candidates = RunWriter::new(&self.directory, "candidates", self.key_len, self.records_in_memory, self.io.clone());
+        let mut candidates = std::mem::replace(&mut self.candidates, fresh_candidates).into_merger()?;
+        let mut visited = RecordReader::open(&visited_path(&self.directory), id_len, self.io.clone())?;
+        let mut merged = RecordWriter::create(&merged_path, self.io.clone())?;
+        let mut next_layer = RecordWriter::create(&next_layer_path, self.io.clone())?;
+        let mut known = visited.next_record()?;
+        let mut new_nodes = 0;
+        while let Some(candidate) = candidates.next_record()? {
+            while let Some(record) = known.take_if(|record| record[..self.key_len] < candidate[..]) {
+                merged.write(&record)?;
+                known = visited.next_record()?;
+            }
+            if known.as_ref().is_some_and(|record| record[..self.key_len] == candidate[..]) {
+                continue;
+            }
+            let mut record = candidate;
+            record.extend_from_slice(&self.next_id.to_le_bytes());
+            merged.write(&record)?;
+            next_layer.write(&record)?;
+            self.next_id = self.next_id.checked_add(1)
+                .ok_or_else(|| io::Error::other("ran out of node ids"))?;
+            new_nodes += 1;
+        }
+        while let Some(record) = known {
+            merged.write(&record)?;
+            known = visited.next_record()?;
+        }
+        merged.finish()?;
+        next_layer.finish()?;
+        std::fs::rename(&merged_path, visited_path(&self.directory))?;
+        candidates.remove()?;
+
+        let fresh_edges = RunWriter::new(&self.directory, "edges", self.key_len + 4 + PUSH_RECORD_LEN, self.records_in_memory, self.io.clone());
+        let mut pending = std::mem::replace(&mut self.pending_edges, fresh_edges).into_merger()?;
+        let mut visited = RecordReader::open(&visited_path(&self.directory), id_len, self.io.clone())?;
+        let mut edges = RecordWriter::append(&edge_list_path(&self.directory), self.io.clone())?;
+        let mut known = visited.next_record()?;
+        while let Some(edge) = pending.next_record()? {
+            let (key, rest) = edge.split_at(self.key_len);
+            while known.as_ref().is_some_and(|record| &record[..self.key_len] < key) {
+                known = visited.next_record()?;
+            }
+            let Some(target) = known.as_ref().filter(|record| &record[..self.key_len] == key) else {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "edge leads to a node missing from the visited set"));
+            };
+            let mut record = Vec::with_capacity(EDGE_RECORD_LEN);
+            record.extend_from_slice(&rest[..4]);
+            record.extend_from_slice(&target[self.key_len..]);
+            record.extend_from_slice(&rest[4..]);
+            edges.write(&record)?;
+            self.stats.edges += 1;
+        }
+        edges.finish()?;
+        pending.remove()?;
+
+        std::fs::remove_file(layer_path(&self.directory, layer))?;
+        self.stats.nodes += new_nodes;
+        self.stats.layer += 1;
+        self.frontier_len = new_nodes;
+        if new_nodes == 0 {
+            std::fs::remove_file(next_layer_path)?;
+        }
+        Ok(())
+    }
+
+    /// Loads the exploration into a graph, for levels which fit in memory after all.
+    /// Only possible between layers, the nodes of the next layer are left unvisited.
+    pub fn load_state_graph(&self) -> io::Result<StateGraph> {
+        if self.frontier.is_some() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "a layer is partially expanded"));
+        }
+        let id_len = self.key_len + 4;
+        let mut graph = StateGraph::new();
+        let mut visited = RecordReader::open(&visited_path(&self.directory), id_len, self.io.clone())?;
+        while let Some(record) = visited.next_record()? {
+            graph.nodes.insert_at(decode_id(&record[self.key_len..]), decode_node(&record[..self.key_len]));
+        }
+        graph.nodes.extend_ids_to(self.next_id);
+
+        for edge in read_edge_list(&self.edge_list_path())? {
+            graph.add_edge(edge?);
+        }
+        graph.compact_edges();
+
+        if self.frontier_len > 0 {
+            let mut frontier = RecordReader::open(&layer_path(&self.directory, self.stats.layer), id_len, self.io.clone())?;
+            while let Some(record) = frontier.next_record()? {
+                let id = decode_id(&record[self.key_len..]);
+                graph.unvisited.insert(id);
+                graph.next_unvisted.push_back(id);
+            }
+        }
+        Ok(graph)
+    }
+}
+
+/// Reads an edge list written by [`DiskExplorer`]
+pub fn read_edge_list(path: &Path) -> io::Result<impl Iterator<Item=io::Result<Edge>>> {
+    let mut reader = RecordReader::open(path, EDGE_RECORD_LEN, Rc::default())?;
+    Ok(std::iter::from_fn(move || {
+        reader.next_record().transpose().map(|record| record.and_then(|record| decode_edge(&record)))
+    }))
+}
+
+fn visited_path(directory: &Path) -> PathBuf {
+    directory.join("visited.bin")
+}
+
+fn layer_path(directory: &Path, layer: usize) -> PathBuf {
+    directory.join(format!("layer-{}.bin", layer))
+}
+
+fn edge_list_path(directory: &Path) -> PathBuf {
+    directory.join("edges.bin")
+}
+
+fn encoded_len(box_count: usize) -> usize {
+    2 + 2 * box_count
+}
+
+/// Player position then boxes, a byte per coordinate. Equal nodes encode to equal bytes.
+fn encode_node(node: &UniqueNode) -> Vec<u8> {
+    let player = node.minimum_reachable_player_position;
+    let mut bytes = vec![player.y as i8 as u8, player.x as i8 as u8];
+    for b in node.environment.iter_boxes() {
+        bytes.extend_from_slice(&[b.i as u8, b.j as u8]);
+    }
+    bytes
+}
+
+fn decode_node(bytes: &[u8]) -> UniqueNode {
+    let position = |pair: &[u8]| IVec2::new(pair[1] as i8 as i32, pair[0] as i8 as i32);
+    let boxes: Vec<IVec2> = bytes[2..].chunks(2).map(position).collect();
+    UniqueNode {
+        // kept in the order written, like reading a graph file
+        environment: GameStateEnvironment::from_ordered(boxes),
+        minimum_reachable_player_position: position(&bytes[..2]),
+    }
+}
+
+fn decode_id(bytes: &[u8]) -> NodeId {
+    NodeId::from_le_bytes(bytes[..4].try_into().expect("ids are four bytes"))
+}
+
+fn encode_push(push: Push, walk: u16) -> [u8; PUSH_RECORD_LEN] {
+    let [walk_low, walk_high] = walk.to_le_bytes();
+    [push.box_position.i as u8, push.box_position.j as u8, direction_to_byte(push.direction), walk_low, walk_high]
+}
+
+fn decode_edge(record: &[u8]) -> io::Result<Edge> {
+    let direction = direction_from_byte(record[10])
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+    Ok(Edge {
+        from: decode_id(&record[0..4]),
+        to: decode_id(&record[4..8]),
+        push: Push {
+            box_position: crate::core::Vec2 { i: record[8] as i8, j: record[9] as i8 },
+            direction,
+        },
+        walk: u16::from_le_bytes([record[11], record[12]]),
+    })
+}
+
+/// Fixed size records read in order
+struct RecordReader {
+    reader: BufReader<File>,
+    record_len: usize,
+    io: Rc<IoCounters>,
+}
+
+impl RecordReader {
+    fn open(path: &Path, record_len: usize, io: Rc<IoCounters>) -> io::Result<RecordReader> {
+        Ok(RecordReader {
+            reader: BufReader::new(File::open(path)?),
+            record_len,
+            io,
+        })
+    }
+
+    fn next_record(&mut self) -> io::Result<Option<Vec<u8>>> {
+        if self.reader.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+        let mut record = vec![0; self.record_len];
+        io::Read::read_exact(&mut self.reader, &mut record)?;
+        self.io.read.set(self.io.read.get() + self.record_len as u64);
+        Ok(Some(record))
+    }
+}
+
+struct RecordWriter {
+    writer: BufWriter<File>,
+    io: Rc<IoCounters>,
+}
+
+impl RecordWriter {
+    fn create(path: &Path, io: Rc<IoCounters>) -> io::Result<RecordWriter> {
+        Ok(RecordWriter { writer: BufWriter::new(File::create(path)?), io })
+    }
+
+    fn append(path: &Path, io: Rc<IoCounters>) -> io::Result<RecordWriter> {
+        let file = OpenOptions::new().append(true).create(true).open(path)?;
+        Ok(RecordWriter { writer: BufWriter::new(file), io })
+    }
+
+    fn write(&mut self, record: &[u8]) -> io::Result<()> {
+        self.io.written.set(self.io.written.get() + record.len() as u64);
+        self.writer.write_all(record)
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Collects records into sorted runs on disk, holding at most `capacity` of them in memory
+struct RunWriter {
+    directory: PathBuf,
+    name: &'static str,
+    record_len: usize,
+    capacity: usize,
+    buffer: Vec<u8>,
+    runs: Vec<PathBuf>,
+    io: Rc<IoCounters>,
+}
+
+impl RunWriter {
+    fn new(directory: &Path, name: &'static str, record_len: usize, capacity: usize, io: Rc<IoCounters>) -> RunWriter {
+        RunWriter {
+            directory: directory.to_path_buf(),
+            name,
+            record_len,
+            capacity: capacity.max(1),
+            buffer: Vec::new(),
+            runs: Vec::new(),
+            io,
+        }
+    }
+
+    fn push(&mut self, record: &[u8]) -> io::Result<()> {
+        debug_assert_eq!(record.len(), self.record_len);
+        self.buffer.extend_from_slice(record);
+        if self.buffer.len() >= self.capacity * self.record_len {
+            self.write_run()?;
+        }
+        Ok(())
+    }
+
+    fn write_run(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let mut records: Vec<&[u8]> = self.buffer.chunks(self.record_len).collect();
+        records.sort_unstable();
+        records.dedup();
+        let path = self.directory.join(format!("{}-{}.run", self.name, self.runs.len()));
+        let mut writer = RecordWriter::create(&path, self.io.clone())?;
+        for record in records {
+            writer.write(record)?;
+        }
+        writer.finish()?;
+        self.runs.push(path);
+        self.buffer.clear();
+        Ok(())
+    }
+
+    fn into_merger(mut self) -> io::Result<RunMerger> {
+        self.write_run()?;
+        let mut readers = Vec::with_capacity(self.runs.len());
+        let mut heads = BinaryHeap::with_capacity(self.runs.len());
+        for (index, path) in self.runs.iter().enumerate() {
+            let mut reader = RecordReader::open(path, self.record_len, self.io.clone())?;
+            if let Some(record) = reader.next_record()? {
+                heads.push(Reverse((record, index)));
+            }
+            readers.push(reader);
+        }
+        Ok(RunMerger {
+            readers,
+            heads,
+            last: None,
+            runs: self.runs,
+        })
+    }
+}
+
+/// Merges sorted runs into one sorted stream without duplicates
+struct RunMerger {
+    readers: Vec<RecordReader>,
+    heads: BinaryHeap<Reverse<(Vec<u8>, usize)>>,
+    last: Option<Vec<u8>>,
+    runs: Vec<PathBuf>,
+}
+
+impl RunMerger {
+    fn next_record(&mut self) -> io::Result<Option<Vec<u8>>> {
+        while let Some(Reverse((record, index))) = self.heads.pop() {
+            if let Some(next) = self.readers[index].next_record()? {
+                self.heads.push(Reverse((next, index)));
+            }
+            if self.last.as_ref() == Some(&record) {
+                continue;
+            }
+            self.last = Some(record.clone());
+            return Ok(Some(record));
+        }
+        Ok(None)
+    }
+
+    fn remove(self) -> io::Result<()> {
+        drop(self.readers);
+        for path in self.runs {
+            std::fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+use crate::state_graph::{Edge, NodeId, Push};
+
+/// Edges of a graph. Exploration appends them to a plain list, [`EdgeStore::compact`] then moves
+/// them into compressed sparse row adjacency, both forward and reverse, which queries walk.
+#[derive(Clone, Default)]
+pub struct EdgeStore {
+    /// Added since the last compaction, may hold duplicates
+    pending: Vec<Edge>,
+    /// Successors of each node, with the push leading there and the walk before it
+    forward: Adjacency,
+    /// Predecessors of each node, with the push leading from there and the walk before it
+    reverse: Adjacency,
+}
+
+/// Compressed sparse row adjacency. The neighbours of node `id` are
+/// `neighbours[offsets[id]..offsets[id + 1]]`, sorted. Offsets are u32 like node ids, so at most
+/// `u32::MAX` edges are compacted.
+#[derive(Clone, Default)]
+struct Adjacency {
+    offsets: Vec<u32>,
+    neighbours: Vec<(NodeId, Push, u16)>,
+}
+
+impl Adjacency {
+    /// `edges` sorted, without duplicates
+    fn from_sorted(edges: &[Edge], node_count: usize) -> Adjacency {
+        assert!(u32::try_from(edges.len()).is_ok(), "{} edges overflow the adjacency offsets", edges.len());
+        let mut offsets = vec![0u32; node_count + 1];
+        for edge in edges {
+            offsets[edge.from as usize + 1] += 1;
+        }
+        for id in 0..node_count {
+            offsets[id + 1] += offsets[id];
+        }
+        let neighbours = edges.iter().map(|edge| (edge.to, edge.push, edge.walk)).collect();
+        Adjacency { offsets, neighbours }
+    }
+
+    fn of(&self, id: NodeId) -> &[(NodeId, Push, u16)] {
+        let id = id as usize;
+        match (self.offsets.get(id), self.offsets.get(id + 1)) {
+            (Some(&start), Some(&end)) => &self.neighbours[start as usize..end as usize],
+            _ => &[],
+        }
+    }
+
+    fn node_count(&self) -> usize {
+        self.offsets.len().saturating_sub(1)
+    }
+}
+
+impl EdgeStore {
+    pub fn new() -> EdgeStore {
+        EdgeStore::default()
+    }
+
+    pub fn insert(&mut self, edge: Edge) {
+        self.pending.push(edge);
+    }
+
+    /// Duplicates added since the last compaction are counted until it removes them
+    pub fn len(&self) -> usize {
+        self.forward.neighbours.len() + self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn memory_bytes(&self) -> usize {
+        let adjacency_bytes = |adjacency: &Adjacency| adjacency.offsets.capacity() * size_of::<u32>()
+            + adjacency.neighbours.capacity() * size_of::<(NodeId, Push, u16)>();
+        self.pending.capacity() * size_of::<Edge>() + adjacency_bytes(&self.forward) + adjacency_bytes(&self.reverse)
+    }
+
+    /// Whether every edge is in the adjacency, so [`EdgeStore::outgoing`] and
+    /// [`EdgeStore::incoming`] see all of them
+    pub fn is_compact(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Every edge, compacted ones sorted by source first
+    pub fn iter(&self) -> impl Iterator<Item=Edge> + '_ {
+        let compacted = (0..self.forward.node_count() as NodeId)
+            .flat_map(move |from| self.forward.of(from).iter()
+                .map(move |&(to, push, walk)| Edge { from, to, push, walk }));
+        compacted.chain(self.pending.iter().copied())
+    }
+
+    /// Compacted edges leaving `id`, sorted by target
+    pub fn outgoing(&self, id: NodeId) -> &[(NodeId, Push, u16)] {
+        assert!(self.is_compact(), "edges must be compacted before they are queried");
+        self.forward.of(id)
+    }
+
+    /// Compacted edges arriving at `id`, sorted by source
+    pub fn incoming(&self, id: NodeId) -> &[(NodeId, Push, u16)] {
+        assert!(self.is_compact(), "edges must be compacted before they are queried");
+        self.reverse.of(id)
+    }
+
+    /// Moves the pending edges into the adjacency and drops duplicates.
+    /// Every edge must connect ids below `next_id`.
+    pub fn compact(&mut self, next_id: NodeId) {
+        if self.is_compact() && self.forward.node_count() == next_id as usize {
+            return;
+        }
+        self.retain(next_id, |_| true);
+    }
+
+    /// Keeps only the edges for which `keep` is true, compacting them
+    pub fn retain(&mut self, next_id: NodeId, mut keep: impl FnMut(&Edge) -> bool) {
+        let mut edges: Vec<Edge> = self.iter().filter(|edge| keep(edge)).collect();
+        self.pending = Vec::new();
+        edges.sort_unstable();
+        edges.dedup();
+        self.forward = Adjacency::from_sorted(&edges, next_id as usize);
+
+        let mut reversed = edges;
+        for edge in &mut reversed {
+            std::mem::swap(&mut edge.from, &mut edge.to);
+        }
+        reversed.sort_unstable();
+        self.reverse = Adjacency::from_sorted(&reversed, next_id as usize);
+    }
+}
+
+impl<'a> IntoIterator for &'a EdgeStore {
+    type Item = Edge;
+    type IntoIter = Box<dyn Iterator<Item=Edge> + 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(self.iter())
+    }
+}
+
+/// The same set of edges, however they are stored
+impl PartialEq for EdgeStore {
+    fn eq(&self, other: &Self) -> bool {
+        let sorted = |store: &EdgeStore| {
+            let mut edges: Vec<Edge> = store.iter().collect();
+            edges.sort_unstable();
+            edges.dedup();
+            edges
+        };
+        sorted(self) == sorted(other)
+    }
+}
+
+impl Eq for EdgeStore {}
+
+impl std::fmt::Debug for EdgeStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
@@ -0,0 +1,241 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{HashSet, VecDeque};
+use crate::core::{Direction, ExplorationOptions, GameState, SharedGameState, Vec2};
+use crate::state_graph::{get_all_adjacent_pushes, Push, UniqueNode};
+
+/// Probes one estimate samples, unless told otherwise
+pub const DEFAULT_PROBES: usize = 1000;
+/// Pushes a single probe follows at most, a safety net for levels with very long push sequences
+const MAX_PROBE_DEPTH: usize = 100_000;
+/// Earlier pushes of a probe checked for commuting with the last one
+const COMMUTE_WINDOW: usize = 16;
+/// Groups the probes are averaged in before taking the median of the group means
+const PROBE_GROUPS: usize = 10;
+
+/// Prediction of how many nodes exploring a level produces, from [`estimate_state_count`]
+#[derive(Debug, Clone)]
+pub struct StateCountEstimate {
+    /// Cells the player can walk on, ignoring boxes
+    pub player_cells: usize,
+    /// Cells a box can rest on without the detectors pruning it
+    pub box_cells: usize,
+    /// Most areas the boxes can cut the player's cells into
+    pub max_player_regions: usize,
+    /// No exploration holds more nodes. Every way to place the boxes, times every region the
+    /// player can be in.
+    pub upper_bound: f64,
+    /// Median of the means of the probe groups
+    pub sampled: f64,
+    /// Lowest and highest mean of the probe groups, with both ends kept between 1 and the bound
+    pub sampled_interval: (f64, f64),
+    pub probes: usize,
+}
+
+/// Predicts how many nodes exploring from `start` with `options` produces, without exploring.
+///
+/// The upper bound counts every placement of the boxes on the cells they can occupy, times the
+/// regions the player can be in. A box cuts a region into at most four, so `n` boxes leave at
+/// most `3n + 1` regions.
+///
+/// The sample is Knuth's tree size estimator, adapted to a graph. Each probe walks from the
+/// start along random pushes, skipping nodes it already passed or could have stepped to earlier.
+/// Every step multiplies the weight by the pushes it could have taken, and divides it by the
+/// pushes leading into the node it arrives at from as deep as it came from, so a node reached by
+/// making the same pushes in another order is not counted once per order. Only orders differing
+/// in the last few pushes are found, so levels with many independent boxes are overestimated.
+/// A probe only pushes a box along a shortest route a lone box could take from where it started,
+/// since a detour mostly arrives at a node fewer pushes reach too, and counting it as deeper
+/// inflates the weight. Nodes only a detour reaches are missed, which offsets some of the
+/// overestimate. Probes are heavy tailed, so the sample is the median of the means of groups of
+/// probes. Panics when `probes` is zero.
+pub fn estimate_state_count(shared: &SharedGameState, start: &GameState, options: &ExplorationOptions, probes: usize, seed: u64) -> StateCountEstimate {
+    assert!(probes > 0, "an estimate needs at least one probe");
+    let layout = shared.bit_layout();
+    let start_index = layout.index_of(start.player).expect("the player must be inside the level");
+    let player_board = layout.flood_fill(shared.walkable_board(), start_index);
+    let player_cells = player_board.count();
+
+    let box_count = start.environment.box_count();
+    let prunes_dead_squares = options.detectors.names().contains(&"dead-squares");
+    let dead_cells = player_board.iter_indexes()
+        .filter(|&index| shared.is_dead_square(layout.position_of(index)))
+        .count();
+    let live_cells = player_cells - dead_cells;
+    let max_dead_boxes = if prunes_dead_squares { shared.spare_boxes(box_count) } else { box_count };
+    let placements: f64 = (0..=max_dead_boxes.min(box_count))
+        .map(|dead_boxes| binomial(dead_cells, dead_boxes) * binomial(live_cells, box_count - dead_boxes))
+        .sum();
+    let box_order_factor = if options.deduplicate_boxes { 1.0 } else { factorial(box_count) };
+    let max_player_regions = (3 * box_count + 1).min(player_cells.saturating_sub(box_count).max(1));
+    // plus the start, in case it already breaks the detectors' rules
+    let upper_bound = placements * box_order_factor * max_player_regions as f64 + 1.0;
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let start_node = UniqueNode::from_game_state(start.clone(), shared);
+    let routes: Vec<Vec<Option<u16>>> = start_node.environment.iter_boxes()
+        .map(|&position| lone_box_pushes(shared, position))
+        .collect();
+    let samples: Vec<f64> = (0..probes)
+        .map(|_| probe(shared, &start_node, &routes, options, &mut rng))
+        .collect();
+    let (median, lowest, highest) = median_of_means(&samples);
+    let within_bound = |count: f64| count.clamp(1.0, upper_bound);
+
+    StateCountEstimate {
+        player_cells,
+        box_cells: if prunes_dead_squares && max_dead_boxes == 0 { live_cells } else { player_cells },
+        max_player_regions,
+        upper_bound,
+        sampled: median.min(upper_bound),
+        sampled_interval: (within_bound(lowest), within_bound(highest)),
+        probes,
+    }
+}
+
+/// One random walk from the start, the sum of its weights. `routes` holds the pushes a lone box
+/// needs from each box of the start to every cell, from [`lone_box_pushes`].
+fn probe(shared: &SharedGameState, start: &UniqueNode, routes: &[Vec<Option<u16>>], options: &ExplorationOptions, rng: &mut StdRng) -> f64 {
+    let layout = shared.bit_layout();
+    // every node on the path and every child of one, all no deeper than the current node
+    let mut seen = HashSet::new();
+    seen.insert(start.clone());
+    // where each box of the start is now, and how often it was pushed to get there
+    let mut boxes: Vec<(Vec2, u16)> = start.environment.iter_boxes().map(|&position| (position, 0)).collect();
+    let mut path = vec![start.clone()];
+    let mut pushes: Vec<Push> = Vec::new();
+    let mut weight = 1.0;
+    let mut total = weight;
+    while pushes.len() < MAX_PROBE_DEPTH {
+        let current = path.last().expect("the path starts with the start");
+        let on_shortest_route = |push: &Push| {
+            let pushed = boxes.iter().position(|&(position, _)| position == push.box_position)
+                .expect("every pushed box came from the start");
+            let destination = layout.index_of(push.box_destination()).expect("boxes stay inside the level");
+            routes[pushed][destination] == Some(boxes[pushed].1 + 1)
+        };
+        let children: Vec<(Push, UniqueNode)> = get_all_adjacent_pushes(current, shared, options).into_iter()
+            .filter(|(push, _, child)| !seen.contains(child) && on_shortest_route(push))
+            .map(|(push, _, child)| (push, child))
+            .collect();
+        if children.is_empty() {
+            break;
+        }
+        seen.extend(children.iter().map(|(_, child)| child.clone()));
+        let (push, child) = children[rng.random_range(0..children.len())].clone();
+        let pushed = boxes.iter_mut().find(|(position, _)| *position == push.box_position)
+            .expect("every pushed box came from the start");
+        *pushed = (push.box_destination(), pushed.1 + 1);
+        pushes.push(push);
+        let parents = 1 + count_commuted_parents(shared, &path, &pushes, &child, options);
+        weight *= children.len() as f64 / parents as f64;
+        total += weight;
+        path.push(child);
+    }
+    total
+}
+
+/// Parents of `child` as deep as its parent on the path, found by moving an earlier push of the
+/// path to the end. `pushes` lead from the start along `path` to `child`.
+fn count_commuted_parents(shared: &SharedGameState, path: &[UniqueNode], pushes: &[Push], child: &UniqueNode, options: &ExplorationOptions) -> usize {
+    let last = pushes.len() - 1;
+    let mut parents = 0;
+    for moved in last.saturating_sub(COMMUTE_WINDOW)..last {
+        let push = pushes[moved];
+        // only the last push of a box can move to the end
+        if pushes[moved + 1..].iter().any(|later| later.box_position == push.box_destination()) {
+            continue;
+        }
+        let parent = pushes[moved + 1..].iter()
+            .try_fold(path[moved].clone(), |node, &later| try_push(shared, &node, later, options));
+        let Some(parent) = parent else {
+            continue;
+        };
+        if try_push(shared, &parent, push, options).as_ref() == Some(child) {
+            parents += 1;
+        }
+    }
+    parents
+}
+
+/// The node after `push`, None when the player cannot make it
+fn try_push(shared: &SharedGameState, node: &UniqueNode, push: Push, options: &ExplorationOptions) -> Option<UniqueNode> {
+    let layout = shared.bit_layout();
+    let boxes = shared.box_board(&node.environment);
+    let destination = layout.index_of(push.box_destination())?;
+    if !shared.walkable_board().contains(destination) || boxes.contains(destination) {
+        return None;
+    }
+    let box_index = node.environment.index_of_box_at(&push.box_position)?;
+    let player = layout.index_of(push.player_position())?;
+    if !shared.reachable_board(node.minimum_reachable_player_position.into(), &boxes).contains(player) {
+        return None;
+    }
+    let mut environment = node.environment.clone();
+    environment.set_box(box_index, &push.box_destination());
+    environment.complete_moves(options);
+    Some(UniqueNode::from_game_state(GameState { player: push.box_position, environment }, shared))
+}
+
+/// Fewest pushes moving a lone box from `from` onto each cell, by layout index. The player is
+/// assumed to reach any side of the box, like [`crate::core::PushDistances`].
+fn lone_box_pushes(shared: &SharedGameState, from: Vec2) -> Vec<Option<u16>> {
+    let layout = shared.bit_layout();
+    let walkable = shared.walkable_board();
+    let is_floor = |position: Vec2| layout.index_of(position).is_some_and(|index| walkable.contains(index));
+    let mut pushes = vec![None; layout.index_count()];
+    let mut queue = VecDeque::new();
+    if let Some(index) = layout.index_of(from) {
+        pushes[index] = Some(0);
+        queue.push_back(from);
+    }
+    while let Some(position) = queue.pop_front() {
+        let next_pushes = pushes[layout.index_of(position).expect("queued cells are inside")].map(|count| count + 1);
+        for direction in Direction::ALL {
+            let destination = position + direction.offset();
+            if !is_floor(destination) || !is_floor(position - direction.offset()) {
+                continue;
+            }
+            let index = layout.index_of(destination).expect("floor is inside the level");
+            if pushes[index].is_none() {
+                pushes[index] = next_pushes;
+                queue.push_back(destination);
+            }
+        }
+    }
+    pushes
+}
+
+/// Median of the means of up to [`PROBE_GROUPS`] groups of samples, with the lowest and highest
+/// of those means
+fn median_of_means(samples: &[f64]) -> (f64, f64, f64) {
+    let mut means: Vec<f64> = samples.chunks(samples.len().div_ceil(PROBE_GROUPS))
+        .map(|group| group.iter().sum::<f64>() / group.len() as f64)
+        .collect();
+    means.sort_by(f64::total_cmp);
+    let middle = means.len() / 2;
+    let median = if means.len().is_multiple_of(2) { (means[middle - 1] + means[middle]) / 2.0 } else { means[middle] };
+    (median, means[0], means[means.len() - 1])
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+    let k = k.min(n - k);
+    (0..k).fold(1.0, |product, i| product * (n - i) as f64 / (i + 1) as f64)
+}
+
+fn factorial(n: usize) -> f64 {
+    (1..=n).map(|i| i as f64).product()
+}
+
+impl std::fmt::Display for StateCountEstimate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} player cells, {} box cells, at most {} player regions",
+                 self.player_cells, self.box_cells, self.max_player_regions)?;
+        writeln!(f, "Upper bound: {:.3e} states", self.upper_bound)?;
+        writeln!(f, "Sampled from {} probes: {:.3e} states, group means {:.3e} to {:.3e}",
+                 self.probes, self.sampled, self.sampled_interval.0, self.sampled_interval.1)
+    }
+}
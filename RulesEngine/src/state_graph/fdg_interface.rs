@@ -1,4 +1,4 @@
-use crate::state_graph::StateGraph;
+use crate::state_graph::{NodeId, StateGraph};
 use grapher::renderer::Renderer;
 use grapher::simulator::SimulatorBuilder;
 use petgraph::Directed;
@@ -21,10 +21,10 @@ pub fn render_interactive_graph(graph: &StateGraph) {
 pub fn convert_to_petgraph(graph: &StateGraph) -> petgraph::Graph<(), (), Directed> {
     let mut petgraph = petgraph::Graph::new();
 
-    let node_map: std::collections::HashMap<usize, petgraph::graph::NodeIndex> = graph
+    let node_map: std::collections::HashMap<NodeId, petgraph::graph::NodeIndex> = graph
         .nodes
-        .iter()
-        .map(|(_, &node_id)| {
+        .ids()
+        .map(|node_id| {
             let index = petgraph.add_node(());
             (node_id, index)
         })
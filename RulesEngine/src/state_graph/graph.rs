@@ -1,43 +1,66 @@
-use crate::state_graph::models::{Edge, StateGraph};
-use std::collections::{HashSet, VecDeque};
-use crate::state_graph::UniqueNode;
+use std::time::Duration;
+use crate::core::{Budget, ExplorationOptions, SearchOrder, Vec2};
+use crate::state_graph::models::{Edge, NodeId, Push, StateGraph};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet, VecDeque};
+use crate::state_graph::{EdgeStore, NodeStore, OutOfNodeIds, UniqueNode};
 
 impl StateGraph {
     pub fn new() -> Self {
         StateGraph {
-            nodes: bimap::BiMap::new(),
-            edges: HashSet::new(),
+            nodes: NodeStore::new(),
+            edges: EdgeStore::new(),
             unvisited: HashSet::new(),
             next_unvisted: VecDeque::new(),
-            next_id: 0,
+            scored_unvisited: BinaryHeap::new(),
+            search_order: SearchOrder::default(),
+            reduce_symmetry: false,
         }
     }
 
-    pub fn upsert_state(&mut self, state: UniqueNode) -> usize {
-        if let Some(&id) = self.nodes.get_by_left(&state) {
-            id
-        } else {
-            let id = self.next_id;
-            self.next_id += 1;
+    /// Panics when the node is new and [`NodeStore::ids_left`] is zero, exploring checks first
+    pub fn upsert_state(&mut self, state: UniqueNode) -> NodeId {
+        let (id, inserted) = self.nodes.find_or_insert(state).expect("exploring checks the ids left before inserting");
+        if inserted {
+            self.unvisited.insert(id);
+            self.next_unvisted.push_back(id);
+        }
+        id
+    }
 
-            // we know that this insertion is unique, because id is unique, and we just checked to ensure that te state is unique
-            self.nodes.insert_no_overwrite(state, id).unwrap();
+    /// Adds nodes not in the graph yet as unvisited, see [`crate::state_graph::NodeStore::append_new`]
+    pub fn append_new_states(&mut self, nodes: Vec<(u64, UniqueNode)>) -> Result<(), OutOfNodeIds> {
+        let first_id = self.nodes.next_id();
+        self.nodes.append_new(nodes)?;
+        for id in first_id..self.nodes.next_id() {
             self.unvisited.insert(id);
             self.next_unvisted.push_back(id);
-            id
         }
+        Ok(())
     }
 
-    pub fn get_state(&self, id: usize) -> Option<&UniqueNode> {
-        self.nodes.get_by_right(&id)
+    pub fn get_state(&self, id: NodeId) -> Option<&UniqueNode> {
+        self.nodes.get(id)
     }
 
     pub fn add_edge(&mut self, edge: Edge) {
         self.edges.insert(edge);
     }
 
-    pub fn take_and_visit_unvisited_node(&mut self) -> Option<usize> {
-        while let Some(node_id) = self.next_unvisted.pop_front() {
+    /// Moves the edges into adjacency lists, done once exploration ends
+    pub fn compact_edges(&mut self) {
+        self.edges.compact(self.nodes.next_id());
+    }
+
+    /// Puts back a node taken with [`StateGraph::take_and_visit_unvisited_node`] without expanding it
+    pub fn return_unvisited(&mut self, id: NodeId) {
+        if self.unvisited.insert(id) {
+            self.next_unvisted.push_front(id);
+        }
+    }
+
+    pub fn take_and_visit_unvisited_node(&mut self, order: SearchOrder) -> Option<NodeId> {
+        while let Some(node_id) = self.take_next_unvisited(order) {
             if self.unvisited.remove(&node_id) {
                 return Some(node_id);
             }
@@ -45,8 +68,90 @@ impl StateGraph {
         None
     }
 
+    fn take_next_unvisited(&mut self, order: SearchOrder) -> Option<NodeId> {
+        if order != SearchOrder::BestFirst {
+            self.unscore_unvisited();
+        }
+        match order {
+            SearchOrder::BreadthFirst => self.next_unvisted.pop_front(),
+            SearchOrder::DepthFirst => self.next_unvisted.pop_back(),
+            SearchOrder::BestFirst => self.scored_unvisited.pop()
+                .map(|Reverse((_, id))| id)
+                .or_else(|| self.next_unvisted.pop_front()),
+            SearchOrder::Random(seed) => {
+                if self.next_unvisted.is_empty() {
+                    return None;
+                }
+                // derived from the visited count rather than kept, so a resumed graph continues the same way
+                let visited = (self.nodes.len() - self.unvisited.len()) as u64;
+                let index = split_mix(seed ^ visited) % self.next_unvisted.len() as u64;
+                self.next_unvisted.swap_remove_back(index as usize)
+            }
+        }
+    }
+
+    /// Moves the nodes queued since the last call into `scored_unvisited`
+    pub fn score_unvisited(&mut self, score: impl Fn(&UniqueNode) -> u32) {
+        while let Some(id) = self.next_unvisted.pop_front() {
+            if let Some(node) = self.nodes.get(id) {
+                self.scored_unvisited.push(Reverse((score(node), id)));
+            }
+        }
+    }
+
+    /// Moves the nodes scored while going best first back to the front of the queue, best first,
+    /// so other orders still expand them
+    fn unscore_unvisited(&mut self) {
+        if self.scored_unvisited.is_empty() {
+            return;
+        }
+        // sorted worst first, each pushed in front of the last
+        for Reverse((_, id)) in std::mem::take(&mut self.scored_unvisited).into_sorted_vec() {
+            self.next_unvisted.push_front(id);
+        }
+    }
+
+    /// Every node waiting to be expanded, in no particular order
+    pub fn iter_frontier(&self) -> impl Iterator<Item=NodeId> + '_ {
+        self.next_unvisted.iter().copied()
+            .chain(self.scored_unvisited.iter().map(|Reverse((_, id))| *id))
+    }
+
+    /// Roughly the bytes the graph takes, for [`ExplorationOptions::max_memory`]
+    pub fn memory_bytes(&self) -> usize {
+        self.nodes.memory_bytes()
+            + self.edges.memory_bytes()
+            + self.unvisited.capacity() * (size_of::<NodeId>() + 1)
+            + self.next_unvisted.capacity() * size_of::<NodeId>()
+            + self.scored_unvisited.capacity() * size_of::<(u32, NodeId)>()
+    }
+
+    /// The first budget of `options` the graph has used up, after exploring for `elapsed`
+    pub fn exhausted_budget(&self, options: &ExplorationOptions, elapsed: Duration) -> Option<Budget> {
+        options.exhausted_budget(self.nodes.len(), self.edges.len(), self.memory_bytes(), elapsed)
+    }
+
     pub fn assert_all_visited(&self) {
         assert!(self.unvisited.is_empty());
         assert!(self.next_unvisted.is_empty());
+        assert!(self.scored_unvisited.is_empty());
+    }
+}
+
+fn split_mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+impl Push {
+    /// Where the player stands to make the push. After the push the player is at `box_position`.
+    pub fn player_position(&self) -> Vec2 {
+        self.box_position - self.direction.offset()
+    }
+
+    pub fn box_destination(&self) -> Vec2 {
+        self.box_position + self.direction.offset()
     }
 }
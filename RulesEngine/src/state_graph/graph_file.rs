@@ -0,0 +1,389 @@
+use std::collections::{HashSet, VecDeque};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use bevy::math::IVec2;
+use crate::core::{Cell, Direction, ExplorationOptions, GameState, GameStateEnvironment, SearchOrder, SharedGameState};
+use crate::state_graph::{canonicalize, Edge, NodeId, Push, StateGraph, UniqueNode};
+
+/// A populated graph along with the level it was populated from, as read from a graph file
+pub struct SavedStateGraph {
+    pub graph: StateGraph,
+    pub shared: SharedGameState,
+    pub initial_id: NodeId,
+}
+
+#[derive(Debug)]
+pub enum GraphFileError {
+    Io(std::io::Error),
+    /// The file does not start with the graph file header
+    NotAGraphFile,
+    UnsupportedVersion(u32),
+    /// The file is truncated or holds values which cannot belong to a graph
+    Corrupt(String),
+    /// The file was generated from a different level than the one it is being loaded for
+    LevelMismatch(String),
+}
+
+const MAGIC: &[u8; 4] = b"SOKG";
+const VERSION: u32 = 5;
+
+/// Writes the graph in a compact binary format. Ids are stored as u32, and every node stores
+/// only its player position and box positions, as single bytes.
+///
+/// The format, all numbers little endian:
+/// - header: `SOKG`, version u32
+/// - level: height u16, width u16, one byte per cell
+/// - initial id u32, next id u32, box count u16
+/// - search order u8, random seed u64, symmetry reduced u8
+/// - nodes: count u64, then per node id u32, player (i8, i8), boxes (i8, i8)...
+/// - edges: count u64, then per edge from u32, to u32, pushed box (i8, i8), direction u8, walk u16
+/// - unvisited ids: count u64, then ids u32
+/// - next unvisited queue: count u64, then ids u32 in queue order, followed by the scored ids of
+///   best first, which are scored again when exploring continues
+pub fn write_state_graph(writer: impl Write, graph: &StateGraph, shared: &SharedGameState, initial_id: NodeId) -> Result<(), GraphFileError> {
+    let mut w = BufWriter::new(writer);
+    w.write_all(MAGIC)?;
+    write_u32(&mut w, VERSION)?;
+
+    write_u16(&mut w, shared.height() as u16)?;
+    write_u16(&mut w, shared.width() as u16)?;
+    for cell in shared.grid.iter().flat_map(|row| row.iter()) {
+        w.write_all(&[cell_to_byte(*cell)])?;
+    }
+
+    let box_count = graph.nodes.values().next()
+        .map(|node| node.environment.box_count())
+        .unwrap_or(0);
+    write_u32(&mut w, initial_id)?;
+    write_u32(&mut w, graph.nodes.next_id())?;
+    write_u16(&mut w, box_count as u16)?;
+    let (order, seed) = search_order_to_bytes(graph.search_order);
+    w.write_all(&[order])?;
+    write_u64(&mut w, seed)?;
+    w.write_all(&[graph.reduce_symmetry as u8])?;
+
+    write_u64(&mut w, graph.nodes.len() as u64)?;
+    for (id, node) in graph.nodes.iter() {
+        if node.environment.box_count() != box_count {
+            return Err(GraphFileError::Corrupt(format!("node {} has {} boxes, expected {}", id, node.environment.box_count(), box_count)));
+        }
+        write_u32(&mut w, id)?;
+        let player = node.minimum_reachable_player_position;
+        w.write_all(&[player.y as i8 as u8, player.x as i8 as u8])?;
+        for b in node.environment.iter_boxes() {
+            w.write_all(&[b.i as u8, b.j as u8])?;
+        }
+    }
+
+    write_u64(&mut w, graph.edges.len() as u64)?;
+    for edge in graph.edges.iter() {
+        write_u32(&mut w, edge.from)?;
+        write_u32(&mut w, edge.to)?;
+        let push = edge.push;
+        w.write_all(&[push.box_position.i as u8, push.box_position.j as u8, direction_to_byte(push.direction)])?;
+        write_u16(&mut w, edge.walk)?;
+    }
+
+    write_u64(&mut w, graph.unvisited.len() as u64)?;
+    for &id in graph.unvisited.iter() {
+        write_u32(&mut w, id)?;
+    }
+
+    write_u64(&mut w, (graph.next_unvisted.len() + graph.scored_unvisited.len()) as u64)?;
+    for id in graph.iter_frontier() {
+        write_u32(&mut w, id)?;
+    }
+
+    w.flush()?;
+    Ok(())
+}
+
+pub fn read_state_graph(reader: impl Read) -> Result<SavedStateGraph, GraphFileError> {
+    let mut r = BufReader::new(reader);
+    let mut magic = [0u8; 4];
+    r.read_exact(&mut magic).map_err(|_| GraphFileError::NotAGraphFile)?;
+    if &magic != MAGIC {
+        return Err(GraphFileError::NotAGraphFile);
+    }
+    let version = read_u32(&mut r)?;
+    if version != VERSION {
+        return Err(GraphFileError::UnsupportedVersion(version));
+    }
+
+    let height = read_u16(&mut r)? as usize;
+    let width = read_u16(&mut r)? as usize;
+    if height > i8::MAX as usize || width > i8::MAX as usize {
+        return Err(GraphFileError::Corrupt(format!("level size {}x{} is too large", width, height)));
+    }
+    let mut grid = Vec::with_capacity(height);
+    for _ in 0..height {
+        let mut row = Vec::with_capacity(width);
+        for _ in 0..width {
+            row.push(cell_from_byte(read_u8(&mut r)?)?);
+        }
+        grid.push(row);
+    }
+    let shared = SharedGameState::new(grid);
+
+    let initial_id = read_u32(&mut r)?;
+    let next_id = read_u32(&mut r)?;
+    let box_count = read_u16(&mut r)? as usize;
+    if box_count > GameStateEnvironment::MAX_BOXES {
+        return Err(GraphFileError::Corrupt(format!("{} boxes, at most {} are supported", box_count, GameStateEnvironment::MAX_BOXES)));
+    }
+    let order = read_u8(&mut r)?;
+    let seed = read_u64(&mut r)?;
+    let reduce_symmetry = read_u8(&mut r)?;
+
+    let mut graph = StateGraph::new();
+    graph.search_order = search_order_from_bytes(order, seed)?;
+    graph.reduce_symmetry = match reduce_symmetry {
+        0 => false,
+        1 => true,
+        other => return Err(GraphFileError::Corrupt(format!("unknown symmetry reduction {}", other))),
+    };
+
+    let node_count = read_u64(&mut r)?;
+    for _ in 0..node_count {
+        let id = read_u32(&mut r)?;
+        let player = read_position(&mut r)?;
+        let boxes = (0..box_count)
+            .map(|_| read_position(&mut r))
+            .collect::<Result<Vec<_>, _>>()?;
+        let node = UniqueNode {
+            // kept in the order written, boxes are only sorted when exploration deduplicates them
+            environment: GameStateEnvironment::from_ordered(boxes),
+            minimum_reachable_player_position: player,
+        };
+        if id >= next_id {
+            return Err(GraphFileError::Corrupt(format!("node id {} is not below next id {}", id, next_id)));
+        }
+        if !graph.nodes.insert_at(id, node) {
+            return Err(GraphFileError::Corrupt(format!("node {} is duplicated", id)));
+        }
+    }
+
+    graph.nodes.extend_ids_to(next_id);
+
+    let edge_count = read_u64(&mut r)?;
+    for _ in 0..edge_count {
+        let from = read_node_id(&mut r, &graph)?;
+        let to = read_node_id(&mut r, &graph)?;
+        let box_position = read_position(&mut r)?.into();
+        let direction = direction_from_byte(read_u8(&mut r)?)?;
+        let walk = read_u16(&mut r)?;
+        graph.edges.insert(Edge { from, to, push: Push { box_position, direction }, walk });
+    }
+    graph.compact_edges();
+
+    let unvisited_count = read_u64(&mut r)?;
+    graph.unvisited = HashSet::new();
+    for _ in 0..unvisited_count {
+        graph.unvisited.insert(read_node_id(&mut r, &graph)?);
+    }
+
+    let queue_count = read_u64(&mut r)?;
+    graph.next_unvisted = VecDeque::new();
+    for _ in 0..queue_count {
+        let id = read_node_id(&mut r, &graph)?;
+        graph.next_unvisted.push_back(id);
+    }
+
+    if graph.get_state(initial_id).is_none() {
+        return Err(GraphFileError::Corrupt(format!("initial node {} is missing", initial_id)));
+    }
+
+    Ok(SavedStateGraph {
+        graph,
+        shared,
+        initial_id,
+    })
+}
+
+/// Writes to a temporary file first and then moves it over `path`, so an interrupted save never
+/// destroys a previous save at the same path.
+pub fn save_state_graph(path: &Path, graph: &StateGraph, shared: &SharedGameState, initial_id: NodeId) -> Result<(), GraphFileError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let file = std::fs::File::create(&temp_path)?;
+    write_state_graph(&file, graph, shared, initial_id)?;
+    file.sync_all()?;
+    std::fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+/// Loads a graph file, checking it was generated from the level described by `shared` and `initial_state`
+pub fn load_state_graph(path: &Path, shared: &SharedGameState, initial_state: &GameState) -> Result<SavedStateGraph, GraphFileError> {
+    let file = std::fs::File::open(path)?;
+    let saved = read_state_graph(file)?;
+    saved.check_level(shared, initial_state)?;
+    Ok(saved)
+}
+
+impl SavedStateGraph {
+    /// Checks the graph was populated from this level. The walls and targets must be identical,
+    /// and the initial node must be the one produced by the initial state, or its representative
+    /// when the graph was reduced by symmetry.
+    pub fn check_level(&self, shared: &SharedGameState, initial_state: &GameState) -> Result<(), GraphFileError> {
+        if self.shared.grid != shared.grid {
+            return Err(GraphFileError::LevelMismatch(format!(
+                "the graph was generated for a {}x{} level with different walls or targets",
+                self.shared.width(), self.shared.height())));
+        }
+        let initial_node = UniqueNode::from_game_state(initial_state.clone(), shared);
+        let stored = self.graph.get_state(self.initial_id).cloned();
+        let matches = if self.graph.reduce_symmetry {
+            // the file does not record whether boxes were told apart, so both sides sort them
+            let options = ExplorationOptions::default();
+            let representative = |mut node: UniqueNode| {
+                node.environment.complete_moves(&options);
+                canonicalize(shared, node, &options).0
+            };
+            stored.is_some_and(|stored| representative(stored) == representative(initial_node))
+        } else {
+            stored == Some(initial_node)
+        };
+        if !matches {
+            return Err(GraphFileError::LevelMismatch(
+                "the graph was generated from a different starting position".to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl From<std::io::Error> for GraphFileError {
+    fn from(value: std::io::Error) -> Self {
+        if value.kind() == std::io::ErrorKind::UnexpectedEof {
+            GraphFileError::Corrupt("file ended early".to_string())
+        } else {
+            GraphFileError::Io(value)
+        }
+    }
+}
+
+impl std::fmt::Display for GraphFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GraphFileError::Io(e) => write!(f, "{}", e),
+            GraphFileError::NotAGraphFile => write!(f, "not a state graph file"),
+            GraphFileError::UnsupportedVersion(version) =>
+                write!(f, "state graph file version {} is not supported, expected {}", version, VERSION),
+            GraphFileError::Corrupt(reason) => write!(f, "state graph file is corrupt: {}", reason),
+            GraphFileError::LevelMismatch(reason) => write!(f, "state graph file does not match the level: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for GraphFileError {}
+
+fn cell_to_byte(cell: Cell) -> u8 {
+    match cell {
+        Cell::Wall => 0,
+        Cell::Floor => 1,
+        Cell::Target => 2,
+    }
+}
+
+fn cell_from_byte(byte: u8) -> Result<Cell, GraphFileError> {
+    match byte {
+        0 => Ok(Cell::Wall),
+        1 => Ok(Cell::Floor),
+        2 => Ok(Cell::Target),
+        other => Err(GraphFileError::Corrupt(format!("unknown cell {}", other))),
+    }
+}
+
+pub(super) fn direction_to_byte(direction: Direction) -> u8 {
+    match direction {
+        Direction::Up => 0,
+        Direction::Down => 1,
+        Direction::Left => 2,
+        Direction::Right => 3,
+    }
+}
+
+pub(super) fn direction_from_byte(byte: u8) -> Result<Direction, GraphFileError> {
+    match byte {
+        0 => Ok(Direction::Up),
+        1 => Ok(Direction::Down),
+        2 => Ok(Direction::Left),
+        3 => Ok(Direction::Right),
+        other => Err(GraphFileError::Corrupt(format!("unknown direction {}", other))),
+    }
+}
+
+fn search_order_to_bytes(order: SearchOrder) -> (u8, u64) {
+    match order {
+        SearchOrder::BreadthFirst => (0, 0),
+        SearchOrder::DepthFirst => (1, 0),
+        SearchOrder::BestFirst => (2, 0),
+        SearchOrder::Random(seed) => (3, seed),
+    }
+}
+
+fn search_order_from_bytes(order: u8, seed: u64) -> Result<SearchOrder, GraphFileError> {
+    match order {
+        0 => Ok(SearchOrder::BreadthFirst),
+        1 => Ok(SearchOrder::DepthFirst),
+        2 => Ok(SearchOrder::BestFirst),
+        3 => Ok(SearchOrder::Random(seed)),
+        other => Err(GraphFileError::Corrupt(format!("unknown search order {}", other))),
+    }
+}
+
+fn write_u16(w: &mut impl Write, value: u16) -> Result<(), GraphFileError> {
+    w.write_all(&value.to_le_bytes())?;
+    Ok(())
+}
+
+fn write_u32(w: &mut impl Write, value: u32) -> Result<(), GraphFileError> {
+    w.write_all(&value.to_le_bytes())?;
+    Ok(())
+}
+
+fn write_u64(w: &mut impl Write, value: u64) -> Result<(), GraphFileError> {
+    w.write_all(&value.to_le_bytes())?;
+    Ok(())
+}
+
+fn read_u8(r: &mut impl Read) -> Result<u8, GraphFileError> {
+    let mut buf = [0u8; 1];
+    r.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u16(r: &mut impl Read) -> Result<u16, GraphFileError> {
+    let mut buf = [0u8; 2];
+    r.read_exact(&mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u32(r: &mut impl Read) -> Result<u32, GraphFileError> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(r: &mut impl Read) -> Result<u64, GraphFileError> {
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_position(r: &mut impl Read) -> Result<IVec2, GraphFileError> {
+    let i = read_u8(r)? as i8;
+    let j = read_u8(r)? as i8;
+    Ok(IVec2 { x: j as i32, y: i as i32 })
+}
+
+/// Reads an id which must refer to a node already read
+fn read_node_id(r: &mut impl Read, graph: &StateGraph) -> Result<NodeId, GraphFileError> {
+    let id = read_u32(r)?;
+    if graph.get_state(id).is_none() {
+        return Err(GraphFileError::Corrupt(format!("reference to unknown node {}", id)));
+    }
+    Ok(id)
+}
@@ -1,6 +1,6 @@
-﻿use std::collections::{HashMap, HashSet};
+﻿use std::collections::HashSet;
 use crate::core::SharedGameState;
-use crate::state_graph::StateGraph;
+use crate::state_graph::{NodeId, StateGraph};
 
 #[derive(Debug)]
 pub struct TrimStats {
@@ -38,13 +38,16 @@ impl TrimStats {
 
 // TODO: populate metadata related to how many unwinnable states are adjacent to every winnable state
 //  that is, how many moves are available which will softlock the level
+/// Removes every node which cannot reach a won state. On a partial graph the unvisited frontier
+/// may still lead to a win, so it is kept along with every node reaching it.
 pub fn trim_unwinnable(graph: &mut StateGraph, shared: &SharedGameState) -> TrimStats {
+    graph.compact_edges();
     let win_checker = shared.get_won_check_helper();
-    let initial_winning_states: Vec<usize> = graph
+    let initial_winning_states: Vec<NodeId> = graph
         .nodes
         .iter()
-        .filter_map(|(node, &node_id)| {
-            if win_checker.is_won(&node.environment) {
+        .filter_map(|(node_id, node)| {
+            if win_checker.is_won(&node.environment) || graph.unvisited.contains(&node_id) {
                 Some(node_id)
             } else {
                 None
@@ -52,10 +55,6 @@ pub fn trim_unwinnable(graph: &mut StateGraph, shared: &SharedGameState) -> Trim
         })
         .collect();
 
-    let mut edge_map_successor_to_predecessors: HashMap<usize, HashSet<usize>> = HashMap::new();
-    for edge in &graph.edges {
-        edge_map_successor_to_predecessors.entry(edge.to).or_default().insert(edge.from);
-    }
     let mut winning_states = HashSet::new();
 
     let mut stack = initial_winning_states.clone();
@@ -64,17 +63,15 @@ pub fn trim_unwinnable(graph: &mut StateGraph, shared: &SharedGameState) -> Trim
             continue;
         }
 
-        if let Some(predecessors) = edge_map_successor_to_predecessors.get(&next) {
-            for &pred in predecessors {
-                stack.push(pred);
-            }
+        for &(pred, _, _) in graph.edges.incoming(next) {
+            stack.push(pred);
         }
     }
 
     let total_nodes = graph.nodes.len();
     let total_edges = graph.edges.len();
-    graph.nodes.retain(|_, node_id| winning_states.contains(node_id));
-    graph.edges.retain(|edge| winning_states.contains(&edge.from) && winning_states.contains(&edge.to));
+    graph.nodes.retain(|node_id, _| winning_states.contains(&node_id));
+    graph.edges.retain(graph.nodes.next_id(), |edge| winning_states.contains(&edge.from) && winning_states.contains(&edge.to));
 
     TrimStats {
         nodes_before: total_nodes,
@@ -0,0 +1,133 @@
+use std::collections::HashSet;
+use crate::core::{DeadlockDetector, DeadlockDetectors, DetectorStage, ExplorationOptions, GameState, PushedState, SharedGameState};
+use crate::state_graph::{populate_step, trim_unwinnable, NodeId, PopulateResult, StateGraph, UniqueNode};
+
+/// How one detector did on every state of a level, against the ground truth from the full graph
+#[derive(Debug)]
+pub struct DetectorAudit {
+    pub name: &'static str,
+    /// Winnable states the detector claims are deadlocked. Each one is a soundness bug, pruning
+    /// them deletes real solutions.
+    pub false_positives: Vec<UniqueNode>,
+    /// Unwinnable states the detector recognises
+    pub caught: usize,
+    /// Unwinnable states in the graph, caught or not
+    pub unwinnable: usize,
+}
+
+/// Result of [`audit_heuristics`]
+#[derive(Debug)]
+pub struct HeuristicAudit {
+    pub total_nodes: usize,
+    pub winnable_nodes: usize,
+    pub detectors: Vec<DetectorAudit>,
+}
+
+impl DetectorAudit {
+    pub fn is_sound(&self) -> bool {
+        self.false_positives.is_empty()
+    }
+
+    /// Fraction of the unwinnable states the detector catches, 1 when there are none
+    pub fn caught_fraction(&self) -> f64 {
+        if self.unwinnable == 0 {
+            1.0
+        } else {
+            self.caught as f64 / self.unwinnable as f64
+        }
+    }
+}
+
+impl HeuristicAudit {
+    pub fn is_sound(&self) -> bool {
+        self.detectors.iter().all(DetectorAudit::is_sound)
+    }
+}
+
+/// Checks every detector in `detectors` against the truth. Explores the whole level without any
+/// pruning, finds the winnable states with [`trim_unwinnable`], then runs each detector on its
+/// own over every state.
+///
+/// State detectors look at every node. Push detectors look at every edge, and a node counts as
+/// flagged when the detector flags any push leading to it.
+pub fn audit_heuristics(shared: &SharedGameState, start: &GameState, detectors: &DeadlockDetectors) -> HeuristicAudit {
+    let mut graph = StateGraph::new();
+    graph.upsert_state(UniqueNode::from_game_state(start.clone(), shared));
+    let exhaustive = ExplorationOptions::exhaustive();
+    while let PopulateResult::Populated = populate_step(&mut graph, shared, &exhaustive) {}
+
+    let mut trimmed = graph.clone();
+    trim_unwinnable(&mut trimmed, shared);
+    let winnable: HashSet<NodeId> = trimmed.nodes.ids().collect();
+    let unwinnable = graph.nodes.len() - winnable.len();
+
+    let detectors = detectors.iter()
+        .map(|detector| {
+            let flagged = flagged_nodes(&graph, shared, detector);
+            let mut false_positives: Vec<UniqueNode> = flagged.iter()
+                .filter(|id| winnable.contains(id))
+                .filter_map(|&id| graph.get_state(id).cloned())
+                .collect();
+            // ids depend on the exploration order, sorting keeps reports comparable
+            false_positives.sort_by_key(|node| (node.environment.iter_boxes().copied().collect::<Vec<_>>(), node.minimum_reachable_player_position.to_array()));
+            DetectorAudit {
+                name: detector.name(),
+                caught: flagged.len() - false_positives.len(),
+                false_positives,
+                unwinnable,
+            }
+        })
+        .collect();
+
+    HeuristicAudit {
+        total_nodes: graph.nodes.len(),
+        winnable_nodes: winnable.len(),
+        detectors,
+    }
+}
+
+fn flagged_nodes(graph: &StateGraph, shared: &SharedGameState, detector: &dyn DeadlockDetector) -> HashSet<NodeId> {
+    match detector.stage() {
+        DetectorStage::State => graph.nodes.iter()
+            .filter(|(_, node)| {
+                let game = GameState {
+                    player: node.minimum_reachable_player_position.into(),
+                    environment: node.environment.clone(),
+                };
+                detector.is_state_deadlocked(shared, &game)
+            })
+            .map(|(id, _)| id)
+            .collect(),
+        DetectorStage::Push => graph.edges.iter()
+            .filter(|edge| {
+                let Some(node) = graph.get_state(edge.to) else {
+                    return false;
+                };
+                let pushed = PushedState {
+                    environment: &node.environment,
+                    boxes: &shared.box_board(&node.environment),
+                    moved_box: edge.push.box_destination(),
+                };
+                detector.is_push_deadlocked(shared, &pushed)
+            })
+            .map(|edge| edge.to)
+            .collect(),
+    }
+}
+
+impl std::fmt::Display for HeuristicAudit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} states, {} winnable and {} unwinnable",
+                 self.total_nodes, self.winnable_nodes, self.total_nodes - self.winnable_nodes)?;
+        for detector in &self.detectors {
+            write!(f, "{}: caught {} of {} unwinnable ({:.1}%), {} false positives",
+                   detector.name, detector.caught, detector.unwinnable, detector.caught_fraction() * 100.0,
+                   detector.false_positives.len())?;
+            if !detector.is_sound() {
+                write!(f, " UNSOUND")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use crate::core::{SharedGameState};
 use serde::{Deserialize, Serialize};
-use crate::state_graph::StateGraph;
+use crate::state_graph::{NodeId, StateGraph};
 
 #[derive(Serialize, Deserialize, Debug)]
 struct JsonData {
@@ -10,22 +10,25 @@ struct JsonData {
 
 #[derive(Serialize, Deserialize, Debug)]
 struct JsonNode {
-    id: usize,
+    id: NodeId,
     on_targets: usize,
+    /// On the frontier of a partial graph
+    unexpanded: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 struct JsonEdge {
-    source: usize,
-    target: usize,
+    source: NodeId,
+    target: NodeId,
 }
 
 pub fn get_json_data(graph: &StateGraph, shared: &SharedGameState) -> String {
-    let nodes: Vec<JsonNode> = graph.nodes.iter().map(|(state, id)| {
+    let nodes: Vec<JsonNode> = graph.nodes.iter().map(|(id, state)| {
         let on_targets = shared.count_boxes_on_goals(&state.environment);
         JsonNode {
-            id: *id,
+            id,
             on_targets,
+            unexpanded: graph.unvisited.contains(&id),
         }
     }).collect();
 
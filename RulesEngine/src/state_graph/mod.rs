@@ -1,16 +1,36 @@
 mod console_interface;
 mod fdg_interface;
 mod graph;
+mod node_store;
+mod edge_store;
 mod json_export;
 mod models;
 mod populate;
+mod parallel_populate;
+mod disk_populate;
 mod graph_trim;
 mod unique_node;
+mod graph_file;
+mod solution;
+mod heuristic_audit;
+mod symmetry_reduction;
+mod estimate;
+mod reverse_populate;
 
 pub use console_interface::*;
 pub use fdg_interface::render_interactive_graph;
 pub use json_export::get_json_data;
 pub use models::*;
+pub use node_store::{NodeStore, OutOfNodeIds};
+pub use edge_store::EdgeStore;
 pub use unique_node::*;
-pub use populate::{populate_step, get_all_adjacent_nodes};
-pub use graph_trim::trim_unwinnable;
\ No newline at end of file
+pub use populate::{add_start, populate_step, populate_all, populate_node, take_next_node, get_all_adjacent_nodes, get_all_adjacent_pushes};
+pub use parallel_populate::populate_parallel_step;
+pub use disk_populate::{read_edge_list, DiskExplorationStats, DiskExplorer};
+pub use graph_trim::trim_unwinnable;
+pub use graph_file::{write_state_graph, read_state_graph, save_state_graph, load_state_graph, SavedStateGraph, GraphFileError};
+pub use solution::{find_push_optimal_path, find_push_optimal_solution, find_move_optimal_solution, push_optimal_solution, solution_to_lurd, Solution};
+pub use heuristic_audit::{audit_heuristics, DetectorAudit, HeuristicAudit};
+pub use symmetry_reduction::{canonicalize, expand_symmetric_graph, expand_symmetric_path, transform_node, transform_push};
+pub use estimate::{estimate_state_count, StateCountEstimate, DEFAULT_PROBES};
+pub use reverse_populate::{get_all_pulls, goal_nodes, pull_all, populate_winnable};
@@ -1,23 +1,57 @@
-use std::collections::{HashSet, VecDeque};
-use crate::state_graph::unique_node::UniqueNode;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet, VecDeque};
+use crate::core::{Budget, Direction, SearchOrder, Vec2};
+use crate::state_graph::{EdgeStore, NodeStore};
+
+/// Ids are u32 to keep nodes and edges small, graphs run into the billions of bytes long before
+/// they run out of ids
+pub type NodeId = u32;
 
 #[derive(Clone)]
 pub struct StateGraph {
-    // map from game state to node id
-    pub nodes: bimap::BiMap<UniqueNode, usize>,
-    pub edges: HashSet<Edge>,
-    pub unvisited: HashSet<usize>,
-    pub next_unvisted: VecDeque<usize>,
-    pub next_id: usize,
+    pub nodes: NodeStore,
+    /// Compacted once exploration is done, see [`EdgeStore::compact`]
+    pub edges: EdgeStore,
+    pub unvisited: HashSet<NodeId>,
+    pub next_unvisted: VecDeque<NodeId>,
+    /// Unvisited nodes by lowest score first, for [`SearchOrder::BestFirst`]. Filled from
+    /// `next_unvisted` by [`StateGraph::score_unvisited`].
+    pub scored_unvisited: BinaryHeap<Reverse<(u32, NodeId)>>,
+    /// The order the graph was last expanded in
+    pub search_order: SearchOrder,
+    /// Whether each node stands for its mirrors and rotations too, see
+    /// [`crate::core::ExplorationOptions::reduce_symmetry`]
+    pub reduce_symmetry: bool,
 }
 
-#[derive(Hash, Eq, PartialEq, Clone)]
+/// A single push leading from one node to another, along with the walk before it.
+///
+/// Nodes only record the minimum reachable player position, so `walk` counts the steps from
+/// there to where the player stands for the push, which is where exploration puts the player.
+/// The player actually stands wherever the previous push left them, so queries following the
+/// player, such as [`crate::state_graph::find_move_optimal_solution`], only take `walk` as it
+/// is while the player stands on the minimum reachable position.
+#[derive(Hash, Eq, PartialEq, Clone, Copy, Debug)]
+#[derive(PartialOrd, Ord)]
 pub struct Edge {
-    pub from: usize,
-    pub to: usize,
+    pub from: NodeId,
+    pub to: NodeId,
+    pub push: Push,
+    pub walk: u16,
+}
+
+#[derive(Hash, Eq, PartialEq, Clone, Copy, Debug)]
+#[derive(PartialOrd, Ord)]
+pub struct Push {
+    /// Position of the box before it is pushed
+    pub box_position: Vec2,
+    pub direction: Direction,
 }
 
+#[derive(Debug, PartialEq, Eq)]
 pub enum PopulateResult {
     AllVisited,
     Populated,
+    /// Stopped with unvisited nodes left, which stay in the graph as its frontier
+    BudgetExhausted(Budget),
 }
@@ -0,0 +1,284 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, BuildHasherDefault, Hasher, RandomState};
+use std::thread;
+use crate::state_graph::{NodeId, UniqueNode};
+
+/// Parts the hash index is split into by hash, so they can be filled on several threads
+pub(crate) const INDEX_SHARDS: usize = 16;
+
+/// Nodes in an arena indexed by their id, with a hash index to find the id of a node.
+///
+/// Each node is stored once. The index maps the hash of a node to its id and compares against
+/// the arena, so a node costs its own size plus a few bytes, instead of being stored twice.
+/// Removed nodes leave a hole, ids are never reused.
+#[derive(Clone)]
+pub struct NodeStore {
+    /// Indexed by id, None for removed nodes
+    arena: Vec<Option<UniqueNode>>,
+    /// [`INDEX_SHARDS`] parts of the index, a hash goes in the one [`shard_of`] picks
+    shards: Vec<IndexShard>,
+    hasher: RandomState,
+    len: usize,
+}
+
+#[derive(Clone, Default)]
+struct IndexShard {
+    index: HashMap<u64, NodeId, BuildHasherDefault<PrehashedHasher>>,
+    /// Further ids for hashes already taken in `index`, which 64 bit hashes make rare
+    collisions: HashMap<u64, Vec<NodeId>, BuildHasherDefault<PrehashedHasher>>,
+}
+
+impl IndexShard {
+    fn insert(&mut self, hash: u64, id: NodeId) {
+        match self.index.entry(hash) {
+            Entry::Vacant(entry) => {
+                entry.insert(id);
+            }
+            Entry::Occupied(_) => self.collisions.entry(hash).or_default().push(id),
+        }
+    }
+
+    fn ids(&self, hash: u64) -> impl Iterator<Item=NodeId> + '_ {
+        self.index.get(&hash).into_iter()
+            .chain(self.collisions.get(&hash).into_iter().flatten())
+            .copied()
+    }
+
+    fn clear(&mut self) {
+        self.index.clear();
+        self.collisions.clear();
+    }
+}
+
+/// The shard of the index holding `hash`. Uses the upper half of the hash, the maps inside a
+/// shard pick buckets by the lower bits.
+pub(crate) fn shard_of(hash: u64) -> usize {
+    (hash >> 32) as usize % INDEX_SHARDS
+}
+
+/// Every id a [`NodeId`] holds is taken
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutOfNodeIds;
+
+impl std::fmt::Display for OutOfNodeIds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ran out of node ids")
+    }
+}
+
+impl std::error::Error for OutOfNodeIds {}
+
+impl Default for NodeStore {
+    fn default() -> NodeStore {
+        NodeStore {
+            arena: Vec::new(),
+            shards: vec![IndexShard::default(); INDEX_SHARDS],
+            hasher: RandomState::default(),
+            len: 0,
+        }
+    }
+}
+
+/// The keys of the index are hashes already, hashing them again is wasted time
+#[derive(Default)]
+struct PrehashedHasher(u64);
+
+impl Hasher for PrehashedHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = self.0.rotate_left(8) ^ byte as u64;
+        }
+    }
+
+    fn write_u64(&mut self, hash: u64) {
+        self.0 = hash;
+    }
+}
+
+impl NodeStore {
+    pub fn new() -> NodeStore {
+        NodeStore::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Bytes held by the arena and the index, leaving out boxes spilled to the heap
+    pub fn memory_bytes(&self) -> usize {
+        // hash map entries plus a control byte each
+        let index_entry = size_of::<(u64, NodeId)>() + 1;
+        self.arena.capacity() * size_of::<Option<UniqueNode>>()
+            + self.shards.iter()
+                .map(|shard| (shard.index.capacity() + shard.collisions.capacity()) * index_entry)
+                .sum::<usize>()
+    }
+
+    /// The id the next inserted node gets, one past the highest id ever handed out
+    pub fn next_id(&self) -> NodeId {
+        NodeId::try_from(self.arena.len()).expect("ids are handed out below NodeId::MAX")
+    }
+
+    /// How many more nodes fit before [`OutOfNodeIds`]
+    pub fn ids_left(&self) -> usize {
+        NodeId::MAX as usize - self.arena.len()
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&UniqueNode> {
+        self.arena.get(id as usize)?.as_ref()
+    }
+
+    pub fn contains_id(&self, id: NodeId) -> bool {
+        self.get(id).is_some()
+    }
+
+    pub fn get_id(&self, node: &UniqueNode) -> Option<NodeId> {
+        self.find_hashed(self.hash_of(node), node)
+    }
+
+    /// The hash the index files `node` under
+    pub fn hash_of(&self, node: &UniqueNode) -> u64 {
+        self.hasher.hash_one(node)
+    }
+
+    /// [`NodeStore::get_id`] with the hash [`NodeStore::hash_of`] gave
+    pub fn find_hashed(&self, hash: u64, node: &UniqueNode) -> Option<NodeId> {
+        self.shards[shard_of(hash)].ids(hash)
+            .find(|&id| self.get(id) == Some(node))
+    }
+
+    /// The id of `node`, inserting it under the next id when it is new.
+    /// The flag is true when the node was inserted.
+    pub fn find_or_insert(&mut self, node: UniqueNode) -> Result<(NodeId, bool), OutOfNodeIds> {
+        let hash = self.hasher.hash_one(&node);
+        if let Some(id) = self.find_hashed(hash, &node) {
+            return Ok((id, false));
+        }
+        if self.ids_left() == 0 {
+            return Err(OutOfNodeIds);
+        }
+        let id = self.next_id();
+        self.index_hashed(hash, id);
+        self.arena.push(Some(node));
+        self.len += 1;
+        Ok((id, true))
+    }
+
+    /// Inserts `node` under a chosen id, as when loading a saved graph.
+    /// Returns false when the id or the node is already taken, or the id is [`NodeId::MAX`].
+    pub fn insert_at(&mut self, id: NodeId, node: UniqueNode) -> bool {
+        let hash = self.hasher.hash_one(&node);
+        let Some(next_id) = id.checked_add(1) else {
+            return false;
+        };
+        if self.contains_id(id) || self.find_hashed(hash, &node).is_some() {
+            return false;
+        }
+        self.extend_ids_to(next_id);
+        self.index_hashed(hash, id);
+        self.arena[id as usize] = Some(node);
+        self.len += 1;
+        true
+    }
+
+    /// Appends nodes not in the store under consecutive ids from [`NodeStore::next_id`], each with
+    /// the hash [`NodeStore::hash_of`] gave. Every shard of the index is filled on a thread of its own.
+    pub fn append_new(&mut self, nodes: Vec<(u64, UniqueNode)>) -> Result<(), OutOfNodeIds> {
+        if self.ids_left() < nodes.len() {
+            return Err(OutOfNodeIds);
+        }
+        let first_id = self.next_id();
+        let mut by_shard = vec![Vec::new(); INDEX_SHARDS];
+        for (offset, &(hash, _)) in nodes.iter().enumerate() {
+            by_shard[shard_of(hash)].push((hash, first_id + offset as NodeId));
+        }
+        thread::scope(|scope| {
+            for (shard, entries) in self.shards.iter_mut().zip(by_shard) {
+                if !entries.is_empty() {
+                    scope.spawn(move || {
+                        for (hash, id) in entries {
+                            shard.insert(hash, id);
+                        }
+                    });
+                }
+            }
+        });
+        self.len += nodes.len();
+        self.arena.extend(nodes.into_iter().map(|(_, node)| Some(node)));
+        Ok(())
+    }
+
+    /// Reserves every id below `next_id`, so new nodes get ids from there on
+    pub fn extend_ids_to(&mut self, next_id: NodeId) {
+        if self.arena.len() < next_id as usize {
+            self.arena.resize(next_id as usize, None);
+        }
+    }
+
+    /// Nodes in id order
+    pub fn iter(&self) -> impl Iterator<Item=(NodeId, &UniqueNode)> {
+        self.arena.iter()
+            .enumerate()
+            .filter_map(|(id, node)| Some((id as NodeId, node.as_ref()?)))
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item=NodeId> + '_ {
+        self.iter().map(|(id, _)| id)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item=&UniqueNode> {
+        self.arena.iter().flatten()
+    }
+
+    /// Removes every node for which `keep` is false. Ids of the kept nodes do not change.
+    pub fn retain(&mut self, mut keep: impl FnMut(NodeId, &UniqueNode) -> bool) {
+        for (id, slot) in self.arena.iter_mut().enumerate() {
+            if slot.as_ref().is_some_and(|node| !keep(id as NodeId, node)) {
+                *slot = None;
+                self.len -= 1;
+            }
+        }
+
+        for shard in &mut self.shards {
+            shard.clear();
+        }
+        let arena = std::mem::take(&mut self.arena);
+        for (id, node) in arena.iter().enumerate() {
+            if let Some(node) = node {
+                self.index_hashed(self.hash_of(node), id as NodeId);
+            }
+        }
+        self.arena = arena;
+        for shard in &mut self.shards {
+            shard.index.shrink_to_fit();
+        }
+    }
+
+    fn index_hashed(&mut self, hash: u64, id: NodeId) {
+        self.shards[shard_of(hash)].insert(hash, id);
+    }
+}
+
+/// Same ids holding the same nodes
+impl PartialEq for NodeStore {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for NodeStore {}
+
+impl std::fmt::Debug for NodeStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
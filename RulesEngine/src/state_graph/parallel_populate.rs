@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::thread;
+use crate::core::{Budget, ExplorationOptions, SharedGameState};
+use crate::state_graph::node_store::{shard_of, INDEX_SHARDS};
+use crate::state_graph::{get_all_adjacent_pushes, take_next_node, Edge, NodeId, PopulateResult, Push, StateGraph, UniqueNode};
+
+/// Nodes each thread expands per batch. Larger batches spend less time waiting on the merge.
+const BATCH_SIZE_PER_THREAD: usize = 256;
+
+/// A child found by a worker, resolved against the graph as it was before the batch
+enum Child {
+    Known(NodeId),
+    /// With the hash the node store files it under
+    New(u64, UniqueNode),
+}
+
+/// A new child, with its position among every child of the batch in batch order
+type Positioned = (usize, u64, UniqueNode);
+/// An expanded node, with every push out of it, the walk before the push and the child it leads to
+type Expanded = (NodeId, Vec<(Push, u16, Child)>);
+
+/// Expands a batch of unvisited nodes on `options.threads` threads.
+///
+/// Workers expand their share of the batch and look every child up in the graph, which is only
+/// read while they run. New children are then split by the index shard their hash falls in, and
+/// each shard drops the children found more than once on a thread of its own. Ids are handed out
+/// in batch order, and every shard of the index takes its new nodes on a thread of its own.
+///
+/// Breadth first, the ids are the same as expanding the batch with
+/// [`crate::state_graph::populate_step`] one node at a time. Other orders take the whole batch
+/// from the frontier before expanding any of it, so a full exploration finds the same nodes and
+/// edges, under other ids.
+///
+/// The batch shrinks as the graph nears `options.max_nodes` or `options.max_edges`, so a step
+/// goes past them by at most the pushes of one node, like a sequential step. When the new nodes
+/// would not all get an id, the batch is put back and the node budget reported used up.
+pub fn populate_parallel_step(graph: &mut StateGraph, shared: &SharedGameState, options: &ExplorationOptions) -> PopulateResult {
+    let threads = options.threads.max(1);
+    let mut batch_size = threads * BATCH_SIZE_PER_THREAD;
+    let mut batch = Vec::new();
+    while batch.len() < batch_size {
+        let Some(id) = take_next_node(graph, shared, options) else {
+            break;
+        };
+        if let Some(node) = graph.get_state(id) {
+            if batch.is_empty() {
+                let pushes_per_node = 4 * node.environment.iter_boxes().count();
+                batch_size = batch_size.min(batch_size_within_budget(graph, options, pushes_per_node));
+            }
+            batch.push((id, node.clone()));
+        }
+    }
+    if batch.is_empty() {
+        graph.assert_all_visited();
+        graph.compact_edges();
+        return PopulateResult::AllVisited;
+    }
+
+    let expanded = {
+        let graph = &*graph;
+        let chunk_size = batch.len().div_ceil(threads);
+        thread::scope(|scope| {
+            let workers: Vec<_> = batch.chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || expand_chunk(graph, shared, options, chunk)))
+                .collect();
+            workers.into_iter()
+                .flat_map(|worker| worker.join().expect("expanding worker panicked"))
+                .collect::<Vec<_>>()
+        })
+    };
+
+    // every child of the batch in batch order, targets of new children resolved below
+    let mut edges = Vec::new();
+    let mut new_by_shard = vec![Vec::new(); INDEX_SHARDS];
+    for (from_id, children) in expanded {
+        for (push, walk, child) in children {
+            let to_id = match child {
+                Child::Known(id) => Some(id),
+                Child::New(hash, node) => {
+                    new_by_shard[shard_of(hash)].push((edges.len(), hash, node));
+                    None
+                }
+            };
+            edges.push((from_id, push, walk, to_id));
+        }
+    }
+
+    let deduplicated: Vec<_> = thread::scope(|scope| {
+        let shards: Vec<_> = new_by_shard.into_iter()
+            .map(|children| scope.spawn(move || deduplicate(children)))
+            .collect();
+        shards.into_iter()
+            .map(|shard| shard.join().expect("deduplicating worker panicked"))
+            .collect()
+    });
+    let mut new_nodes = Vec::new();
+    let mut repeats = Vec::new();
+    for (firsts, shard_repeats) in deduplicated {
+        new_nodes.extend(firsts);
+        repeats.extend(shard_repeats);
+    }
+
+    if graph.nodes.ids_left() < new_nodes.len() {
+        // the id space is the last node budget, the batch waits to be expanded again
+        for &(id, _) in batch.iter().rev() {
+            graph.return_unvisited(id);
+        }
+        return PopulateResult::BudgetExhausted(Budget::Nodes);
+    }
+    new_nodes.sort_unstable_by_key(|&(position, _, _)| position);
+    let first_id = graph.nodes.next_id();
+    for (offset, &(position, _, _)) in new_nodes.iter().enumerate() {
+        edges[position].3 = Some(first_id + offset as NodeId);
+    }
+    for (position, first) in repeats {
+        edges[position].3 = edges[first].3;
+    }
+    graph.append_new_states(new_nodes.into_iter().map(|(_, hash, node)| (hash, node)).collect())
+        .expect("the batch was checked to fit");
+
+    for (from_id, push, walk, to_id) in edges {
+        graph.add_edge(Edge {
+            from: from_id,
+            to: to_id.expect("every child has an id"),
+            push,
+            walk,
+        });
+    }
+
+    PopulateResult::Populated
+}
+
+/// The most nodes a batch expands without going past the node or edge budget, at least one.
+/// Expanding a node adds at most one node and one edge per push.
+fn batch_size_within_budget(graph: &StateGraph, options: &ExplorationOptions, pushes_per_node: usize) -> usize {
+    let headroom = |max: Option<usize>, used: usize| max.map_or(usize::MAX, |max| max.saturating_sub(used));
+    let headroom = headroom(options.max_nodes, graph.nodes.len())
+        .min(headroom(options.max_edges, graph.edges.len()));
+    (headroom / pushes_per_node.max(1)).max(1)
+}
+
+/// Splits the new children of one shard into the first of each node, and the positions of the
+/// later ones along with the position of their first
+fn deduplicate(children: Vec<Positioned>) -> (Vec<Positioned>, Vec<(usize, usize)>) {
+    let firsts: Vec<usize> = {
+        let mut first_positions = HashMap::with_capacity(children.len());
+        children.iter()
+            .map(|(position, _, node)| *first_positions.entry(node).or_insert(*position))
+            .collect()
+    };
+    let mut unique = Vec::new();
+    let mut repeats = Vec::new();
+    for (child, first) in children.into_iter().zip(firsts) {
+        if child.0 == first {
+            unique.push(child);
+        } else {
+            repeats.push((child.0, first));
+        }
+    }
+    (unique, repeats)
+}
+
+fn expand_chunk(
+    graph: &StateGraph,
+    shared: &SharedGameState,
+    options: &ExplorationOptions,
+    chunk: &[(NodeId, UniqueNode)],
+) -> Vec<Expanded> {
+    chunk.iter()
+        .map(|(from_id, node)| {
+            let children = get_all_adjacent_pushes(node, shared, options).into_iter()
+                .map(|(push, walk, child)| {
+                    let hash = graph.nodes.hash_of(&child);
+                    match graph.nodes.find_hashed(hash, &child) {
+                        Some(id) => (push, walk, Child::Known(id)),
+                        None => (push, walk, Child::New(hash, child)),
+                    }
+                })
+                .collect();
+            (*from_id, children)
+        })
+        .collect()
+}
@@ -1,79 +1,192 @@
-use crate::core::{GameUpdate, UserAction, step, SharedGameState, GameState, is_winnable, WinnableState, TRIM_HEURISTICAL_UNWINNABLE};
-use crate::state_graph::Edge;
+use std::time::Instant;
+use crate::core::{GameUpdate, UserAction, step_with_box_board, push_lower_bound, SharedGameState, GameState, ExplorationOptions, PushedState, SearchOrder, Budget};
+use crate::state_graph::symmetry_reduction::canonicalize_reachable;
+use crate::state_graph::{canonicalize, populate_parallel_step, Edge, NodeId, OutOfNodeIds, Push};
 use crate::state_graph::models::{PopulateResult, StateGraph};
 use crate::state_graph::unique_node::UniqueNode;
 
-pub fn get_all_adjacent_nodes(from_node: &UniqueNode, shared: &SharedGameState) -> Vec<UniqueNode> {
+pub fn get_all_adjacent_nodes(from_node: &UniqueNode, shared: &SharedGameState, options: &ExplorationOptions) -> Vec<UniqueNode> {
+    get_all_adjacent_pushes(from_node, shared, options).into_iter()
+        .map(|(_, _, node)| node)
+        .collect()
+}
+
+/// Every node reachable with a single push, along with the push leading to it and the walk
+/// before the push, see [`Edge::walk`].
+/// Leaves out every node the detectors of `options` recognise as unwinnable.
+/// Children are canonicalized when `options.reduce_symmetry` is set.
+pub fn get_all_adjacent_pushes(from_node: &UniqueNode, shared: &SharedGameState, options: &ExplorationOptions) -> Vec<(Push, u16, UniqueNode)> {
     let from_state = GameState {
         player: from_node.minimum_reachable_player_position.into(),
         environment: from_node.environment.clone(),
     };
 
-     if TRIM_HEURISTICAL_UNWINNABLE {
-        let can_win = is_winnable(shared, &from_state);
+    if options.detectors.is_state_deadlocked(shared, &from_state) {
+        // if we cannot win from this state, then we abort. pretend this node has no adjacent nodes.
+        return vec![];
+    }
+
+    let layout = shared.bit_layout();
+    let walkable = shared.walkable_board();
+    let boxes = shared.box_board(&from_node.environment);
+    let walks = shared.walk_steps(from_state.player, &boxes);
 
-        match can_win {
-            WinnableState::WinMaybePossible => {}
-            WinnableState::WinImpossible => {
-                // if we cannot win from this state, then we abort. pretend this node has no adjacent nodes.
-                return vec![];
+    // reused for every child, to avoid allocating inside the loop
+    let mut next_boxes = layout.empty();
+    let mut next_open = layout.empty();
+    let mut next_reachable = layout.empty();
+    let mut scratch = layout.empty();
+
+    let reduce_symmetry = options.reduce_symmetry && !shared.symmetries().is_empty();
+    let mut next_states = Vec::new();
+    for box_pos in from_node.environment.iter_boxes() {
+        for (player_pos, action) in UserAction::all_push_actions_around(box_pos) {
+            let Some(player_index) = layout.index_of(player_pos) else {
+                continue;
+            };
+            let Some(walk) = walks[player_index] else {
+                continue;
+            };
+            let new_box_pos = *box_pos + (*box_pos - player_pos);
+            let Some(new_box_index) = layout.index_of(new_box_pos) else {
+                continue;
+            };
+            if !walkable.contains(new_box_index) || boxes.contains(new_box_index) {
+                continue;
             }
-        }
-    }
 
-    let reachable_positions = shared.reachable_positions_visitation(&from_state);
-    let actions = from_node.environment.iter_boxes()
-        .flat_map(UserAction::all_push_actions_around)
-        .filter(|(box_pos, _)| reachable_positions.get(&(*box_pos).into())
-            .map(|cell| cell.is_reachable())
-            .unwrap_or(false))
-        .collect::<Vec<_>>();
+            let box_index = layout.index_of(*box_pos).expect("boxes must be inside the level");
+            next_boxes.copy_from(&boxes);
+            next_boxes.unset(box_index);
+            next_boxes.set(new_box_index);
 
-    let next_states: Vec<UniqueNode> = actions.into_iter()
-        .filter_map(|(pos, action)| {
             let from_state = GameState {
-                player: pos,
+                player: player_pos,
                 environment: from_node.environment.clone(),
             };
-            let update = step(shared, &from_state, action);
+            let update = step_with_box_board(shared, &from_state, &boxes, action, options);
             let GameUpdate::NextState(new_state, change_type) = update else {
-                return None;
+                continue;
             };
             if !change_type.did_box_move() {
-                return None;
+                continue;
+            }
+            let pushed = PushedState {
+                environment: &new_state.environment,
+                boxes: &next_boxes,
+                moved_box: new_box_pos,
+            };
+            if options.detectors.is_push_deadlocked(shared, &pushed) {
+                continue;
             }
 
-            Some(UniqueNode::from_game_state(new_state, shared))
-        })
-        .collect();
+            next_open.set_difference_of(walkable, &next_boxes);
+            layout.flood_fill_into(&next_open, box_index, &mut next_reachable, &mut scratch);
+            let min_reachable = layout.position_of(next_reachable.first_index().expect("the player always reaches their own cell"));
+
+            let UserAction::Move(direction) = action;
+            let push = Push {
+                box_position: *box_pos,
+                direction,
+            };
+            let node = UniqueNode {
+                environment: new_state.environment,
+                minimum_reachable_player_position: min_reachable.into(),
+            };
+            let node = if reduce_symmetry {
+                canonicalize_reachable(shared, node, &next_reachable, options).0
+            } else {
+                node
+            };
+            next_states.push((push, walk, node));
+        }
+    }
 
     next_states
 }
 
-pub fn populate_node(graph: &mut StateGraph, from_id: usize, shared: &SharedGameState) {
+/// Expands `from_id`. Puts it back unvisited and fails when the graph has fewer ids left than
+/// it has children.
+pub fn populate_node(graph: &mut StateGraph, from_id: NodeId, shared: &SharedGameState, options: &ExplorationOptions) -> Result<(), OutOfNodeIds> {
     let Some(source_node) = graph.get_state(from_id) else {
-        return;
+        return Ok(());
     };
     let source_node = source_node.clone();
 
-    let adjacent_nodes = get_all_adjacent_nodes(&source_node, shared);
-    for node in adjacent_nodes {
+    let adjacent_nodes = get_all_adjacent_pushes(&source_node, shared, options);
+    if graph.nodes.ids_left() < adjacent_nodes.len() {
+        graph.return_unvisited(from_id);
+        return Err(OutOfNodeIds);
+    }
+    for (push, walk, node) in adjacent_nodes {
         let to_id = graph.upsert_state(node);
         let edge = Edge {
             from: from_id,
             to: to_id,
+            push,
+            walk,
         };
         graph.add_edge(edge);
     }
+    Ok(())
+}
+
+/// Adds `start` to `graph` as the first node to expand. The start is canonicalized like every
+/// child when `options.reduce_symmetry` is set, so every node of a reduced graph is a
+/// representative.
+pub fn add_start(graph: &mut StateGraph, shared: &SharedGameState, start: &GameState, options: &ExplorationOptions) -> NodeId {
+    let node = UniqueNode::from_game_state(start.clone(), shared);
+    let node = if options.reduce_symmetry { canonicalize(shared, node, options).0 } else { node };
+    graph.upsert_state(node)
+}
+
+/// Takes and visits the next node to expand in `options.search_order`, scoring new nodes first when going best first
+pub fn take_next_node(graph: &mut StateGraph, shared: &SharedGameState, options: &ExplorationOptions) -> Option<NodeId> {
+    graph.search_order = options.search_order;
+    graph.reduce_symmetry = options.reduce_symmetry && !shared.symmetries().is_empty();
+    if options.search_order == SearchOrder::BestFirst {
+        // deadlocked nodes go last
+        graph.score_unvisited(|node| push_lower_bound(shared, &node.environment).unwrap_or(u32::MAX));
+    }
+    graph.take_and_visit_unvisited_node(options.search_order)
 }
 
-pub fn populate_step(graph: &mut StateGraph, shared: &SharedGameState) -> PopulateResult {
-    let picked_node = graph.take_and_visit_unvisited_node();
+pub fn populate_step(graph: &mut StateGraph, shared: &SharedGameState, options: &ExplorationOptions) -> PopulateResult {
+    let picked_node = take_next_node(graph, shared, options);
 
     let Some(node_id) = picked_node else {
         graph.assert_all_visited();
+        graph.compact_edges();
         return PopulateResult::AllVisited;
     };
-    populate_node(graph, node_id, shared);
+    if populate_node(graph, node_id, shared, options).is_err() {
+        // the id space is the last node budget
+        return PopulateResult::BudgetExhausted(Budget::Nodes);
+    }
     PopulateResult::Populated
 }
+
+/// Populates until every node is visited, or until a budget of `options` is used up.
+/// Expands batches in parallel when `options.threads` is above one.
+pub fn populate_all(graph: &mut StateGraph, shared: &SharedGameState, options: &ExplorationOptions) -> PopulateResult {
+    let start_time = Instant::now();
+    loop {
+        if let Some(budget) = graph.exhausted_budget(options, start_time.elapsed()) {
+            graph.compact_edges();
+            return PopulateResult::BudgetExhausted(budget);
+        }
+        let result = if options.threads > 1 {
+            populate_parallel_step(graph, shared, options)
+        } else {
+            populate_step(graph, shared, options)
+        };
+        match result {
+            PopulateResult::Populated => {}
+            PopulateResult::AllVisited => return PopulateResult::AllVisited,
+            PopulateResult::BudgetExhausted(budget) => {
+                graph.compact_edges();
+                return PopulateResult::BudgetExhausted(budget);
+            }
+        }
+    }
+}
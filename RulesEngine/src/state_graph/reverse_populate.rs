@@ -0,0 +1,277 @@
+use std::collections::HashSet;
+use std::time::Instant;
+use crate::core::{BitBoard, Budget, Cell, ExplorationOptions, GameStateEnvironment, SharedGameState, UserAction, Vec2};
+use crate::state_graph::symmetry_reduction::canonicalize_reachable;
+use crate::state_graph::{get_all_adjacent_pushes, take_next_node, Edge, PopulateResult, Push, StateGraph, UniqueNode};
+
+/// Every node with a push leading into `from_node`, along with that push. The reverse of
+/// [`get_all_adjacent_pushes`], without the detectors, which never prune a node that can still win.
+/// Parents are canonicalized when `options.reduce_symmetry` is set.
+pub fn get_all_pulls(from_node: &UniqueNode, shared: &SharedGameState, options: &ExplorationOptions) -> Vec<(Push, UniqueNode)> {
+    let layout = shared.bit_layout();
+    let walkable = shared.walkable_board();
+    let boxes = shared.box_board(&from_node.environment);
+    let reachable = shared.reachable_board(from_node.minimum_reachable_player_position.into(), &boxes);
+
+    // reused for every parent, to avoid allocating inside the loop
+    let mut previous_boxes = layout.empty();
+    let mut previous_open = layout.empty();
+    let mut previous_reachable = layout.empty();
+    let mut scratch = layout.empty();
+
+    let reduce_symmetry = options.reduce_symmetry && !shared.symmetries().is_empty();
+    let mut previous_states = Vec::new();
+    for (box_index, &box_pos) in from_node.environment.iter_boxes().enumerate() {
+        for (previous_box_pos, action) in UserAction::all_push_actions_around(&box_pos) {
+            // the player stands where the box was before the push
+            let UserAction::Move(direction) = action;
+            let previous_player_pos = previous_box_pos - direction.offset();
+            let (Some(previous_box_index), Some(previous_player_index)) = (layout.index_of(previous_box_pos), layout.index_of(previous_player_pos)) else {
+                continue;
+            };
+            if !reachable.contains(previous_box_index) {
+                continue;
+            }
+            if !walkable.contains(previous_player_index) || boxes.contains(previous_player_index) {
+                continue;
+            }
+
+            let mut environment = from_node.environment.clone();
+            environment.set_box(box_index, &previous_box_pos);
+            environment.complete_moves(options);
+
+            previous_boxes.copy_from(&boxes);
+            previous_boxes.unset(layout.index_of(box_pos).expect("boxes must be inside the level"));
+            previous_boxes.set(previous_box_index);
+            previous_open.set_difference_of(walkable, &previous_boxes);
+            layout.flood_fill_into(&previous_open, previous_player_index, &mut previous_reachable, &mut scratch);
+            let min_reachable = layout.position_of(previous_reachable.first_index().expect("the player always reaches their own cell"));
+
+            let push = Push {
+                box_position: previous_box_pos,
+                direction,
+            };
+            let node = UniqueNode {
+                environment,
+                minimum_reachable_player_position: min_reachable.into(),
+            };
+            let node = if reduce_symmetry {
+                canonicalize_reachable(shared, node, &previous_reachable, options).0
+            } else {
+                node
+            };
+            previous_states.push((push, node));
+        }
+    }
+
+    previous_states
+}
+
+/// Every won node with the boxes of `environment`: a box on every target, the spare boxes on
+/// every choice of other cells, and the player in each area the boxes leave. Empty when there
+/// are fewer boxes than targets. Without [`ExplorationOptions::deduplicate_boxes`], every order
+/// of the boxes on their cells is a node of its own. Err with the budget of `options` used up
+/// while generating them.
+pub fn goal_nodes(shared: &SharedGameState, environment: &GameStateEnvironment, options: &ExplorationOptions) -> Result<Vec<UniqueNode>, Budget> {
+    goal_nodes_since(shared, environment, options, Instant::now())
+}
+
+/// Pulls from every node of `graph` until every node is visited, or until a budget of `options`
+/// is used up. Seeded with [`goal_nodes`], fills the graph with every node that can reach a win.
+pub fn pull_all(graph: &mut StateGraph, shared: &SharedGameState, options: &ExplorationOptions) -> PopulateResult {
+    pull_all_since(graph, shared, options, Instant::now())
+}
+
+/// Populates `graph`, holding only the start from [`crate::state_graph::add_start`], with the
+/// nodes and edges [`crate::state_graph::populate_all`] followed by
+/// [`crate::state_graph::trim_unwinnable`] leaves, without exploring unwinnable nodes.
+/// Pulls back from every won node first, then pushes forward from the start through the nodes
+/// found. Leaves the graph empty when the start cannot win, and unchanged when a budget is used up.
+///
+/// Pulls also find the winnable nodes the start never reaches, so this only pays off when few
+/// nodes can win and the detectors of [`get_all_adjacent_pushes`] miss the deadlocks: then it
+/// skips most of the graph pushing forward would build. Where the detectors already catch nearly
+/// every deadlock, pushing forward and trimming is the faster way.
+pub fn populate_winnable(graph: &mut StateGraph, shared: &SharedGameState, options: &ExplorationOptions) -> PopulateResult {
+    let start_time = Instant::now();
+    let Some(start) = graph.unvisited.iter().next().and_then(|&id| graph.get_state(id)).cloned() else {
+        return PopulateResult::AllVisited;
+    };
+
+    let goals = match goal_nodes_since(shared, &start.environment, options, start_time) {
+        Ok(goals) => goals,
+        Err(budget) => return PopulateResult::BudgetExhausted(budget),
+    };
+    let mut winnable = StateGraph::new();
+    if winnable.nodes.ids_left() < goals.len() {
+        return PopulateResult::BudgetExhausted(Budget::Nodes);
+    }
+    for goal in goals {
+        winnable.upsert_state(goal);
+    }
+    if let PopulateResult::BudgetExhausted(budget) = pull_all_since(&mut winnable, shared, options, start_time) {
+        return PopulateResult::BudgetExhausted(budget);
+    }
+
+    if winnable.nodes.get_id(&start).is_none() {
+        *graph = StateGraph {
+            search_order: options.search_order,
+            reduce_symmetry: options.reduce_symmetry && !shared.symmetries().is_empty(),
+            ..StateGraph::new()
+        };
+        return PopulateResult::AllVisited;
+    }
+
+    // pushed into a copy, so that a budget used up here leaves the graph unchanged
+    let mut forward = graph.clone();
+    loop {
+        let memory_bytes = winnable.memory_bytes() + forward.memory_bytes();
+        if let Some(budget) = options.exhausted_budget(forward.nodes.len(), forward.edges.len(), memory_bytes, start_time.elapsed()) {
+            return PopulateResult::BudgetExhausted(budget);
+        }
+        let Some(from_id) = take_next_node(&mut forward, shared, options) else {
+            break;
+        };
+        let from_node = forward.get_state(from_id).expect("unvisited nodes exist").clone();
+        let children: Vec<_> = get_all_adjacent_pushes(&from_node, shared, options).into_iter()
+            .filter(|(_, _, node)| winnable.nodes.get_id(node).is_some())
+            .collect();
+        if forward.nodes.ids_left() < children.len() {
+            return PopulateResult::BudgetExhausted(Budget::Nodes);
+        }
+        for (push, walk, node) in children {
+            let to = forward.upsert_state(node);
+            forward.add_edge(Edge { from: from_id, to, push, walk });
+        }
+    }
+    forward.assert_all_visited();
+    forward.compact_edges();
+    *graph = forward;
+    PopulateResult::AllVisited
+}
+
+fn goal_nodes_since(shared: &SharedGameState, environment: &GameStateEnvironment, options: &ExplorationOptions, start_time: Instant) -> Result<Vec<UniqueNode>, Budget> {
+    let layout = shared.bit_layout();
+    let targets: Vec<Vec2> = shared.grid.iter().enumerate()
+        .flat_map(|(i, row)| row.iter().enumerate()
+            .filter(|&(_, &cell)| cell == Cell::Target)
+            .map(move |(j, _)| Vec2 { i: i as i8, j: j as i8 }))
+        .collect();
+    let box_count = environment.iter_boxes().count();
+    let Some(spare_count) = box_count.checked_sub(targets.len()) else {
+        return Ok(Vec::new());
+    };
+    let other_cells: Vec<Vec2> = shared.walkable_board().iter_indexes()
+        .map(|index| layout.position_of(index))
+        .filter(|position| !targets.contains(position))
+        .collect();
+
+    let mut goals = Vec::new();
+    let mut seen = HashSet::new();
+    let mut add_goal = |cells: &[Vec2]| {
+        // checked before every placement, as there can be far more placements than nodes fit
+        let memory_bytes = goals.capacity() * size_of::<UniqueNode>() + seen.capacity() * (size_of::<UniqueNode>() + 1);
+        if let Some(budget) = options.exhausted_budget(goals.len(), 0, memory_bytes, start_time.elapsed()) {
+            return Err(budget);
+        }
+
+        let mut goal = environment.clone();
+        for (index, cell) in cells.iter().enumerate() {
+            goal.set_box(index, cell);
+        }
+        goal.complete_moves(options);
+
+        let mut open = layout.empty();
+        open.set_difference_of(shared.walkable_board(), &shared.box_board(&goal));
+        while let Some(first) = open.first_index() {
+            let region = layout.flood_fill(&open, first);
+            let node = UniqueNode {
+                environment: goal.clone(),
+                minimum_reachable_player_position: layout.position_of(first).into(),
+            };
+            let node = canonical(shared, node, &region, options);
+            if seen.insert(node.clone()) {
+                goals.push(node);
+            }
+            let remaining = open.clone();
+            open.set_difference_of(&remaining, &region);
+        }
+        Ok(())
+    };
+
+    let mut cells = targets;
+    for_each_combination(&other_cells, spare_count, &mut cells, &mut |cells| {
+        if options.deduplicate_boxes {
+            add_goal(cells)
+        } else {
+            for_each_permutation(&mut cells.to_vec(), 0, &mut add_goal)
+        }
+    })?;
+    Ok(goals)
+}
+
+fn pull_all_since(graph: &mut StateGraph, shared: &SharedGameState, options: &ExplorationOptions, start_time: Instant) -> PopulateResult {
+    loop {
+        if let Some(budget) = graph.exhausted_budget(options, start_time.elapsed()) {
+            return PopulateResult::BudgetExhausted(budget);
+        }
+        let Some(id) = graph.take_and_visit_unvisited_node(options.search_order) else {
+            return PopulateResult::AllVisited;
+        };
+        let node = graph.get_state(id).expect("unvisited nodes exist").clone();
+        let parents = get_all_pulls(&node, shared, options);
+        if graph.nodes.ids_left() < parents.len() {
+            graph.return_unvisited(id);
+            return PopulateResult::BudgetExhausted(Budget::Nodes);
+        }
+        for (_, parent) in parents {
+            graph.upsert_state(parent);
+        }
+    }
+}
+
+fn canonical(shared: &SharedGameState, node: UniqueNode, reachable: &BitBoard, options: &ExplorationOptions) -> UniqueNode {
+    if options.reduce_symmetry && !shared.symmetries().is_empty() {
+        canonicalize_reachable(shared, node, reachable, options).0
+    } else {
+        node
+    }
+}
+
+/// Visits `chosen` followed by every way to choose `count` of `items`, keeping their order.
+/// Stops at the first error.
+fn for_each_combination(
+    items: &[Vec2],
+    count: usize,
+    chosen: &mut Vec<Vec2>,
+    visit: &mut impl FnMut(&[Vec2]) -> Result<(), Budget>,
+) -> Result<(), Budget> {
+    if count == 0 {
+        return visit(chosen);
+    }
+    for (index, &item) in items.iter().enumerate() {
+        chosen.push(item);
+        let visited = for_each_combination(&items[index + 1..], count - 1, chosen, visit);
+        chosen.pop();
+        visited?;
+    }
+    Ok(())
+}
+
+/// Visits every order of `items`, leaving the first `fixed` in place. Stops at the first error.
+fn for_each_permutation(
+    items: &mut [Vec2],
+    fixed: usize,
+    visit: &mut impl FnMut(&[Vec2]) -> Result<(), Budget>,
+) -> Result<(), Budget> {
+    if fixed + 1 >= items.len() {
+        return visit(items);
+    }
+    for index in fixed..items.len() {
+        items.swap(fixed, index);
+        let visited = for_each_permutation(items, fixed + 1, visit);
+        items.swap(fixed, index);
+        visited?;
+    }
+    Ok(())
+}
@@ -0,0 +1,182 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use crate::core::{Direction, GameState, SharedGameState, Vec2};
+use crate::solver::{LowerBound, MatchingBound};
+use crate::state_graph::{NodeId, StateGraph, UniqueNode};
+
+/// A solution as the push states it passes through, and every move made
+pub struct Solution {
+    /// Starts with the initial node and ends with a won node
+    pub nodes: Vec<UniqueNode>,
+    /// LURD notation, see [`solution_to_lurd`]
+    pub moves: String,
+}
+
+impl Solution {
+    pub fn push_count(&self) -> usize {
+        self.nodes.len() - 1
+    }
+
+    pub fn move_count(&self) -> usize {
+        self.moves.len()
+    }
+}
+
+/// Finds a solution with the fewest pushes, by breadth first search from `from_id` to the nearest
+/// won state. The path includes both the start and the won node.
+/// Returns None when no won state is reachable in the graph.
+/// The edges must be compacted, see [`StateGraph::compact_edges`].
+pub fn find_push_optimal_path(graph: &StateGraph, shared: &SharedGameState, from_id: NodeId) -> Option<Vec<NodeId>> {
+    assert!(graph.edges.is_compact(), "edges must be compacted before searching the graph");
+    let win_checker = shared.get_won_check_helper();
+
+    let mut came_from: HashMap<NodeId, NodeId> = HashMap::new();
+    let mut queue = VecDeque::from([from_id]);
+    while let Some(id) = queue.pop_front() {
+        let node = graph.get_state(id)?;
+        if win_checker.is_won(&node.environment) {
+            let mut path = vec![id];
+            let mut current = id;
+            while let Some(&previous) = came_from.get(&current) {
+                path.push(previous);
+                current = previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        for &(next, _, _) in graph.edges.outgoing(id) {
+            if next == from_id || came_from.contains_key(&next) {
+                continue;
+            }
+            came_from.insert(next, id);
+            queue.push_back(next);
+        }
+    }
+
+    None
+}
+
+/// Same as [`find_push_optimal_path`], resolving the ids to their nodes
+pub fn find_push_optimal_solution(graph: &StateGraph, shared: &SharedGameState, from_id: NodeId) -> Option<Vec<UniqueNode>> {
+    let path = find_push_optimal_path(graph, shared, from_id)?;
+    path.into_iter()
+        .map(|id| graph.get_state(id).cloned())
+        .collect()
+}
+
+/// Expands a sequence of push states into every move the player makes, in LURD notation.
+/// Walks are lowercase and pushes uppercase. `start` is the actual starting state, since nodes
+/// only record the minimum reachable player position.
+/// Returns None if consecutive nodes do not differ by exactly one push the player can reach.
+pub fn solution_to_lurd(shared: &SharedGameState, start: &GameState, nodes: &[UniqueNode]) -> Option<String> {
+    let mut moves = String::new();
+    let mut state = start.clone();
+
+    for next in nodes.iter().skip(1) {
+        let (from, to) = {
+            let mut moved_from = state.environment.iter_boxes().filter(|&b| !next.environment.has_box_at(b));
+            let mut moved_to = next.environment.iter_boxes().filter(|&b| !state.environment.has_box_at(b));
+            let (Some(&from), Some(&to), None, None) = (moved_from.next(), moved_to.next(), moved_from.next(), moved_to.next()) else {
+                return None;
+            };
+            (from, to)
+        };
+        let direction = Direction::from_offset(to - from)?;
+
+        let walk = shared.walk_path(&state, from - direction.offset())?;
+        moves.extend(walk.iter().map(|d| d.lurd_char(false)));
+        moves.push(direction.lurd_char(true));
+
+        state = GameState {
+            player: from,
+            environment: next.environment.clone(),
+        };
+    }
+
+    Some(moves)
+}
+
+/// The push-optimal solution from [`find_push_optimal_solution`], expanded into moves
+pub fn push_optimal_solution(graph: &StateGraph, shared: &SharedGameState, start: &GameState, from_id: NodeId) -> Option<Solution> {
+    let nodes = find_push_optimal_solution(graph, shared, from_id)?;
+    let moves = solution_to_lurd(shared, start, &nodes)?;
+    Some(Solution { nodes, moves })
+}
+
+/// Finds a solution with the fewest moves, counting both walking and pushing.
+///
+/// Searches with A* over pairs of node and player position, since how far the player walks
+/// before a push depends on where the previous push left them. The walks of the edges are taken
+/// while the player stands where they were measured from, see [`crate::state_graph::Edge`], and
+/// walks are measured from the player otherwise. Every push is also a move, so the push lower
+/// bound [`MatchingBound`] never overestimates the moves left either.
+/// The edges must be compacted, see [`StateGraph::compact_edges`].
+pub fn find_move_optimal_solution(graph: &StateGraph, shared: &SharedGameState, start: &GameState, from_id: NodeId) -> Option<Solution> {
+    assert!(graph.edges.is_compact(), "edges must be compacted before searching the graph");
+    let win_checker = shared.get_won_check_helper();
+    let bound = MatchingBound::new(shared);
+
+    type SearchState = (NodeId, Vec2);
+    let start_state: SearchState = (from_id, start.player);
+    let mut best_cost: HashMap<SearchState, u32> = HashMap::from([(start_state, 0)]);
+    let mut came_from: HashMap<SearchState, SearchState> = HashMap::new();
+    let mut open = BinaryHeap::new();
+    open.push(Reverse((0u32, 0u32, start_state)));
+
+    while let Some(Reverse((_, cost, state))) = open.pop() {
+        if best_cost.get(&state).is_some_and(|&best| best < cost) {
+            continue;
+        }
+        let (id, player) = state;
+        let node = graph.get_state(id)?;
+        if win_checker.is_won(&node.environment) {
+            let mut path = vec![id];
+            let mut current = state;
+            while let Some(&previous) = came_from.get(&current) {
+                path.push(previous.0);
+                current = previous;
+            }
+            path.reverse();
+            let nodes = path.into_iter()
+                .map(|id| graph.get_state(id).cloned())
+                .collect::<Option<Vec<_>>>()?;
+            let moves = solution_to_lurd(shared, start, &nodes)?;
+            return Some(Solution { nodes, moves });
+        }
+
+        let on_edge_walks = player == Vec2::from(node.minimum_reachable_player_position);
+        let mut walk_distances = None;
+        for &(to, push, edge_walk) in graph.edges.outgoing(id) {
+            let walk = if on_edge_walks {
+                edge_walk
+            } else {
+                let walk_distances = walk_distances.get_or_insert_with(|| shared.walk_distances(&GameState {
+                    player,
+                    environment: node.environment.clone(),
+                }));
+                let Some(&Some(walk)) = walk_distances.get(&push.player_position().into()) else {
+                    continue;
+                };
+                walk
+            };
+            let next_cost = cost + walk as u32 + 1;
+            let next_state = (to, push.box_position);
+            if best_cost.get(&next_state).is_some_and(|&best| best <= next_cost) {
+                continue;
+            }
+            let Some(next_node) = graph.get_state(to) else {
+                continue;
+            };
+            best_cost.insert(next_state, next_cost);
+            came_from.insert(next_state, state);
+            let Some(estimate) = bound.estimate(next_node) else {
+                continue;
+            };
+            let estimate = next_cost + estimate;
+            open.push(Reverse((estimate, next_cost, next_state)));
+        }
+    }
+
+    None
+}
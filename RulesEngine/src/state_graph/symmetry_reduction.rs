@@ -0,0 +1,184 @@
+use std::cmp::Ordering;
+use crate::core::{BitBoard, Direction, ExplorationOptions, GameState, GameStateEnvironment, SearchOrder, SharedGameState, Symmetry, Vec2};
+use crate::state_graph::{Edge, NodeId, Push, StateGraph, UniqueNode};
+
+/// The representative of `node` among its mirrors and rotations, along with the symmetry mapping
+/// `node` onto it. The representative has the smallest boxes, then the smallest player position.
+pub fn canonicalize(shared: &SharedGameState, node: UniqueNode, options: &ExplorationOptions) -> (UniqueNode, Symmetry) {
+    if shared.symmetries().is_empty() {
+        return (node, Symmetry::Identity);
+    }
+    let reachable = reachable_board(shared, &node);
+    canonicalize_reachable(shared, node, &reachable, options)
+}
+
+/// Same as [`canonicalize`], with the cells the player reaches in `node` already known
+pub(crate) fn canonicalize_reachable(shared: &SharedGameState, node: UniqueNode, reachable: &BitBoard, options: &ExplorationOptions) -> (UniqueNode, Symmetry) {
+    let mut best: Option<(UniqueNode, Symmetry)> = None;
+    for &symmetry in shared.symmetries() {
+        let environment = transform_environment(shared, &node.environment, symmetry, options);
+        let current = best.as_ref().map_or(&node, |(best, _)| best);
+        let boxes_order = environment.iter_boxes().cmp(current.environment.iter_boxes());
+        if boxes_order == Ordering::Greater {
+            continue;
+        }
+        let player = min_transformed_position(shared, reachable, symmetry);
+        if boxes_order == Ordering::Equal && player >= Vec2::from(current.minimum_reachable_player_position) {
+            continue;
+        }
+        best = Some((UniqueNode {
+            environment,
+            minimum_reachable_player_position: player.into(),
+        }, symmetry));
+    }
+    best.unwrap_or((node, Symmetry::Identity))
+}
+
+/// `node` mirrored or rotated by `symmetry`
+pub fn transform_node(shared: &SharedGameState, node: &UniqueNode, symmetry: Symmetry, options: &ExplorationOptions) -> UniqueNode {
+    let reachable = reachable_board(shared, node);
+    UniqueNode {
+        environment: transform_environment(shared, &node.environment, symmetry, options),
+        minimum_reachable_player_position: min_transformed_position(shared, &reachable, symmetry).into(),
+    }
+}
+
+pub fn transform_push(shared: &SharedGameState, push: Push, symmetry: Symmetry) -> Push {
+    Push {
+        box_position: shared_apply(shared, symmetry, push.box_position),
+        direction: symmetry.apply_direction(push.direction),
+    }
+}
+
+/// Expands a graph explored with [`ExplorationOptions::reduce_symmetry`] back into every state
+/// reachable from `start`, for visualizing and solving. The pushes of each state are the pushes
+/// of its representative, mirrored or rotated back, so trimming the reduced graph first trims the
+/// expanded graph too. The start gets id 0. States whose representative is still unvisited stay
+/// unvisited, so a partial graph keeps its frontier.
+pub fn expand_symmetric_graph(reduced: &StateGraph, shared: &SharedGameState, start: &UniqueNode, options: &ExplorationOptions) -> StateGraph {
+    let mut full = StateGraph::new();
+    if find_reduced(reduced, shared, start, options).is_none() {
+        // the start was trimmed away
+        return full;
+    }
+    full.upsert_state(start.clone());
+    let mut frontier = Vec::new();
+    while let Some(id) = full.take_and_visit_unvisited_node(SearchOrder::BreadthFirst) {
+        let node = full.get_state(id).expect("visited nodes exist").clone();
+        let Some((reduced_id, symmetry)) = find_reduced(reduced, shared, &node, options) else {
+            continue;
+        };
+        if reduced.unvisited.contains(&reduced_id) {
+            frontier.push(id);
+            continue;
+        }
+        let inverse = symmetry.inverse();
+        // the walks of the representative start from a position which need not map onto this one
+        let walks = shared.walk_steps(node.minimum_reachable_player_position.into(), &shared.box_board(&node.environment));
+        let layout = shared.bit_layout();
+        for &(_, push, _) in reduced.edges.outgoing(reduced_id) {
+            let push = transform_push(shared, push, inverse);
+            let walk = layout.index_of(push.player_position())
+                .and_then(|index| walks[index])
+                .expect("the player reaches the pushes of every symmetric node");
+            let child = apply_push(shared, &node, push, options);
+            let to = full.upsert_state(child);
+            full.add_edge(Edge { from: id, to, push, walk });
+        }
+    }
+    for id in frontier {
+        full.return_unvisited(id);
+    }
+    full.compact_edges();
+    full
+}
+
+/// A path found with [`ExplorationOptions::reduce_symmetry`], where every node after the first
+/// is a representative, turned into the states actually played from the first node. Each
+/// representative is mirrored or rotated back onto the state one push away from the one before.
+/// The path is returned as it is without symmetry reduction.
+pub fn expand_symmetric_path(shared: &SharedGameState, path: &[UniqueNode], options: &ExplorationOptions) -> Vec<UniqueNode> {
+    if !options.reduce_symmetry || shared.symmetries().is_empty() {
+        return path.to_vec();
+    }
+    let mut expanded: Vec<UniqueNode> = path.iter().take(1).cloned().collect();
+    for representative in path.iter().skip(1) {
+        let previous = expanded.last().expect("the path starts with the first node");
+        let next = std::iter::once(&Symmetry::Identity).chain(shared.symmetries())
+            .map(|&symmetry| transform_node(shared, representative, symmetry, options))
+            .find(|candidate| push_between(shared, previous, candidate, options).is_some())
+            .expect("consecutive nodes of a path are one push apart, up to symmetry");
+        expanded.push(next);
+    }
+    expanded
+}
+
+/// The push the player can make in `from` to reach `to`, None when there is none
+fn push_between(shared: &SharedGameState, from: &UniqueNode, to: &UniqueNode, options: &ExplorationOptions) -> Option<Push> {
+    let mut moved_from = from.environment.iter_boxes().filter(|&b| !to.environment.has_box_at(b));
+    let mut moved_to = to.environment.iter_boxes().filter(|&b| !from.environment.has_box_at(b));
+    let (Some(&box_position), Some(&destination), None, None) = (moved_from.next(), moved_to.next(), moved_from.next(), moved_to.next()) else {
+        return None;
+    };
+    let push = Push {
+        box_position,
+        direction: Direction::from_offset(destination - box_position)?,
+    };
+    let player_index = shared.bit_layout().index_of(box_position - push.direction.offset())?;
+    if !reachable_board(shared, from).contains(player_index) {
+        return None;
+    }
+    (apply_push(shared, from, push, options) == *to).then_some(push)
+}
+
+/// The node standing for `node` in `reduced`, and the symmetry mapping `node` onto it.
+/// Every node of `reduced` is a representative, so one found as it is needs no symmetry.
+fn find_reduced(reduced: &StateGraph, shared: &SharedGameState, node: &UniqueNode, options: &ExplorationOptions) -> Option<(NodeId, Symmetry)> {
+    if let Some(id) = reduced.nodes.get_id(node) {
+        return Some((id, Symmetry::Identity));
+    }
+    let (canonical, symmetry) = canonicalize(shared, node.clone(), options);
+    reduced.nodes.get_id(&canonical).map(|id| (id, symmetry))
+}
+
+/// The node after `push`, which must be possible in `node`
+fn apply_push(shared: &SharedGameState, node: &UniqueNode, push: Push, options: &ExplorationOptions) -> UniqueNode {
+    let mut environment = node.environment.clone();
+    let index = environment.index_of_box_at(&push.box_position).expect("pushes move an existing box");
+    environment.set_box(index, &push.box_destination());
+    environment.complete_moves(options);
+    let player = shared.min_reachable_position(&GameState {
+        player: push.box_position,
+        environment: environment.clone(),
+    });
+    UniqueNode {
+        environment,
+        minimum_reachable_player_position: player.into(),
+    }
+}
+
+fn transform_environment(shared: &SharedGameState, environment: &GameStateEnvironment, symmetry: Symmetry, options: &ExplorationOptions) -> GameStateEnvironment {
+    let mut transformed = environment.clone();
+    for (index, &b) in environment.iter_boxes().enumerate() {
+        transformed.set_box(index, &shared_apply(shared, symmetry, b));
+    }
+    transformed.complete_moves(options);
+    transformed
+}
+
+fn shared_apply(shared: &SharedGameState, symmetry: Symmetry, position: Vec2) -> Vec2 {
+    symmetry.apply(position, shared.size())
+}
+
+fn reachable_board(shared: &SharedGameState, node: &UniqueNode) -> BitBoard {
+    let boxes = shared.box_board(&node.environment);
+    shared.reachable_board(node.minimum_reachable_player_position.into(), &boxes)
+}
+
+fn min_transformed_position(shared: &SharedGameState, reachable: &BitBoard, symmetry: Symmetry) -> Vec2 {
+    let layout = shared.bit_layout();
+    reachable.iter_indexes()
+        .map(|index| shared_apply(shared, symmetry, layout.position_of(index)))
+        .min()
+        .expect("the player always reaches their own cell")
+}
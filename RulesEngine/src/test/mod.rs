@@ -1,4 +1,22 @@
 ﻿mod test_moves;
 mod test_util;
 mod test_populate;
-mod test_heuristics;
\ No newline at end of file
+mod test_heuristics;
+mod test_level_collection;
+mod test_parse_level;
+mod test_bitboard;
+mod test_graph_file;
+mod test_solution;
+mod test_solver;
+mod test_lower_bound;
+mod test_dead_squares;
+mod test_corral;
+mod test_deadlock_detectors;
+mod test_heuristic_audit;
+mod test_exploration_options;
+mod test_parallel_populate;
+mod test_graph_storage;
+mod test_disk_populate;
+mod test_symmetry;
+mod test_estimate;
+mod test_reverse_populate;
@@ -0,0 +1,75 @@
+﻿
+#[cfg(test)]
+mod test {
+    use bevy::math::IVec2;
+    use crate::test::test_util::GameTestState;
+
+    fn assert_reachable_matches_visitation(level: &str) {
+        let game = GameTestState::new(level);
+        let visitation = game.shared.reachable_positions_visitation(&game.game_state);
+        let mut expected = Vec::new();
+        for y in 0..game.shared.height() as i32 {
+            for x in 0..game.shared.width() as i32 {
+                let pos = IVec2 { x, y };
+                if visitation[&pos].is_reachable() {
+                    expected.push(pos);
+                }
+            }
+        }
+
+        // both in row-major order, the same order as the bits
+        let actual: Vec<IVec2> = game.shared.reachable_positions(&game.game_state).into_iter().map(|x| x.into()).collect();
+        assert_eq!(expected, actual);
+        let min: IVec2 = game.shared.min_reachable_position(&game.game_state).into();
+        assert_eq!(expected[0], min);
+    }
+
+    #[test]
+    fn reachable_matches_visitation_in_small_room() {
+        assert_reachable_matches_visitation(r#"
+#####
+#  .#
+# $ #
+#$@ #
+#.  #
+#####
+"#);
+    }
+
+    #[test]
+    fn reachable_does_not_wrap_between_rows() {
+        // the right edge of each row sits directly next to the left edge of the next row in the board
+        assert_reachable_matches_visitation(r#"
+#####
+#@$ .
+ ####
+.  ##
+#####
+"#);
+    }
+
+    #[test]
+    fn reachable_crosses_word_boundaries() {
+        // rows wider than a single 64 bit word, with the reachable area split across words
+        assert_reachable_matches_visitation(r#"
+##########################################################################
+#@                                                              $        #
+# ############################################################## ####### #
+#                                                               $       .#
+##########################################################################
+"#);
+    }
+
+    #[test]
+    fn min_reachable_is_walled_off_by_boxes() {
+        let game = GameTestState::new(r#"
+######
+#  # #
+#  $.#
+#  #@#
+######
+"#);
+        let min: IVec2 = game.shared.min_reachable_position(&game.game_state).into();
+        assert_eq!(IVec2 { x: 4, y: 1 }, min);
+    }
+}
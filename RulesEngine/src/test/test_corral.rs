@@ -0,0 +1,101 @@
+﻿
+#[cfg(test)]
+mod test {
+    use crate::core::*;
+    use crate::state_graph::{get_all_adjacent_nodes, UniqueNode};
+    use crate::test::test_util::GameTestState;
+
+    const POCKET_LEVEL: &str = r#"
+########
+#@ .  .#
+#      #
+###$####
+  # $ #
+  #   #
+  #####
+"#;
+
+    fn assert_level_corral_state(expected: WinnableState, level: &str) {
+        let game = GameTestState::new(level);
+        let winnable = is_winnable_with_corrals(&game.shared, &game.game_state);
+
+        assert_eq!(expected, winnable, "Expected {:?} but found was {:?}, for level: {}", expected, winnable, level);
+    }
+
+    #[test]
+    fn find_corrals_finds_interior_and_fence() {
+        let game = GameTestState::new(POCKET_LEVEL);
+        let corrals = find_corrals(&game.shared, &game.game_state);
+
+        assert_eq!(1, corrals.len());
+        assert_eq!(vec![
+            Vec2 { i: 4, j: 3 },
+            Vec2 { i: 4, j: 5 },
+            Vec2 { i: 5, j: 3 },
+            Vec2 { i: 5, j: 4 },
+            Vec2 { i: 5, j: 5 },
+        ], corrals[0].interior);
+        assert_eq!(vec![Vec2 { i: 3, j: 3 }, Vec2 { i: 4, j: 4 }], corrals[0].boxes);
+    }
+
+    #[test]
+    fn find_corrals_without_unreachable_floor_finds_none() {
+        let game = GameTestState::new(r#"
+######
+#@ $.#
+#    #
+######
+"#);
+        assert!(find_corrals(&game.shared, &game.game_state).is_empty());
+    }
+
+    #[test]
+    fn is_winnable_with_corrals_with_pocket_player_cannot_enter_win_impossible() {
+        // the fence can only be pushed into the pocket, and nothing can be pushed back out
+        assert_level_corral_state(WinnableState::WinImpossible, POCKET_LEVEL);
+    }
+
+    #[test]
+    fn is_winnable_with_corrals_with_corral_that_can_be_opened_win_possible() {
+        assert_level_corral_state(WinnableState::WinMaybePossible, r#"
+######
+#@$ .#
+######
+"#);
+        assert_level_corral_state(WinnableState::WinMaybePossible, r#"
+#######
+#@ $ .#
+### ###
+  #.#
+  ###
+"#);
+    }
+
+    #[test]
+    fn is_winnable_with_corrals_with_spare_boxes_win_possible() {
+        assert_level_corral_state(WinnableState::WinMaybePossible, r#"
+########
+#@ $.  #
+#      #
+###$####
+  # $ #
+  #   #
+  #####
+"#);
+    }
+
+    #[test]
+    fn find_adjacent_nodes_in_corral_deadlock_finds_none() {
+        let game = GameTestState::new(POCKET_LEVEL);
+        let source_node = UniqueNode {
+            environment: game.game_state.environment.clone(),
+            minimum_reachable_player_position: game.game_state.player.into(),
+        };
+
+        let options = ExplorationOptions {
+            detectors: DeadlockDetectors::none().with(CorralDetector),
+            ..ExplorationOptions::default()
+        };
+        assert!(get_all_adjacent_nodes(&source_node, &game.shared, &options).is_empty());
+    }
+}
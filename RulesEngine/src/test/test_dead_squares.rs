@@ -0,0 +1,114 @@
+﻿
+#[cfg(test)]
+mod test {
+    use bevy::math::IVec2;
+    use crate::core::*;
+    use crate::state_graph::{get_all_adjacent_nodes, UniqueNode};
+    use crate::test::test_util::{assert_game_set_matches, assert_symbols_match, GameTestState};
+
+    fn adjacent_states(game: &GameTestState) -> Vec<GameState> {
+        let source_node = UniqueNode {
+            environment: game.game_state.environment.clone(),
+            minimum_reachable_player_position: game.game_state.player.into(),
+        };
+        // dead squares are pruned without picking any detectors
+        get_all_adjacent_nodes(&source_node, &game.shared, &ExplorationOptions::default()).into_iter()
+            .map(|node| GameState {
+                player: node.minimum_reachable_player_position.into(),
+                environment: node.environment,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dead_squares_are_walls_and_corners_away_from_targets(){
+        let level = r#"
+######
+#    #
+# $. #
+#@   #
+######
+"#;
+        let game = GameTestState::new(level);
+        let mut dead = Vec::new();
+        for y in 0..game.shared.height() as i32 {
+            for x in 0..game.shared.width() as i32 {
+                let pos = IVec2 { x, y };
+                if game.shared.is_dead_square(pos.into()) {
+                    dead.push(pos);
+                }
+            }
+        }
+
+        let expected_symbols = r#"
+______
+_****_
+_*__*_
+_****_
+______
+"#;
+        let actual_symbols = game.render_where_present(dead, '*', '_');
+        assert_symbols_match(expected_symbols, actual_symbols.as_str());
+    }
+
+    #[test]
+    fn find_adjacent_nodes_skips_pushes_onto_dead_squares(){
+        let level = r#"
+######
+#    #
+# $. #
+#@   #
+######
+"#;
+        let game = GameTestState::new(level);
+        let new_game_states = adjacent_states(&game);
+
+        let expected_moves = vec![r#"
+######
+#@   #
+#  * #
+#    #
+######
+"#,
+        ];
+
+        assert_game_set_matches(&new_game_states, game.shared, expected_moves);
+    }
+
+    #[test]
+    fn find_adjacent_nodes_allows_spare_boxes_on_dead_squares(){
+        // one more box than targets, and that box is already on a dead square
+        let level = r#"
+######
+#    #
+# $. #
+#@ $ #
+######
+"#;
+        let game = GameTestState::new(level);
+        let new_game_states = adjacent_states(&game);
+
+        let expected_moves = vec![r#"
+######
+#@   #
+#  * #
+#  $ #
+######
+"#,r#"
+######
+#@   #
+# $. #
+# $  #
+######
+"#,r#"
+######
+#@   #
+# $. #
+#   $#
+######
+"#,
+        ];
+
+        assert_game_set_matches(&new_game_states, game.shared, expected_moves);
+    }
+}
@@ -0,0 +1,89 @@
+﻿
+#[cfg(test)]
+mod test {
+    use crate::core::*;
+    use crate::state_graph::{get_all_adjacent_nodes, UniqueNode};
+    use crate::test::test_util::{GameTestState, PruneEveryPush};
+
+    const DEAD_SQUARE_LEVEL: &str = r#"
+######
+#    #
+# $. #
+#@   #
+######
+"#;
+
+    /// Pushing the lower box up freezes it against the other one, off the targets
+    const FREEZE_LEVEL: &str = r#"
+#######
+#. $ .#
+# $   #
+# @   #
+#######
+"#;
+
+    fn adjacent_count(level: &str, options: &ExplorationOptions) -> usize {
+        let game = GameTestState::new(level);
+        let source_node = UniqueNode::from_game_state(game.game_state.clone(), &game.shared);
+        get_all_adjacent_nodes(&source_node, &game.shared, options).len()
+    }
+
+    fn with_detectors(detectors: DeadlockDetectors) -> ExplorationOptions {
+        ExplorationOptions {
+            detectors,
+            ..ExplorationOptions::default()
+        }
+    }
+
+    #[test]
+    fn from_names_keeps_the_given_order() {
+        let detectors = DeadlockDetectors::from_names("corral, freeze").unwrap();
+        assert_eq!(vec!["corral", "freeze"], detectors.names());
+
+        assert_eq!(BUILTIN_DETECTOR_NAMES.to_vec(), DeadlockDetectors::from_names("all").unwrap().names());
+        assert!(DeadlockDetectors::from_names("none").unwrap().is_empty());
+    }
+
+    #[test]
+    fn from_names_rejects_unknown_detectors() {
+        let error = DeadlockDetectors::from_names("freeze,magic").err().unwrap();
+        assert!(error.contains("magic"), "{}", error);
+    }
+
+    #[test]
+    fn no_detectors_keep_every_push() {
+        assert_eq!(4, adjacent_count(DEAD_SQUARE_LEVEL, &with_detectors(DeadlockDetectors::none())));
+        assert_eq!(1, adjacent_count(DEAD_SQUARE_LEVEL, &with_detectors(DeadlockDetectors::all())));
+    }
+
+    #[test]
+    fn default_detectors_prune_dead_squares_and_frozen_boxes() {
+        assert_eq!(vec!["dead-squares", "freeze", "trapped"], ExplorationOptions::default().detectors.names());
+
+        let without_freeze = with_detectors(DeadlockDetectors::from_names("dead-squares,trapped").unwrap());
+        assert_eq!(adjacent_count(FREEZE_LEVEL, &without_freeze) - 1, adjacent_count(FREEZE_LEVEL, &ExplorationOptions::default()));
+        assert_eq!(1, adjacent_count(DEAD_SQUARE_LEVEL, &ExplorationOptions::default()));
+    }
+
+    #[test]
+    fn stats_count_checks_and_prunes_per_detector() {
+        let options = with_detectors(DeadlockDetectors::from_names("dead-squares,freeze").unwrap());
+        adjacent_count(DEAD_SQUARE_LEVEL, &options);
+
+        let detectors = &options.detectors;
+        let stats = detectors.stats();
+        assert_eq!(("dead-squares", 4, 3), (stats[0].name, stats[0].checked, stats[0].pruned));
+        // only the push dead squares let through reaches the next detector
+        assert_eq!(("freeze", 1, 0), (stats[1].name, stats[1].checked, stats[1].pruned));
+
+        detectors.reset_stats();
+        assert!(detectors.stats().iter().all(|stats| stats.checked == 0 && stats.pruned == 0));
+    }
+
+    #[test]
+    fn custom_detectors_join_the_chain() {
+        let options = with_detectors(DeadlockDetectors::none().with(PruneEveryPush));
+        assert_eq!(0, adjacent_count(DEAD_SQUARE_LEVEL, &options));
+        assert_eq!(4, options.detectors.stats()[0].pruned);
+    }
+}
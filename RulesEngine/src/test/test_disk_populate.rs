@@ -0,0 +1,72 @@
+﻿#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+    use crate::core::*;
+    use crate::state_graph::{read_edge_list, DiskExplorer, PopulateResult, UniqueNode};
+    use crate::test::test_util::{edge_set, explore, node_set, GameTestState, WAREHOUSE};
+
+    fn directory(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("sokobauto_disk_{}_{}", name, std::process::id()))
+    }
+
+    fn assert_disk_matches_memory(name: &str, options: &ExplorationOptions) {
+        let game = GameTestState::new(WAREHOUSE);
+        let directory = directory(name);
+        let start = UniqueNode::from_game_state(game.game_state.clone(), &game.shared);
+        // a small memory budget forces several sorted runs per layer
+        let mut explorer = DiskExplorer::new(&directory, &game.shared, start, 200).unwrap();
+
+        let result = explorer.populate_all(options).unwrap();
+        let on_disk = explorer.load_state_graph().unwrap();
+        let stats = explorer.stats();
+        let edge_list_len = read_edge_list(&explorer.edge_list_path()).unwrap().count();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        let in_memory = explore(&game, options);
+        assert_eq!(result, PopulateResult::AllVisited);
+        assert_eq!(node_set(&on_disk), node_set(&in_memory));
+        assert_eq!(edge_set(&on_disk), edge_set(&in_memory));
+        assert_eq!(stats.nodes as usize, in_memory.nodes.len());
+        assert_eq!(stats.expanded as usize, in_memory.nodes.len());
+        assert_eq!(edge_list_len, in_memory.edges.len());
+        assert!(on_disk.unvisited.is_empty());
+        assert!(stats.bytes_written > 0 && stats.bytes_read > 0);
+    }
+
+    #[test]
+    fn disk_exploration_finds_the_same_graph_as_memory() {
+        assert_disk_matches_memory("default", &ExplorationOptions::default());
+    }
+
+    #[test]
+    fn disk_exploration_keeps_box_order_without_deduplication() {
+        assert_disk_matches_memory("duplicated", &ExplorationOptions {
+            deduplicate_boxes: false,
+            ..ExplorationOptions::exhaustive()
+        });
+    }
+
+    #[test]
+    fn limits_stop_between_layers_with_the_last_layer_unvisited() {
+        let game = GameTestState::new(WAREHOUSE);
+        let directory = directory("limited");
+        let start = UniqueNode::from_game_state(game.game_state.clone(), &game.shared);
+        let mut explorer = DiskExplorer::new(&directory, &game.shared, start, 64).unwrap();
+        let options = ExplorationOptions {
+            max_nodes: Some(20),
+            ..ExplorationOptions::default()
+        };
+
+        let result = explorer.populate_all(&options).unwrap();
+        let partial = explorer.load_state_graph().unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(result, PopulateResult::BudgetExhausted(Budget::Nodes));
+        assert!(partial.nodes.len() >= 20);
+        assert!(!partial.unvisited.is_empty());
+        for &id in &partial.unvisited {
+            assert!(partial.edges.outgoing(id).is_empty());
+            assert!(!partial.edges.incoming(id).is_empty());
+        }
+    }
+}
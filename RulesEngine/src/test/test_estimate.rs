@@ -0,0 +1,84 @@
+﻿#[cfg(test)]
+mod test {
+    use crate::core::*;
+    use crate::state_graph::estimate_state_count;
+    use crate::test::test_util::{explore, GameTestState, ROOM, WAREHOUSE};
+
+    const CORRIDOR: &str = r#"
+######
+#@$ .#
+######
+"#;
+
+    fn explored_count(game: &GameTestState, options: &ExplorationOptions) -> usize {
+        explore(game, options).nodes.len()
+    }
+
+    #[test]
+    fn upper_bound_holds_every_explored_state() {
+        for level in [CORRIDOR, ROOM, WAREHOUSE] {
+            let game = GameTestState::new(level);
+            for options in [ExplorationOptions::default(), ExplorationOptions::exhaustive()] {
+                let estimate = estimate_state_count(&game.shared, &game.game_state, &options, 10, 0);
+                assert!(estimate.upper_bound >= explored_count(&game, &options) as f64);
+            }
+        }
+    }
+
+    #[test]
+    fn sample_is_exact_without_choices() {
+        let game = GameTestState::new(CORRIDOR);
+        let options = ExplorationOptions::default();
+        let estimate = estimate_state_count(&game.shared, &game.game_state, &options, 20, 0);
+        let explored = explored_count(&game, &options) as f64;
+        assert_eq!(estimate.sampled, explored);
+        assert_eq!(estimate.sampled_interval, (explored, explored));
+    }
+
+    #[test]
+    fn sample_is_repeatable_and_inside_its_interval() {
+        let game = GameTestState::new(WAREHOUSE);
+        let options = ExplorationOptions::default();
+        let first = estimate_state_count(&game.shared, &game.game_state, &options, 50, 7);
+        let second = estimate_state_count(&game.shared, &game.game_state, &options, 50, 7);
+        assert_eq!(first.sampled, second.sampled);
+        assert_eq!(first.probes, 50);
+        assert!(first.sampled_interval.0 <= first.sampled && first.sampled <= first.sampled_interval.1);
+        assert!(first.sampled <= first.upper_bound);
+    }
+
+    /// Seeds 0 to 9 all land within 3 times the explored count on WAREHOUSE, with and without the
+    /// detectors, so the sample is asked to land within this factor
+    const SAMPLE_FACTOR: f64 = 4.0;
+
+    #[test]
+    fn sample_is_near_the_explored_count() {
+        let game = GameTestState::new(WAREHOUSE);
+        let options = ExplorationOptions::default();
+        let estimate = estimate_state_count(&game.shared, &game.game_state, &options, 1000, 0);
+        let explored = explored_count(&game, &options) as f64;
+        assert!(estimate.sampled >= explored / SAMPLE_FACTOR && estimate.sampled <= explored * SAMPLE_FACTOR,
+                "sampled {} for {} explored nodes", estimate.sampled, explored);
+        assert!(1.0 <= estimate.sampled_interval.0 && estimate.sampled_interval.1 <= estimate.upper_bound);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one probe")]
+    fn zero_probes_are_rejected() {
+        let game = GameTestState::new(CORRIDOR);
+        estimate_state_count(&game.shared, &game.game_state, &ExplorationOptions::default(), 0, 0);
+    }
+
+    #[test]
+    fn dead_squares_shrink_the_bound() {
+        let game = GameTestState::new(ROOM);
+        let options = ExplorationOptions {
+            detectors: DeadlockDetectors::none().with(DeadSquareDetector),
+            ..ExplorationOptions::default()
+        };
+        let pruned = estimate_state_count(&game.shared, &game.game_state, &options, 1, 0);
+        let exhaustive = estimate_state_count(&game.shared, &game.game_state, &ExplorationOptions::exhaustive(), 1, 0);
+        assert!(pruned.box_cells < exhaustive.box_cells);
+        assert!(pruned.upper_bound < exhaustive.upper_bound);
+    }
+}
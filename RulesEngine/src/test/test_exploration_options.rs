@@ -0,0 +1,195 @@
+﻿
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+    use crate::core::*;
+    use crate::state_graph::{get_json_data, populate_all, populate_node, populate_step, take_next_node, trim_unwinnable, PopulateResult, StateGraph};
+    use crate::test::test_util::{node_set, start_graph, GameTestState, ROOM};
+
+    fn explore(options: &ExplorationOptions) -> (StateGraph, PopulateResult) {
+        let game = GameTestState::new(ROOM);
+        let mut graph = start_graph(&game, options);
+        let result = populate_all(&mut graph, &game.shared, options);
+        (graph, result)
+    }
+
+    #[test]
+    fn depth_first_finds_the_same_nodes_as_breadth_first() {
+        let (breadth_first, _) = explore(&ExplorationOptions::default());
+        let (depth_first, _) = explore(&ExplorationOptions {
+            search_order: SearchOrder::DepthFirst,
+            ..ExplorationOptions::default()
+        });
+
+        assert_eq!(node_set(&breadth_first), node_set(&depth_first));
+        assert_eq!(breadth_first.edges.len(), depth_first.edges.len());
+    }
+
+    #[test]
+    fn without_deduplication_box_orders_are_different_nodes() {
+        let (deduplicated, _) = explore(&ExplorationOptions::exhaustive());
+        let (duplicated, _) = explore(&ExplorationOptions {
+            deduplicate_boxes: false,
+            ..ExplorationOptions::exhaustive()
+        });
+
+        assert!(duplicated.nodes.len() > deduplicated.nodes.len(),
+                "{} nodes without deduplication, {} with", duplicated.nodes.len(), deduplicated.nodes.len());
+    }
+
+    #[test]
+    fn exhaustive_exploration_keeps_deadlocked_nodes() {
+        let (pruned, _) = explore(&ExplorationOptions::default());
+        let (exhaustive, _) = explore(&ExplorationOptions::exhaustive());
+
+        assert!(exhaustive.nodes.len() > pruned.nodes.len());
+        assert!(node_set(&pruned).is_subset(&node_set(&exhaustive)));
+    }
+
+    #[test]
+    fn node_limit_stops_exploration_early() {
+        let (complete, result) = explore(&ExplorationOptions::default());
+        assert!(matches!(result, PopulateResult::AllVisited));
+
+        let (limited, result) = explore(&ExplorationOptions {
+            max_nodes: Some(10),
+            ..ExplorationOptions::default()
+        });
+        assert_eq!(result, PopulateResult::BudgetExhausted(Budget::Nodes));
+        assert!(limited.nodes.len() >= 10 && limited.nodes.len() < complete.nodes.len());
+        assert!(!limited.unvisited.is_empty());
+    }
+
+    #[test]
+    fn every_budget_stops_exploration_early() {
+        let (complete, _) = explore(&ExplorationOptions::default());
+        let budgets = [
+            (Budget::Edges, ExplorationOptions { max_edges: Some(10), ..ExplorationOptions::default() }),
+            (Budget::Memory, ExplorationOptions { max_memory: Some(1), ..ExplorationOptions::default() }),
+            (Budget::Time, ExplorationOptions { max_time: Some(Duration::ZERO), ..ExplorationOptions::default() }),
+        ];
+        for (budget, options) in budgets {
+            let (partial, result) = explore(&options);
+            assert_eq!(result, PopulateResult::BudgetExhausted(budget));
+            assert!(partial.nodes.len() < complete.nodes.len());
+            assert!(!partial.unvisited.is_empty());
+            assert!(partial.edges.is_compact());
+        }
+    }
+
+    #[test]
+    fn trimming_a_partial_graph_keeps_its_frontier() {
+        let game = GameTestState::new(ROOM);
+        let (mut partial, _) = explore(&ExplorationOptions {
+            max_nodes: Some(20),
+            ..ExplorationOptions::default()
+        });
+        let frontier = partial.unvisited.clone();
+
+        trim_unwinnable(&mut partial, &game.shared);
+
+        assert!(frontier.iter().all(|&id| partial.nodes.contains_id(id)));
+        // the start leads to the frontier
+        assert!(partial.nodes.contains_id(0));
+        let json = get_json_data(&partial, &game.shared);
+        assert_eq!(json.matches("\"unexpanded\": true").count(), frontier.len());
+    }
+
+    #[test]
+    fn search_order_parses_short_and_long_names() {
+        assert_eq!(Ok(SearchOrder::BreadthFirst), "bfs".parse());
+        assert_eq!(Ok(SearchOrder::DepthFirst), "depth-first".parse());
+        assert!("sideways".parse::<SearchOrder>().is_err());
+    }
+
+    #[test]
+    fn search_order_names_round_trip() {
+        assert_eq!(Ok(SearchOrder::BestFirst), "best-first".parse());
+        assert_eq!(Ok(SearchOrder::Random(0)), "random".parse());
+        assert!("random:seven".parse::<SearchOrder>().is_err());
+        for order in [SearchOrder::BreadthFirst, SearchOrder::DepthFirst, SearchOrder::BestFirst, SearchOrder::Random(7)] {
+            assert_eq!(Ok(order), order.to_string().parse());
+        }
+    }
+
+    #[test]
+    fn every_order_finds_the_same_nodes() {
+        let (breadth_first, _) = explore(&ExplorationOptions::default());
+        for search_order in [SearchOrder::BestFirst, SearchOrder::Random(3)] {
+            let (graph, _) = explore(&ExplorationOptions {
+                search_order,
+                ..ExplorationOptions::default()
+            });
+            assert_eq!(node_set(&breadth_first), node_set(&graph), "{}", search_order);
+            assert_eq!(breadth_first.edges.len(), graph.edges.len(), "{}", search_order);
+            assert_eq!(graph.search_order, search_order);
+        }
+    }
+
+    #[test]
+    fn switching_away_from_best_first_keeps_the_scored_nodes() {
+        let (breadth_first, _) = explore(&ExplorationOptions::default());
+        for search_order in [SearchOrder::BreadthFirst, SearchOrder::DepthFirst, SearchOrder::Random(5)] {
+            let game = GameTestState::new(ROOM);
+            let best_first = ExplorationOptions {
+                search_order: SearchOrder::BestFirst,
+                ..ExplorationOptions::default()
+            };
+            let mut graph = start_graph(&game, &best_first);
+            for _ in 0..5 {
+                populate_step(&mut graph, &game.shared, &best_first);
+            }
+            assert!(!graph.scored_unvisited.is_empty());
+
+            let options = ExplorationOptions {
+                search_order,
+                ..ExplorationOptions::default()
+            };
+            assert_eq!(populate_all(&mut graph, &game.shared, &options), PopulateResult::AllVisited);
+            assert!(graph.scored_unvisited.is_empty());
+            assert_eq!(node_set(&breadth_first), node_set(&graph), "{}", search_order);
+        }
+    }
+
+    #[test]
+    fn random_order_depends_only_on_the_seed() {
+        let visit_order = |seed| {
+            let game = GameTestState::new(ROOM);
+            let options = ExplorationOptions {
+                search_order: SearchOrder::Random(seed),
+                ..ExplorationOptions::default()
+            };
+            let mut graph = start_graph(&game, &options);
+            let mut order = Vec::new();
+            while let Some(id) = take_next_node(&mut graph, &game.shared, &options) {
+                order.push(id);
+                populate_node(&mut graph, id, &game.shared, &options).unwrap();
+            }
+            order
+        };
+
+        assert_eq!(visit_order(1), visit_order(1));
+        assert_ne!(visit_order(1), visit_order(2));
+    }
+
+    #[test]
+    fn best_first_reaches_a_win_within_a_smaller_budget() {
+        let game = GameTestState::new(ROOM);
+        let nodes_until_won = |search_order| {
+            let options = ExplorationOptions {
+                search_order,
+                ..ExplorationOptions::default()
+            };
+            let mut graph = start_graph(&game, &options);
+            let won = game.shared.get_won_check_helper();
+            while !graph.nodes.values().any(|node| won.is_won(&node.environment)) {
+                assert_eq!(populate_step(&mut graph, &game.shared, &options), PopulateResult::Populated);
+            }
+            graph.nodes.len()
+        };
+
+        let best_first = nodes_until_won(SearchOrder::BestFirst);
+        let breadth_first = nodes_until_won(SearchOrder::BreadthFirst);
+        assert!(best_first < breadth_first, "{} nodes best first, {} breadth first", best_first, breadth_first);
+    }
+}
@@ -0,0 +1,193 @@
+﻿
+#[cfg(test)]
+mod test {
+    use bevy::math::IVec2;
+    use crate::core::{ExplorationOptions, GameStateEnvironment, SearchOrder};
+    use crate::state_graph::{load_state_graph, populate_step, read_state_graph, save_state_graph, write_state_graph, GraphFileError, NodeId, PopulateResult, SavedStateGraph, StateGraph, UniqueNode};
+    use crate::test::test_util::GameTestState;
+
+    const LEVEL: &str = r#"
+#######
+#     #
+# $$  #
+#  @ ##
+#.  .##
+#######
+"#;
+
+    fn populate(game: &GameTestState, max_steps: usize) -> (StateGraph, NodeId) {
+        let mut graph = StateGraph::new();
+        let first_id = graph.upsert_state(UniqueNode::from_game_state(game.game_state.clone(), &game.shared));
+        for _ in 0..max_steps {
+            if let PopulateResult::AllVisited = populate_step(&mut graph, &game.shared, &ExplorationOptions::default()) {
+                break;
+            }
+        }
+        (graph, first_id)
+    }
+
+    fn round_trip(game: &GameTestState, graph: &StateGraph, first_id: NodeId) -> SavedStateGraph {
+        let mut bytes = Vec::new();
+        write_state_graph(&mut bytes, graph, &game.shared, first_id).unwrap();
+        read_state_graph(bytes.as_slice()).unwrap()
+    }
+
+    fn assert_graphs_equal(expected: &StateGraph, actual: &StateGraph) {
+        assert_eq!(expected.nodes, actual.nodes);
+        assert_eq!(expected.edges, actual.edges);
+        assert_eq!(expected.unvisited, actual.unvisited);
+        assert_eq!(expected.next_unvisted, actual.next_unvisted);
+        assert_eq!(expected.nodes.next_id(), actual.nodes.next_id());
+    }
+
+    #[test]
+    fn complete_graph_round_trips() {
+        let game = GameTestState::new(LEVEL);
+        let (graph, first_id) = populate(&game, usize::MAX);
+
+        let saved = round_trip(&game, &graph, first_id);
+
+        assert_graphs_equal(&graph, &saved.graph);
+        assert_eq!(first_id, saved.initial_id);
+        assert!(saved.shared.grid == game.shared.grid);
+        assert!(saved.check_level(&game.shared, &game.game_state).is_ok());
+    }
+
+    #[test]
+    fn partial_graph_keeps_frontier() {
+        let game = GameTestState::new(LEVEL);
+        let (graph, first_id) = populate(&game, 5);
+        assert!(!graph.unvisited.is_empty());
+
+        let mut saved = round_trip(&game, &graph, first_id);
+        assert_graphs_equal(&graph, &saved.graph);
+
+        // continuing from the loaded graph ends in the same place as continuing the original
+        let (complete, _) = populate(&game, usize::MAX);
+        while let PopulateResult::Populated = populate_step(&mut saved.graph, &saved.shared, &ExplorationOptions::default()) {}
+        assert_graphs_equal(&complete, &saved.graph);
+    }
+
+    #[test]
+    fn partial_best_first_graph_keeps_order_and_frontier() {
+        let game = GameTestState::new(LEVEL);
+        let options = ExplorationOptions {
+            search_order: SearchOrder::BestFirst,
+            ..ExplorationOptions::default()
+        };
+        let mut graph = StateGraph::new();
+        let first_id = graph.upsert_state(UniqueNode::from_game_state(game.game_state.clone(), &game.shared));
+        for _ in 0..5 {
+            populate_step(&mut graph, &game.shared, &options);
+        }
+        assert!(!graph.scored_unvisited.is_empty());
+
+        let mut saved = round_trip(&game, &graph, first_id);
+        assert_eq!(saved.graph.search_order, SearchOrder::BestFirst);
+        assert_eq!(saved.graph.unvisited, graph.unvisited);
+
+        while let PopulateResult::Populated = populate_step(&mut graph, &game.shared, &options) {}
+        while let PopulateResult::Populated = populate_step(&mut saved.graph, &saved.shared, &options) {}
+        assert_graphs_equal(&graph, &saved.graph);
+    }
+
+    #[test]
+    fn keeps_the_order_of_boxes() {
+        let game = GameTestState::new(LEVEL);
+        let (mut graph, first_id) = populate(&game, 0);
+        // the boxes of the first node listed in reverse, a separate node when boxes are not deduplicated
+        let mut boxes: Vec<IVec2> = game.game_state.environment.iter_boxes().map(|&b| b.into()).collect();
+        boxes.reverse();
+        let first = graph.get_state(first_id).unwrap();
+        let reversed = UniqueNode {
+            environment: GameStateEnvironment::from_ordered(boxes),
+            minimum_reachable_player_position: first.minimum_reachable_player_position,
+        };
+        assert_ne!(first, &reversed);
+        graph.upsert_state(reversed);
+
+        let saved = round_trip(&game, &graph, first_id);
+
+        assert_graphs_equal(&graph, &saved.graph);
+    }
+
+    #[test]
+    fn rejects_different_level() {
+        let game = GameTestState::new(LEVEL);
+        let (graph, first_id) = populate(&game, usize::MAX);
+        let saved = round_trip(&game, &graph, first_id);
+
+        let other_walls = GameTestState::new(r#"
+#######
+#     #
+# $$ ##
+#  @ ##
+#.  .##
+#######
+"#);
+        let result = saved.check_level(&other_walls.shared, &other_walls.game_state);
+        assert!(matches!(result, Err(GraphFileError::LevelMismatch(_))));
+
+        let other_start = GameTestState::new(r#"
+#######
+#     #
+# $ $ #
+#  @ ##
+#.  .##
+#######
+"#);
+        let result = saved.check_level(&other_start.shared, &other_start.game_state);
+        assert!(matches!(result, Err(GraphFileError::LevelMismatch(_))));
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        let result = read_state_graph("not a graph".as_bytes());
+        assert!(matches!(result, Err(GraphFileError::NotAGraphFile)));
+    }
+
+    #[test]
+    fn rejects_truncated_file() {
+        let game = GameTestState::new(LEVEL);
+        let (graph, first_id) = populate(&game, usize::MAX);
+        let mut bytes = Vec::new();
+        write_state_graph(&mut bytes, &graph, &game.shared, first_id).unwrap();
+        bytes.truncate(bytes.len() - 3);
+
+        let result = read_state_graph(bytes.as_slice());
+        assert!(matches!(result, Err(GraphFileError::Corrupt(_))));
+    }
+
+    #[test]
+    fn rejects_too_many_boxes() {
+        let game = GameTestState::new(LEVEL);
+        let (graph, first_id) = populate(&game, usize::MAX);
+        let mut bytes = Vec::new();
+        write_state_graph(&mut bytes, &graph, &game.shared, first_id).unwrap();
+        // past the header, the level, and the initial and next ids
+        let box_count_at = 12 + game.shared.height() as usize * game.shared.width() as usize + 8;
+        bytes[box_count_at..box_count_at + 2].copy_from_slice(&(GameStateEnvironment::MAX_BOXES as u16 + 1).to_le_bytes());
+
+        let result = read_state_graph(bytes.as_slice());
+        assert!(matches!(result, Err(GraphFileError::Corrupt(_))));
+    }
+
+    #[test]
+    fn checkpoint_overwrites_previous_save() {
+        let game = GameTestState::new(LEVEL);
+        let dir = std::env::temp_dir().join(format!("sokobauto_checkpoint_{}", std::process::id()));
+        let path = dir.join("state_graph.checkpoint");
+
+        let (partial, first_id) = populate(&game, 2);
+        save_state_graph(&path, &partial, &game.shared, first_id).unwrap();
+        let (more, _) = populate(&game, 6);
+        save_state_graph(&path, &more, &game.shared, first_id).unwrap();
+
+        let loaded = load_state_graph(&path, &game.shared, &game.game_state).unwrap();
+        let leftover_temp = dir.join("state_graph.checkpoint.tmp").exists();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_graphs_equal(&more, &loaded.graph);
+        assert!(!leftover_temp);
+    }
+}
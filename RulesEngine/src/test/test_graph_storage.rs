@@ -0,0 +1,131 @@
+﻿#[cfg(test)]
+mod test {
+    use bevy::math::IVec2;
+    use crate::core::*;
+    use crate::state_graph::{get_json_data, populate_all, trim_unwinnable, Edge, EdgeStore, NodeId, NodeStore, Push, StateGraph, UniqueNode};
+    use crate::test::test_util::{GameTestState, ROOM};
+
+    fn node(boxes: &[(i8, i8)]) -> UniqueNode {
+        UniqueNode {
+            environment: GameStateEnvironment::new(boxes.iter().map(|&(i, j)| IVec2::new(j as i32, i as i32)).collect()),
+            minimum_reachable_player_position: IVec2::new(1, 1),
+        }
+    }
+
+    fn edge(from: NodeId, to: NodeId) -> Edge {
+        Edge {
+            from,
+            to,
+            push: Push {
+                box_position: Vec2 { i: 1, j: 1 },
+                direction: Direction::Right,
+            },
+            walk: 0,
+        }
+    }
+
+    #[test]
+    fn node_store_finds_ids_and_keeps_them_through_removal() {
+        let mut nodes = NodeStore::new();
+        assert_eq!(nodes.find_or_insert(node(&[(1, 1)])), Ok((0, true)));
+        assert_eq!(nodes.find_or_insert(node(&[(1, 2)])), Ok((1, true)));
+        assert_eq!(nodes.find_or_insert(node(&[(1, 3)])), Ok((2, true)));
+        assert_eq!(nodes.find_or_insert(node(&[(1, 2)])), Ok((1, false)));
+
+        nodes.retain(|id, _| id != 1);
+
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes.get(1), None);
+        assert_eq!(nodes.get_id(&node(&[(1, 2)])), None);
+        assert_eq!(nodes.get_id(&node(&[(1, 3)])), Some(2));
+        assert_eq!(nodes.find_or_insert(node(&[(1, 2)])), Ok((3, true)));
+        assert_eq!(nodes.ids().collect::<Vec<_>>(), vec![0, 2, 3]);
+    }
+
+    #[test]
+    fn node_store_rejects_taken_ids_and_nodes() {
+        let mut nodes = NodeStore::new();
+        assert!(nodes.insert_at(5, node(&[(1, 1)])));
+        assert!(!nodes.insert_at(5, node(&[(1, 2)])));
+        assert!(!nodes.insert_at(2, node(&[(1, 1)])));
+
+        nodes.extend_ids_to(8);
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes.find_or_insert(node(&[(1, 2)])), Ok((8, true)));
+    }
+
+    #[test]
+    fn node_store_keeps_ids_below_the_last_node_id() {
+        let mut nodes = NodeStore::new();
+        assert_eq!(nodes.ids_left(), NodeId::MAX as usize);
+        assert!(!nodes.insert_at(NodeId::MAX, node(&[(1, 1)])));
+        assert_eq!(nodes.next_id(), 0);
+        assert!(nodes.is_empty());
+    }
+
+    #[test]
+    fn compacting_edges_builds_forward_and_reverse_adjacency() {
+        let mut edges = EdgeStore::new();
+        for (from, to) in [(2, 0), (0, 1), (0, 2), (1, 2), (0, 1)] {
+            edges.insert(edge(from, to));
+        }
+        assert!(!edges.is_compact());
+
+        edges.compact(3);
+
+        assert!(edges.is_compact());
+        assert_eq!(edges.len(), 4);
+        let targets = |id| edges.outgoing(id).iter().map(|&(to, _, _)| to).collect::<Vec<_>>();
+        let sources = |id| edges.incoming(id).iter().map(|&(from, _, _)| from).collect::<Vec<_>>();
+        assert_eq!((targets(0), targets(1), targets(2)), (vec![1, 2], vec![2], vec![0]));
+        assert_eq!((sources(0), sources(1), sources(2)), (vec![2], vec![0], vec![0, 1]));
+        assert_eq!(edges.iter().collect::<Vec<_>>(), vec![edge(0, 1), edge(0, 2), edge(1, 2), edge(2, 0)]);
+    }
+
+    #[test]
+    fn edges_added_after_compacting_are_merged_by_the_next_compaction() {
+        let mut edges = EdgeStore::new();
+        edges.insert(edge(0, 1));
+        edges.compact(2);
+        edges.insert(edge(1, 0));
+        edges.insert(edge(0, 1));
+        assert!(!edges.is_compact());
+
+        edges.compact(2);
+
+        assert_eq!(edges.len(), 2);
+        assert_eq!(edges.outgoing(1).len(), 1);
+        assert_eq!(edges.incoming(1).len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "edges must be compacted")]
+    fn querying_pending_edges_panics() {
+        let mut edges = EdgeStore::new();
+        edges.insert(edge(0, 1));
+        edges.outgoing(0);
+    }
+
+    #[test]
+    fn explored_graphs_are_compacted_and_trim_keeps_working() {
+        let game = GameTestState::new(ROOM);
+        let mut graph = StateGraph::new();
+        let first_id = graph.upsert_state(UniqueNode::from_game_state(game.game_state.clone(), &game.shared));
+        populate_all(&mut graph, &game.shared, &ExplorationOptions::exhaustive());
+        assert!(graph.edges.is_compact());
+        let total_edges = graph.edges.len();
+        let incoming: usize = graph.nodes.ids().map(|id| graph.edges.incoming(id).len()).sum();
+        assert_eq!(incoming, total_edges);
+
+        let stats = trim_unwinnable(&mut graph, &game.shared);
+
+        assert!(stats.nodes_removed() > 0);
+        assert!(graph.get_state(first_id).is_some());
+        for edge in &graph.edges {
+            assert!(graph.get_state(edge.from).is_some() && graph.get_state(edge.to).is_some());
+        }
+        let json: serde_json::Value = serde_json::from_str(&get_json_data(&graph, &game.shared)).unwrap();
+        assert_eq!(json["nodes"].as_array().unwrap().len(), graph.nodes.len());
+        assert_eq!(json["links"].as_array().unwrap().len(), graph.edges.len());
+    }
+}
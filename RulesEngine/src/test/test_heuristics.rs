@@ -11,6 +11,13 @@ mod test {
         assert_eq!(expected, winnable, "Expected {:?} but found was {:?}, for level: {}", expected, winnable, level);
     }
 
+    fn assert_after_push_winnable_state(expected: WinnableState, moved_box: Vec2, level: &str) {
+        let game = GameTestState::new(level);
+        let winnable = is_winnable_after_push(&game.shared, &game.game_state.environment, moved_box);
+
+        assert_eq!(expected, winnable, "Expected {:?} but found was {:?}, after moving {:?} in level: {}", expected, winnable, moved_box, level);
+    }
+
     #[test]
     fn is_winnable_with_single_free_box_win_possible() {
         assert_level_winnable_state(WinnableState::WinMaybePossible, r#"
@@ -117,6 +124,101 @@ mod test {
 #@$  $#
 #..$  #
 #######
+"#);
+    }
+
+    #[test]
+    fn is_winnable_after_push_with_boxes_side_by_side_against_wall_win_impossible() {
+        assert_after_push_winnable_state(WinnableState::WinImpossible, Vec2 { i: 1, j: 3 }, r#"
+#######
+# $$  #
+#     #
+#@ .. #
+#######
+"#);
+        assert_after_push_winnable_state(WinnableState::WinImpossible, Vec2 { i: 2, j: 1 }, r#"
+######
+#@   #
+#$  .#
+#$  .#
+#    #
+######
+"#);
+    }
+
+    #[test]
+    fn is_winnable_after_push_with_boxes_side_by_side_in_open_win_possible() {
+        assert_after_push_winnable_state(WinnableState::WinMaybePossible, Vec2 { i: 2, j: 3 }, r#"
+#######
+#     #
+# $$  #
+#     #
+#@ .. #
+#######
+"#);
+    }
+
+    #[test]
+    fn is_winnable_after_push_with_box_square_win_impossible() {
+        assert_after_push_winnable_state(WinnableState::WinImpossible, Vec2 { i: 2, j: 2 }, r#"
+#######
+#     #
+# $$  #
+# $$  #
+#     #
+#@....#
+#######
+"#);
+        // two boxes and two walls
+        assert_after_push_winnable_state(WinnableState::WinImpossible, Vec2 { i: 2, j: 3 }, r#"
+#######
+#  #  #
+#  $$ #
+#   # #
+#@ .. #
+#######
+"#);
+    }
+
+    #[test]
+    fn is_winnable_after_push_with_frozen_chain_win_impossible() {
+        // every box of the chain only moves once its neighbour does
+        assert_after_push_winnable_state(WinnableState::WinImpossible, Vec2 { i: 2, j: 4 }, r#"
+########
+#   #  #
+#  $$  #
+#  $#  #
+#@ ... #
+########
+"#);
+    }
+
+    #[test]
+    fn is_winnable_after_push_with_frozen_boxes_on_targets_win_possible() {
+        assert_after_push_winnable_state(WinnableState::WinMaybePossible, Vec2 { i: 1, j: 3 }, r#"
+#######
+# **  #
+#     #
+#@    #
+#######
+"#);
+    }
+
+    #[test]
+    fn is_winnable_after_push_with_spare_frozen_box_win_possible() {
+        assert_after_push_winnable_state(WinnableState::WinMaybePossible, Vec2 { i: 1, j: 3 }, r#"
+#######
+# *$  #
+#     #
+#@    #
+#######
+"#);
+        assert_after_push_winnable_state(WinnableState::WinImpossible, Vec2 { i: 1, j: 3 }, r#"
+#######
+# *$  #
+#     #
+#@  . #
+#######
 "#);
     }
 }
\ No newline at end of file
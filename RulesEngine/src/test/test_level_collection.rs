@@ -0,0 +1,70 @@
+﻿
+#[cfg(test)]
+mod test {
+    use crate::level_collection::*;
+
+    const COLLECTION: &str = r#"
+; a collection of two levels
+Title: First
+Author: Someone
+
+#####
+#@$.#
+#####
+
+######
+#@ $.#
+######
+Title: Second
+Author: Someone Else
+"#;
+
+    #[test]
+    fn parses_single_level_without_headers() {
+        let levels = parse_level_collection(r#"
+####
+#@$.#
+#####
+"#);
+        assert_eq!(1, levels.len());
+        assert_eq!(None, levels[0].title);
+        assert_eq!("####\n#@$.#\n#####", levels[0].map);
+    }
+
+    #[test]
+    fn parses_headers_above_and_below_maps() {
+        let levels = parse_level_collection(COLLECTION);
+        assert_eq!(2, levels.len());
+
+        assert_eq!(Some("First".to_string()), levels[0].title);
+        assert_eq!(Some("Someone".to_string()), levels[0].author);
+        assert_eq!("#####\n#@$.#\n#####", levels[0].map);
+
+        assert_eq!(Some("Second".to_string()), levels[1].title);
+        assert_eq!(Some("Someone Else".to_string()), levels[1].author);
+        assert_eq!("######\n#@ $.#\n######", levels[1].map);
+    }
+
+    #[test]
+    fn selects_level_by_position_or_title() {
+        let by_position = select_level(parse_level_collection(COLLECTION), &LevelSelector::parse("2")).unwrap();
+        assert_eq!(Some("Second".to_string()), by_position.title);
+
+        let by_title = select_level(parse_level_collection(COLLECTION), &LevelSelector::parse("first")).unwrap();
+        assert_eq!(Some("First".to_string()), by_title.title);
+
+        assert!(select_level(parse_level_collection(COLLECTION), &LevelSelector::parse("3")).is_err());
+        assert!(select_level(parse_level_collection(COLLECTION), &LevelSelector::parse("Third")).is_err());
+    }
+
+    #[test]
+    fn bundled_example_levels_parse() {
+        let levels = parse_level_collection(include_str!("../../levels/examples.txt"));
+        assert_eq!(6, levels.len());
+        assert!(levels.iter().all(|level| level.title.is_some()));
+
+        let default_level = parse_level_collection(include_str!("../../levels/default.txt"));
+        assert_eq!(1, default_level.len());
+        assert_eq!(default_level[0].map, levels[0].map);
+    }
+}
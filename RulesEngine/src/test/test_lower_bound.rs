@@ -0,0 +1,147 @@
+﻿#[cfg(test)]
+mod test {
+    use crate::core::*;
+    use crate::solver::{solve_a_star, MatchingBound, SolverLimits};
+    use crate::test::test_util::GameTestState;
+
+    fn lower_bound(level: &str) -> Option<u32> {
+        let game = GameTestState::new(level);
+        push_lower_bound(&game.shared, &game.game_state.environment)
+    }
+
+    /// Lowest total over every way to give each row a different column, by trying them all
+    fn brute_force_assignment(costs: &[Vec<Option<u32>>], columns: usize) -> Option<u32> {
+        fn search(costs: &[Vec<Option<u32>>], row: usize, used: &mut Vec<bool>) -> Option<u32> {
+            if row == costs.len() {
+                return Some(0);
+            }
+            let mut best = None;
+            for column in 0..used.len() {
+                let Some(cost) = costs[row][column] else { continue };
+                if used[column] {
+                    continue;
+                }
+                used[column] = true;
+                if let Some(rest) = search(costs, row + 1, used) {
+                    best = Some(best.map_or(cost + rest, |b: u32| b.min(cost + rest)));
+                }
+                used[column] = false;
+            }
+            best
+        }
+        search(costs, 0, &mut vec![false; columns])
+    }
+
+    #[test]
+    fn assignment_matches_brute_force() {
+        let mut seed = 12345u64;
+        let mut next = move || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) as u32
+        };
+        for _ in 0..300 {
+            let rows = (next() % 5) as usize;
+            let columns = rows + (next() % 3) as usize;
+            let costs: Vec<Vec<Option<u32>>> = (0..rows)
+                .map(|_| (0..columns).map(|_| if next() % 4 == 0 { None } else { Some(next() % 20) }).collect())
+                .collect();
+
+            let expected = brute_force_assignment(&costs, columns);
+            let actual = min_cost_assignment(rows, columns, |r, c| costs[r][c]);
+            assert_eq!(expected, actual, "costs {:?}", costs);
+        }
+    }
+
+    #[test]
+    fn assignment_needs_a_column_per_row() {
+        assert_eq!(None, min_cost_assignment(2, 1, |_, _| Some(1)));
+        assert_eq!(Some(0), min_cost_assignment(0, 3, |_, _| Some(1)));
+    }
+
+    #[test]
+    fn push_distances_follow_pushable_paths() {
+        let game = GameTestState::new(r#"
+#######
+#.   @#
+#     #
+#######
+"#);
+        let distances = game.shared.push_distances();
+        let at = |i, j| Vec2 { i, j };
+        assert_eq!(Some(0), distances.distance(0, at(1, 1)));
+        assert_eq!(Some(3), distances.distance(0, at(1, 4)));
+        // nowhere to stand to push a box in the far corner
+        assert_eq!(None, distances.distance(0, at(1, 5)));
+        // a box along the bottom wall can never be pushed up to the target row
+        assert_eq!(None, distances.distance(0, at(2, 3)));
+        assert!(!distances.reaches_any_target(at(2, 3)));
+    }
+
+    #[test]
+    fn bound_counts_pushes_around_walls() {
+        // the box has to go around the wall, which the plain box distance would ignore
+        assert_eq!(Some(4), lower_bound(r#"
+#########
+#@      #
+#       #
+#  .#$  #
+#       #
+#       #
+#########
+"#));
+    }
+
+    #[test]
+    fn bound_assigns_boxes_to_different_targets() {
+        // both boxes are nearest the left target, but only one can use it
+        assert_eq!(Some(1 + 3), lower_bound(r#"
+########
+#@     #
+#.$$  .#
+#      #
+########
+"#));
+    }
+
+    #[test]
+    fn bound_is_none_for_deadlocks() {
+        // the box against the top wall can only move along it, and no target is there
+        assert_eq!(None, lower_bound(r#"
+######
+#@ $ #
+#    #
+#  . #
+######
+"#));
+        // two boxes which can each only reach the same target
+        assert_eq!(None, lower_bound(r#"
+#######
+#$.$  #
+#@   .#
+#######
+"#));
+    }
+
+    #[test]
+    fn bound_is_zero_when_won() {
+        assert_eq!(Some(0), lower_bound(r#"
+#####
+#@**#
+#####
+"#));
+    }
+
+    #[test]
+    fn matching_bound_keeps_solutions_optimal() {
+        let game = GameTestState::new(r#"
+########
+#    . #
+#      #
+#  $#$ #
+# .@   #
+########
+"#);
+        let result = solve_a_star(&game.shared, &game.game_state, &MatchingBound::new(&game.shared), &ExplorationOptions::default(), SolverLimits::unlimited());
+        assert_eq!(Some(4), result.outcome.solution().map(|nodes| nodes.len() - 1));
+    }
+}
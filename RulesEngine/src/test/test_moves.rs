@@ -1,7 +1,6 @@
 ﻿
 #[cfg(test)]
 mod test {
-    use std::hint::black_box;
     use Direction::*;
     use crate::core::*;
     use crate::test::test_util::GameTestState;
@@ -123,7 +122,7 @@ mod test {
 "#;
         game.assert_matches(expected_level);
 
-        if black_box(DEDUPLICATE_BOXES) {
+        if ExplorationOptions::default().deduplicate_boxes {
             assert_eq!(original_state, new_state);
         } else {
             assert_ne!(original_state, new_state);
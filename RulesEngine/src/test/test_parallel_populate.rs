@@ -0,0 +1,93 @@
+﻿#[cfg(test)]
+mod test {
+    use crate::core::*;
+    use crate::state_graph::{populate_all, populate_parallel_step, PopulateResult, StateGraph};
+    use crate::test::test_util::{edge_set, explore, node_set, start_graph, GameTestState, ROOM, WAREHOUSE};
+
+    const LEVELS: &[&str] = &[
+        ROOM,
+        WAREHOUSE,
+        r#"
+   #########
+########  ##
+#          ###
+# @$$ ##   ..#
+# $$   ##  ..#
+#         ####
+###########
+"#,
+    ];
+
+    fn explore_level(level: &str, options: &ExplorationOptions) -> StateGraph {
+        let graph = explore(&GameTestState::new(level), options);
+        assert!(graph.unvisited.is_empty());
+        graph
+    }
+
+    fn with_threads(threads: usize, search_order: SearchOrder) -> ExplorationOptions {
+        ExplorationOptions {
+            threads,
+            search_order,
+            detectors: DeadlockDetectors::all(),
+            ..ExplorationOptions::default()
+        }
+    }
+
+    #[test]
+    fn parallel_breadth_first_matches_sequential_exactly() {
+        for level in LEVELS {
+            let sequential = explore_level(level, &with_threads(1, SearchOrder::BreadthFirst));
+            let parallel = explore_level(level, &with_threads(4, SearchOrder::BreadthFirst));
+
+            assert_eq!(sequential.nodes, parallel.nodes);
+            assert_eq!(sequential.edges, parallel.edges);
+        }
+    }
+
+    #[test]
+    fn parallel_depth_first_finds_the_same_graph() {
+        for level in LEVELS {
+            let sequential = explore_level(level, &with_threads(1, SearchOrder::DepthFirst));
+            let parallel = explore_level(level, &with_threads(3, SearchOrder::DepthFirst));
+
+            assert_eq!(node_set(&sequential), node_set(&parallel));
+            assert_eq!(edge_set(&sequential), edge_set(&parallel));
+        }
+    }
+
+    #[test]
+    fn parallel_step_reports_when_all_visited() {
+        let game = GameTestState::new(LEVELS[0]);
+        let options = with_threads(2, SearchOrder::BreadthFirst);
+        let mut graph = start_graph(&game, &options);
+
+        assert_eq!(populate_parallel_step(&mut graph, &game.shared, &options), PopulateResult::Populated);
+        while populate_parallel_step(&mut graph, &game.shared, &options) == PopulateResult::Populated {}
+
+        assert!(graph.unvisited.is_empty());
+        assert_eq!(graph.nodes, explore_level(LEVELS[0], &with_threads(1, SearchOrder::BreadthFirst)).nodes);
+    }
+
+    #[test]
+    fn parallel_step_stays_within_the_node_and_edge_budgets() {
+        let game = GameTestState::new(LEVELS[2]);
+        let pushes_per_node = 4 * game.game_state.environment.iter_boxes().count();
+        let budgets = [
+            (Some(100), None, Budget::Nodes),
+            (None, Some(300), Budget::Edges),
+        ];
+
+        for (max_nodes, max_edges, budget) in budgets {
+            let options = ExplorationOptions {
+                max_nodes,
+                max_edges,
+                ..with_threads(4, SearchOrder::BreadthFirst)
+            };
+            let mut graph = start_graph(&game, &options);
+
+            assert_eq!(populate_all(&mut graph, &game.shared, &options), PopulateResult::BudgetExhausted(budget));
+            assert!(graph.nodes.len() < max_nodes.unwrap_or(usize::MAX - pushes_per_node) + pushes_per_node);
+            assert!(graph.edges.len() < max_edges.unwrap_or(usize::MAX - pushes_per_node) + pushes_per_node);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+﻿
+#[cfg(test)]
+mod test {
+    use crate::console_interface::*;
+    use crate::core::GameStateEnvironment;
+
+    fn assert_parse_error(expected: LevelParseError, level: &str) {
+        match parse_level(level) {
+            Ok(_) => panic!("Expected {:?} but level parsed, for level: {}", expected, level),
+            Err(err) => assert_eq!(expected, err, "for level: {}", level),
+        }
+    }
+
+    fn at(line: usize, column: usize) -> LevelPosition {
+        LevelPosition { line, column }
+    }
+
+    #[test]
+    fn parses_valid_level() {
+        let (game_state, shared) = parse_level(r#"
+#####
+#@$.#
+#####
+"#).unwrap();
+        assert_eq!(1, game_state.environment.iter_boxes().count());
+        assert_eq!(1, shared.total_targets());
+    }
+
+    #[test]
+    fn parses_player_on_target() {
+        let (game_state, shared) = parse_level(r#"
+######
+#+$ .#
+# $  #
+######
+"#).unwrap();
+        assert_eq!(2, game_state.environment.iter_boxes().count());
+        assert_eq!(2, shared.total_targets());
+    }
+
+    #[test]
+    fn rejects_missing_player() {
+        assert_parse_error(LevelParseError::MissingPlayer { at: at(2, 1) }, r#"
+#####
+# $.#
+#####
+"#);
+    }
+
+    #[test]
+    fn rejects_duplicate_player() {
+        assert_parse_error(LevelParseError::DuplicatePlayer { at: at(4, 3), first: at(3, 2) }, r#"
+#####
+#@$.#
+#.+$#
+#####
+"#);
+    }
+
+    #[test]
+    fn rejects_too_many_boxes() {
+        let max = GameStateEnvironment::MAX_BOXES;
+        let row = "$".repeat(100);
+        let level = format!("@{}\n{}\n{}", row, row, row);
+        let result = parse_level_with_validation(&level, LevelValidation::PERMISSIVE);
+        assert_eq!(Err(LevelParseError::TooManyBoxes { at: at(3, max - 200 + 1), count: 300, max }), result.map(|_| ()));
+    }
+
+    #[test]
+    fn rejects_box_target_mismatch() {
+        assert_parse_error(LevelParseError::BoxTargetMismatch { at: at(3, 4), boxes: 2, targets: 1 }, r#"
+######
+#@$$.#
+######
+"#);
+        assert_parse_error(LevelParseError::BoxTargetMismatch { at: at(3, 5), boxes: 1, targets: 2 }, r#"
+######
+#@$..#
+######
+"#);
+    }
+
+    #[test]
+    fn rejects_open_map() {
+        assert_parse_error(LevelParseError::NotEnclosed { at: at(3, 4) }, r#"
+#####
+#@$.
+#####
+"#);
+        assert_parse_error(LevelParseError::NotEnclosed { at: at(4, 3) }, r#"
+#####
+#@$.#
+## ##
+"#);
+    }
+
+    #[test]
+    fn accepts_open_map_outside_of_player_area() {
+        parse_level(r#"
+  #####
+  #@$.#
+  #####
+"#).unwrap();
+    }
+
+    #[test]
+    fn rejects_coordinate_overflow() {
+        let level = format!("#@{}#", " ".repeat(200));
+        let result = parse_level_with_validation(&level, LevelValidation::PERMISSIVE);
+        assert_eq!(Err(LevelParseError::CoordinateOverflow { at: at(1, 128) }), result.map(|_| ()));
+    }
+
+    #[test]
+    fn rejects_unknown_character() {
+        assert_parse_error(LevelParseError::UnknownCharacter { at: at(3, 3), character: 'x' }, r#"
+#####
+#@x.#
+#####
+"#);
+    }
+
+    #[test]
+    fn bundled_example_levels_are_valid() {
+        for level in crate::level_collection::parse_level_collection(include_str!("../../levels/examples.txt")) {
+            if let Err(err) = parse_level(&level.map) {
+                panic!("{} is invalid: {}", level.describe(), err);
+            }
+        }
+    }
+}
@@ -3,7 +3,7 @@
 mod test {
     use crate::core::*;
     use crate::state_graph::{get_all_adjacent_nodes, UniqueNode};
-    use crate::test::test_util::{assert_game_set_matches, assert_symbols_match, GameTestState};
+    use crate::test::test_util::{assert_game_set_matches, assert_symbols_match, explore, GameTestState, ROOM};
 
     #[test]
     fn find_reachable_finds_all_reachable(){
@@ -26,6 +26,21 @@ ____
         assert_symbols_match(expected_symbols, actual_symbols.as_str());
     }
 
+    #[test]
+    fn edges_record_the_walk_from_the_minimum_reachable_position() {
+        let game = GameTestState::new(ROOM);
+        let graph = explore(&game, &ExplorationOptions::default());
+        assert!(graph.edges.iter().any(|edge| edge.walk > 0));
+        for edge in graph.edges.iter() {
+            let from = graph.get_state(edge.from).unwrap();
+            let distances = game.shared.walk_distances(&GameState {
+                player: from.minimum_reachable_player_position.into(),
+                environment: from.environment.clone(),
+            });
+            assert_eq!(distances[&edge.push.player_position().into()], Some(edge.walk));
+        }
+    }
+
     #[test]
     fn find_adjacent_nodes_finds_all_possible_actions(){
         let level = r#"
@@ -40,7 +55,7 @@ ____
             environment: game.game_state.environment.clone(),
             minimum_reachable_player_position: game.game_state.player.into(),
         };
-        let new_game_states: Vec<GameState> = get_all_adjacent_nodes(&source_node, &game.shared).into_iter()
+        let new_game_states: Vec<GameState> = get_all_adjacent_nodes(&source_node, &game.shared, &ExplorationOptions::default()).into_iter()
             .map(|node| GameState {
                 player: node.minimum_reachable_player_position.into(),
                 environment: node.environment,
@@ -0,0 +1,145 @@
+﻿#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use crate::core::*;
+    use crate::state_graph::{get_all_pulls, goal_nodes, populate_winnable, trim_unwinnable, PopulateResult};
+    use crate::test::test_util::{edge_set, explore, node_set, start_graph, GameTestState, LOPSIDED, MIRRORED, ROOM, WAREHOUSE};
+
+    /// The box starts in a corner
+    const UNWINNABLE: &str = r#"
+#####
+#$ .#
+# @ #
+#####
+"#;
+
+    /// One box more than there are targets
+    const SPARE_BOX: &str = r#"
+######
+#    #
+# $$ #
+#@ . #
+######
+"#;
+
+    /// One box fewer than there are targets
+    const MISSING_BOX: &str = r#"
+#####
+#@$.#
+#  .#
+#####
+"#;
+
+    fn option_variants() -> Vec<ExplorationOptions> {
+        vec![
+            ExplorationOptions::default(),
+            ExplorationOptions::exhaustive(),
+            ExplorationOptions { deduplicate_boxes: false, ..ExplorationOptions::default() },
+            ExplorationOptions { reduce_symmetry: true, ..ExplorationOptions::default() },
+        ]
+    }
+
+    #[test]
+    fn matches_populating_then_trimming() {
+        for level in [ROOM, WAREHOUSE, MIRRORED, LOPSIDED, UNWINNABLE, SPARE_BOX, MISSING_BOX] {
+            let game = GameTestState::new(level);
+            for options in option_variants() {
+                let mut forward = explore(&game, &options);
+                trim_unwinnable(&mut forward, &game.shared);
+
+                let mut reverse = start_graph(&game, &options);
+                assert_eq!(populate_winnable(&mut reverse, &game.shared, &options), PopulateResult::AllVisited);
+
+                assert_eq!(node_set(&reverse), node_set(&forward));
+                assert_eq!(edge_set(&reverse), edge_set(&forward));
+            }
+        }
+    }
+
+    #[test]
+    fn pulls_undo_every_push() {
+        let game = GameTestState::new(ROOM);
+        let options = ExplorationOptions::exhaustive();
+        let graph = explore(&game, &options);
+
+        for edge in graph.edges.iter() {
+            let from = graph.get_state(edge.from).unwrap();
+            let to = graph.get_state(edge.to).unwrap();
+            let pulls = get_all_pulls(to, &game.shared, &options);
+            assert!(pulls.contains(&(edge.push, from.clone())));
+        }
+    }
+
+    #[test]
+    fn goal_nodes_are_won() {
+        let game = GameTestState::new(ROOM);
+        let won = game.shared.get_won_check_helper();
+        let deduplicated = goal_nodes(&game.shared, &game.game_state.environment, &ExplorationOptions::default()).unwrap();
+        assert!(!deduplicated.is_empty());
+        assert!(deduplicated.iter().all(|node| won.is_won(&node.environment)));
+
+        let options = ExplorationOptions { deduplicate_boxes: false, ..ExplorationOptions::default() };
+        let ordered = goal_nodes(&game.shared, &game.game_state.environment, &options).unwrap();
+        assert_eq!(ordered.len(), 2 * deduplicated.len());
+    }
+
+    #[test]
+    fn goal_nodes_place_spare_boxes_off_the_targets() {
+        let game = GameTestState::new(SPARE_BOX);
+        let won = game.shared.get_won_check_helper();
+        let goals = goal_nodes(&game.shared, &game.game_state.environment, &ExplorationOptions::default()).unwrap();
+        assert!(!goals.is_empty());
+        for goal in &goals {
+            assert!(won.is_won(&goal.environment));
+            let boxes = game.shared.box_board(&goal.environment);
+            assert_eq!(boxes.count(), 2);
+        }
+    }
+
+    #[test]
+    fn goal_nodes_are_distinct_under_symmetry_reduction() {
+        let game = GameTestState::new(MIRRORED);
+        let options = ExplorationOptions { reduce_symmetry: true, ..ExplorationOptions::default() };
+        let goals = goal_nodes(&game.shared, &game.game_state.environment, &options).unwrap();
+        assert_eq!(goals.iter().collect::<HashSet<_>>().len(), goals.len());
+    }
+
+    #[test]
+    fn goal_nodes_stay_within_the_node_budget() {
+        let game = GameTestState::new(SPARE_BOX);
+        let options = ExplorationOptions {
+            deduplicate_boxes: false,
+            max_nodes: Some(3),
+            ..ExplorationOptions::default()
+        };
+        assert_eq!(goal_nodes(&game.shared, &game.game_state.environment, &options), Err(Budget::Nodes));
+    }
+
+    #[test]
+    fn goal_nodes_need_a_box_per_target() {
+        let game = GameTestState::new(MISSING_BOX);
+        assert!(goal_nodes(&game.shared, &game.game_state.environment, &ExplorationOptions::default()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn budget_leaves_the_graph_unchanged() {
+        let game = GameTestState::new(WAREHOUSE);
+        let options = ExplorationOptions { max_nodes: Some(10), ..ExplorationOptions::default() };
+        let mut graph = start_graph(&game, &options);
+        assert_eq!(populate_winnable(&mut graph, &game.shared, &options), PopulateResult::BudgetExhausted(Budget::Nodes));
+        assert_eq!(graph.nodes.len(), 1);
+        assert_eq!(graph.unvisited.len(), 1);
+    }
+
+    #[test]
+    fn budget_while_pushing_forward_leaves_the_graph_unchanged() {
+        // pulling adds no edges, so only pushing forward can use up the edge budget
+        let game = GameTestState::new(WAREHOUSE);
+        let options = ExplorationOptions { max_edges: Some(3), ..ExplorationOptions::default() };
+        let mut graph = start_graph(&game, &options);
+        assert_eq!(populate_winnable(&mut graph, &game.shared, &options), PopulateResult::BudgetExhausted(Budget::Edges));
+        assert_eq!(graph.nodes.len(), 1);
+        assert_eq!(graph.edges.len(), 0);
+        assert_eq!(graph.unvisited.len(), 1);
+    }
+}
@@ -0,0 +1,177 @@
+﻿
+#[cfg(test)]
+mod test {
+    use std::collections::{HashSet, VecDeque};
+    use crate::core::{step, Direction, ExplorationOptions, GameUpdate, UserAction};
+    use crate::state_graph::{find_move_optimal_solution, find_push_optimal_solution, populate_step, push_optimal_solution, solution_to_lurd, NodeId, PopulateResult, Solution, StateGraph, UniqueNode};
+    use crate::test::test_util::GameTestState;
+
+    fn populate(game: &GameTestState) -> (StateGraph, NodeId) {
+        let mut graph = StateGraph::new();
+        let first_id = graph.upsert_state(UniqueNode::from_game_state(game.game_state.clone(), &game.shared));
+        while let PopulateResult::Populated = populate_step(&mut graph, &game.shared, &ExplorationOptions::default()) {}
+        (graph, first_id)
+    }
+
+    fn solve_both(game: &GameTestState) -> (Solution, Solution) {
+        let (graph, first_id) = populate(game);
+        let push_optimal = push_optimal_solution(&graph, &game.shared, &game.game_state, first_id).unwrap();
+        let move_optimal = find_move_optimal_solution(&graph, &game.shared, &game.game_state, first_id).unwrap();
+        (push_optimal, move_optimal)
+    }
+
+    /// Fewest moves to win, by searching every single move. Only practical for tiny levels.
+    fn brute_force_move_count(game: &GameTestState) -> usize {
+        let mut seen = HashSet::from([game.game_state.clone()]);
+        let mut queue = VecDeque::from([(game.game_state.clone(), 0)]);
+        while let Some((state, moves)) = queue.pop_front() {
+            if game.shared.is_won(&state) {
+                return moves;
+            }
+            for direction in Direction::ALL {
+                if let GameUpdate::NextState(next, _) = step(&game.shared, &state, UserAction::Move(direction)) {
+                    if seen.insert(next.clone()) {
+                        queue.push_back((next, moves + 1));
+                    }
+                }
+            }
+        }
+        panic!("level has no solution");
+    }
+
+    fn solve(game: &GameTestState) -> Option<(Vec<UniqueNode>, String)> {
+        let (graph, first_id) = populate(game);
+
+        let solution = find_push_optimal_solution(&graph, &game.shared, first_id)?;
+        let moves = solution_to_lurd(&game.shared, &game.game_state, &solution).expect("solution must expand into moves");
+        Some((solution, moves))
+    }
+
+    #[test]
+    fn single_push() {
+        let game = GameTestState::new(r#"
+#####
+#@$.#
+#####
+"#);
+        let (solution, moves) = solve(&game).unwrap();
+        assert_eq!(2, solution.len());
+        assert_eq!("R", moves);
+    }
+
+    #[test]
+    fn walks_between_pushes() {
+        let game = GameTestState::new(r#"
+#######
+#.    #
+#  $ @#
+#     #
+#######
+"#);
+        let (solution, moves) = solve(&game).unwrap();
+        // two pushes left and one up, in some order
+        assert_eq!(4, solution.len());
+        assert_eq!(3, moves.chars().filter(|c| c.is_ascii_uppercase()).count());
+
+        let mut game = game;
+        game.assert_lurd(&moves);
+        assert!(game.shared.is_won(&game.game_state));
+    }
+
+    #[test]
+    fn solution_is_push_optimal() {
+        let mut game = GameTestState::new(r#"
+#######
+#@    #
+# $ $ #
+#     #
+#.  . #
+#######
+"#);
+        let (solution, moves) = solve(&game).unwrap();
+        game.assert_lurd(&moves);
+        assert!(game.shared.is_won(&game.game_state));
+        assert_eq!(solution.len() - 1, moves.chars().filter(|c| c.is_ascii_uppercase()).count());
+    }
+
+    #[test]
+    fn unsolvable_level_has_no_solution() {
+        let game = GameTestState::new(r#"
+#####
+#@ $#
+#  .#
+#####
+"#);
+        assert!(solve(&game).is_none());
+    }
+
+    #[test]
+    fn rejects_nodes_without_a_push_between() {
+        let level = r#"
+#######
+#@ $ .#
+#######
+"#;
+        let game = GameTestState::new(level);
+        let start = UniqueNode::from_game_state(game.game_state.clone(), &game.shared);
+        let mut far = GameTestState::new(level);
+        far.assert_lurd("rRR");
+        let skipped = UniqueNode::from_game_state(far.game_state.clone(), &far.shared);
+
+        assert!(solution_to_lurd(&game.shared, &game.game_state, &[start, skipped]).is_none());
+    }
+
+    #[test]
+    fn move_optimal_matches_brute_force() {
+        let levels = [r#"
+#######
+#.    #
+#  $ @#
+#     #
+#######
+"#, r#"
+#######
+#@    #
+# $ $ #
+#     #
+#.  . #
+#######
+"#, r#"
+########
+#   #  #
+# $   .#
+#@ ## $#
+#    . #
+########
+"#];
+        for level in levels {
+            let game = GameTestState::new(level);
+            let (push_optimal, move_optimal) = solve_both(&game);
+
+            assert_eq!(brute_force_move_count(&game), move_optimal.move_count(), "in level {}", level);
+            assert!(move_optimal.move_count() <= push_optimal.move_count());
+            assert!(move_optimal.push_count() >= push_optimal.push_count());
+
+            let mut played = GameTestState::new(level);
+            played.assert_lurd(&move_optimal.moves);
+            assert!(played.shared.is_won(&played.game_state));
+        }
+    }
+
+    #[test]
+    fn move_optimal_can_take_extra_pushes() {
+        let game = GameTestState::new(r#"
+########
+#    . #
+#      #
+#  $#$ #
+# .@   #
+########
+"#);
+        let (push_optimal, move_optimal) = solve_both(&game);
+
+        assert_eq!((4, 18), (push_optimal.push_count(), push_optimal.move_count()));
+        assert_eq!((6, 12), (move_optimal.push_count(), move_optimal.move_count()));
+        assert_eq!(brute_force_move_count(&game), move_optimal.move_count());
+    }
+}
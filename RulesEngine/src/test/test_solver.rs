@@ -0,0 +1,229 @@
+﻿
+#[cfg(test)]
+mod test {
+    use crate::core::ExplorationOptions;
+    use crate::solver::{solve_a_star, solve_bidirectional, solve_ida_star, BoxDistanceBound, LimitReached, LowerBound, SolveOutcome, SolverLimits, SolverResult, ZeroBound};
+    use crate::state_graph::{find_push_optimal_path, populate_step, solution_to_lurd, PopulateResult, StateGraph, UniqueNode};
+    use crate::test::test_util::{GameTestState, MIRRORED};
+
+    const SOLVABLE_LEVELS: [&str; 3] = [r#"
+#######
+#.    #
+#  $ @#
+#     #
+#######
+"#, r#"
+#######
+#@    #
+# $ $ #
+#     #
+#.  . #
+#######
+"#, r#"
+########
+#    . #
+#      #
+#  $#$ #
+# .@   #
+########
+"#];
+
+    /// The box under the target takes a detour, pushing the far box in looks cheaper when the
+    /// spare box counts towards the estimate
+    const SPARE_BOX_LEVEL: &str = r#"
+#########
+#   .   #
+#   #   #
+#   $   #
+#       #
+#       #
+# $     #
+#@      #
+#########
+"#;
+
+    const UNSOLVABLE_LEVEL: &str = r#"
+#######
+#@ $  #
+#   $ #
+# #####
+#.  . #
+#######
+"#;
+
+    fn graph_push_count(game: &GameTestState) -> Option<usize> {
+        let mut graph = StateGraph::new();
+        let first_id = graph.upsert_state(UniqueNode::from_game_state(game.game_state.clone(), &game.shared));
+        while let PopulateResult::Populated = populate_step(&mut graph, &game.shared, &ExplorationOptions::default()) {}
+        find_push_optimal_path(&graph, &game.shared, first_id).map(|path| path.len() - 1)
+    }
+
+    fn assert_solution_plays(level: &str, result: &SolverResult) {
+        let SolveOutcome::Solved(nodes) = &result.outcome else {
+            panic!("expected a solution, got {:?}", result.outcome);
+        };
+        let mut game = GameTestState::new(level);
+        let moves = solution_to_lurd(&game.shared, &game.game_state, nodes).unwrap();
+        game.assert_lurd(&moves);
+        assert!(game.shared.is_won(&game.game_state));
+    }
+
+    fn push_count(result: &SolverResult) -> Option<usize> {
+        result.outcome.solution().map(|nodes| nodes.len() - 1)
+    }
+
+    #[test]
+    fn a_star_is_push_optimal() {
+        for level in SOLVABLE_LEVELS.into_iter().chain([SPARE_BOX_LEVEL]) {
+            let game = GameTestState::new(level);
+            let bound = BoxDistanceBound::new(&game.shared);
+            let result = solve_a_star(&game.shared, &game.game_state, &bound, &ExplorationOptions::default(), SolverLimits::unlimited());
+
+            assert_solution_plays(level, &result);
+            assert_eq!(graph_push_count(&game), push_count(&result), "in level {}", level);
+            assert!(result.stats.nodes_expanded > 0);
+        }
+    }
+
+    #[test]
+    fn ida_star_is_push_optimal() {
+        for level in SOLVABLE_LEVELS.into_iter().chain([SPARE_BOX_LEVEL]) {
+            let game = GameTestState::new(level);
+            let bound = BoxDistanceBound::new(&game.shared);
+            let result = solve_ida_star(&game.shared, &game.game_state, &bound, &ExplorationOptions::default(), SolverLimits::unlimited());
+
+            assert_solution_plays(level, &result);
+            assert_eq!(graph_push_count(&game), push_count(&result), "in level {}", level);
+            assert!(result.stats.nodes_expanded > 0);
+        }
+    }
+
+    #[test]
+    fn bidirectional_is_push_optimal() {
+        for level in SOLVABLE_LEVELS {
+            let game = GameTestState::new(level);
+            let result = solve_bidirectional(&game.shared, &game.game_state, &ExplorationOptions::default(), SolverLimits::unlimited());
+
+            assert_solution_plays(level, &result);
+            assert_eq!(graph_push_count(&game), push_count(&result), "in level {}", level);
+            assert!(result.stats.nodes_expanded > 0);
+        }
+    }
+
+    #[test]
+    fn solutions_play_with_symmetry_reduction() {
+        let rotated = r#"
+#######
+#.   .#
+#  $  #
+# $@$ #
+#  $  #
+#.   .#
+#######
+"#;
+        let options = ExplorationOptions { reduce_symmetry: true, ..ExplorationOptions::default() };
+        for level in [MIRRORED, rotated] {
+            let game = GameTestState::new(level);
+            assert!(!game.shared.symmetries().is_empty());
+            let bound = BoxDistanceBound::new(&game.shared);
+            let results = [
+                solve_a_star(&game.shared, &game.game_state, &bound, &options, SolverLimits::unlimited()),
+                solve_ida_star(&game.shared, &game.game_state, &bound, &options, SolverLimits::unlimited()),
+                solve_bidirectional(&game.shared, &game.game_state, &options, SolverLimits::unlimited()),
+            ];
+
+            for result in results {
+                assert_solution_plays(level, &result);
+                assert_eq!(graph_push_count(&game), push_count(&result), "in level {}", level);
+            }
+        }
+    }
+
+    #[test]
+    fn bidirectional_keeps_boxes_apart() {
+        let options = ExplorationOptions {
+            deduplicate_boxes: false,
+            ..ExplorationOptions::default()
+        };
+        for level in SOLVABLE_LEVELS {
+            let game = GameTestState::new(level);
+            let result = solve_bidirectional(&game.shared, &game.game_state, &options, SolverLimits::unlimited());
+
+            assert_solution_plays(level, &result);
+            assert_eq!(graph_push_count(&game), push_count(&result), "in level {}", level);
+        }
+    }
+
+    #[test]
+    fn better_bound_expands_fewer_nodes() {
+        let level = SOLVABLE_LEVELS[2];
+        let game = GameTestState::new(level);
+        let zero = solve_a_star(&game.shared, &game.game_state, &ZeroBound, &ExplorationOptions::default(), SolverLimits::unlimited());
+        let box_distance = solve_a_star(&game.shared, &game.game_state, &BoxDistanceBound::new(&game.shared), &ExplorationOptions::default(), SolverLimits::unlimited());
+
+        assert_eq!(push_count(&zero), push_count(&box_distance));
+        assert!(box_distance.stats.nodes_expanded < zero.stats.nodes_expanded,
+                "{} expanded with the bound, {} without", box_distance.stats.nodes_expanded, zero.stats.nodes_expanded);
+    }
+
+    #[test]
+    fn reports_unsolvable() {
+        let game = GameTestState::new(UNSOLVABLE_LEVEL);
+        assert_eq!(None, graph_push_count(&game));
+        let bound = BoxDistanceBound::new(&game.shared);
+
+        let a_star = solve_a_star(&game.shared, &game.game_state, &bound, &ExplorationOptions::default(), SolverLimits::unlimited());
+        assert!(matches!(a_star.outcome, SolveOutcome::Unsolvable), "{:?}", a_star.outcome);
+
+        let ida_star = solve_ida_star(&game.shared, &game.game_state, &bound, &ExplorationOptions::default(), SolverLimits::unlimited());
+        assert!(matches!(ida_star.outcome, SolveOutcome::Unsolvable), "{:?}", ida_star.outcome);
+
+        let bidirectional = solve_bidirectional(&game.shared, &game.game_state, &ExplorationOptions::default(), SolverLimits::unlimited());
+        assert!(matches!(bidirectional.outcome, SolveOutcome::Unsolvable), "{:?}", bidirectional.outcome);
+    }
+
+    #[test]
+    fn stops_at_node_limit() {
+        let game = GameTestState::new(SOLVABLE_LEVELS[1]);
+        let limits = SolverLimits {
+            max_nodes_expanded: Some(2),
+            max_time: None,
+        };
+
+        let a_star = solve_a_star(&game.shared, &game.game_state, &ZeroBound, &ExplorationOptions::default(), limits);
+        assert!(matches!(a_star.outcome, SolveOutcome::LimitReached(LimitReached::Nodes)), "{:?}", a_star.outcome);
+        assert_eq!(2, a_star.stats.nodes_expanded);
+
+        let ida_star = solve_ida_star(&game.shared, &game.game_state, &ZeroBound, &ExplorationOptions::default(), limits);
+        assert!(matches!(ida_star.outcome, SolveOutcome::LimitReached(LimitReached::Nodes)), "{:?}", ida_star.outcome);
+        assert_eq!(2, ida_star.stats.nodes_expanded);
+
+        let bidirectional = solve_bidirectional(&game.shared, &game.game_state, &ExplorationOptions::default(), limits);
+        assert!(matches!(bidirectional.outcome, SolveOutcome::LimitReached(LimitReached::Nodes)), "{:?}", bidirectional.outcome);
+        assert_eq!(2, bidirectional.stats.nodes_expanded);
+    }
+
+    #[test]
+    fn stops_at_time_limit() {
+        let game = GameTestState::new(SOLVABLE_LEVELS[1]);
+        let limits = SolverLimits {
+            max_nodes_expanded: None,
+            max_time: Some(std::time::Duration::ZERO),
+        };
+        let result = solve_a_star(&game.shared, &game.game_state, &ZeroBound, &ExplorationOptions::default(), limits);
+        assert!(matches!(result.outcome, SolveOutcome::LimitReached(LimitReached::Time)), "{:?}", result.outcome);
+    }
+
+    #[test]
+    fn box_distance_bound_ignores_walls() {
+        let game = GameTestState::new(r#"
+#######
+#. # $#
+#  #  #
+#@    #
+#######
+"#);
+        let node = UniqueNode::from_game_state(game.game_state.clone(), &game.shared);
+        assert_eq!(Some(4), BoxDistanceBound::new(&game.shared).estimate(&node));
+    }
+}
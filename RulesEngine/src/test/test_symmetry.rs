@@ -0,0 +1,174 @@
+﻿#[cfg(test)]
+mod test {
+    use crate::core::*;
+    use crate::state_graph::{canonicalize, expand_symmetric_graph, populate_all, read_state_graph, transform_node, transform_push, trim_unwinnable, write_state_graph, PopulateResult, Push, StateGraph, UniqueNode};
+    use crate::test::test_util::{edge_set, explore, node_set, start_graph, GameTestState, LOPSIDED, MIRRORED, ROOM};
+
+    /// Every mirror and rotation of a square
+    const SQUARE: &str = r#"
+#######
+#.   .#
+# $   #
+#  @  #
+#   $ #
+#.   .#
+#######
+"#;
+
+    /// Left to right inside the walls, not outside them
+    const RAGGED: &str = r#"
+ ########
+###.   .###
+  #     #
+  # $@$ #
+  #######
+"#;
+
+    fn explore_trimmed(game: &GameTestState, options: &ExplorationOptions) -> StateGraph {
+        let mut graph = explore(game, options);
+        if options.trim_unwinnable {
+            trim_unwinnable(&mut graph, &game.shared);
+        }
+        graph
+    }
+
+    fn reduced(options: ExplorationOptions) -> ExplorationOptions {
+        ExplorationOptions {
+            reduce_symmetry: true,
+            ..options
+        }
+    }
+
+    #[test]
+    fn detects_symmetries() {
+        let mirrored = GameTestState::new(MIRRORED);
+        assert_eq!(mirrored.shared.symmetries(), &[Symmetry::MirrorColumns]);
+
+        let square = GameTestState::new(SQUARE);
+        assert_eq!(square.shared.symmetries().len(), 7);
+
+        let ragged = GameTestState::new(RAGGED);
+        assert_eq!(ragged.shared.symmetries(), &[Symmetry::MirrorColumns]);
+
+        let asymmetric = GameTestState::new(ROOM);
+        assert!(asymmetric.shared.symmetries().is_empty());
+    }
+
+    #[test]
+    fn symmetries_are_undone_by_their_inverse() {
+        let size = Vec2 { i: 5, j: 5 };
+        let position = Vec2 { i: 1, j: 3 };
+        for symmetry in Symmetry::ALL {
+            let moved = symmetry.apply(position, size);
+            assert_eq!(symmetry.inverse().apply(moved, size), position, "{:?}", symmetry);
+            for direction in [Direction::Up, Direction::Down, Direction::Left, Direction::Right] {
+                // a step in `direction` lands where the mapped step lands
+                let stepped = symmetry.apply(position + direction.offset(), size);
+                assert_eq!(moved + symmetry.apply_direction(direction).offset(), stepped, "{:?} {:?}", symmetry, direction);
+            }
+        }
+    }
+
+    #[test]
+    fn mirrored_states_share_a_representative() {
+        let game = GameTestState::new(SQUARE);
+        let options = ExplorationOptions::default();
+        let node = UniqueNode::from_game_state(game.game_state.clone(), &game.shared);
+        let (canonical, _) = canonicalize(&game.shared, node.clone(), &options);
+        for symmetry in Symmetry::ALL {
+            let moved = transform_node(&game.shared, &node, symmetry, &options);
+            let (moved_canonical, to_canonical) = canonicalize(&game.shared, moved.clone(), &options);
+            assert_eq!(canonical, moved_canonical, "{:?}", symmetry);
+            assert_eq!(transform_node(&game.shared, &moved, to_canonical, &options), moved_canonical);
+        }
+        let push = Push { box_position: Vec2 { i: 2, j: 2 }, direction: Direction::Right };
+        assert_eq!(transform_push(&game.shared, push, Symmetry::Rotate90), Push { box_position: Vec2 { i: 2, j: 4 }, direction: Direction::Down });
+    }
+
+    #[test]
+    fn reduces_state_counts() {
+        for (level, factor) in [(MIRRORED, 2), (SQUARE, 8)] {
+            let game = GameTestState::new(level);
+            let full = explore_trimmed(&game, &ExplorationOptions::exhaustive());
+            let reduced = explore_trimmed(&game, &reduced(ExplorationOptions::exhaustive()));
+            // states mapped onto themselves by a symmetry are not shared
+            assert!(reduced.nodes.len() * factor >= full.nodes.len());
+            assert!(reduced.nodes.len() * factor < full.nodes.len() * 3 / 2,
+                    "{} nodes reduced to {}", full.nodes.len(), reduced.nodes.len());
+        }
+    }
+
+    #[test]
+    fn expands_to_the_full_graph() {
+        for level in [MIRRORED, LOPSIDED, SQUARE, RAGGED, ROOM] {
+            let game = GameTestState::new(level);
+            for options in [ExplorationOptions::exhaustive(), ExplorationOptions::default()] {
+                let full = explore_trimmed(&game, &options);
+                let options = reduced(options);
+                let reduced = explore_trimmed(&game, &options);
+                let start = UniqueNode::from_game_state(game.game_state.clone(), &game.shared);
+                let expanded = expand_symmetric_graph(&reduced, &game.shared, &start, &options);
+
+                assert_eq!(node_set(&full), node_set(&expanded));
+                assert_eq!(edge_set(&full), edge_set(&expanded));
+                if !full.nodes.is_empty() {
+                    assert_eq!(expanded.get_state(0), Some(&start));
+                }
+            }
+        }
+    }
+    #[test]
+    fn reduced_graphs_only_hold_representatives() {
+        let game = GameTestState::new(LOPSIDED);
+        let options = reduced(ExplorationOptions::default());
+        let graph = explore(&game, &options);
+        let start = UniqueNode::from_game_state(game.game_state.clone(), &game.shared);
+        let (representative, _) = canonicalize(&game.shared, start.clone(), &options);
+        assert_ne!(representative, start);
+        assert_eq!(graph.get_state(0), Some(&representative));
+        for node in graph.nodes.values() {
+            assert_eq!(&canonicalize(&game.shared, node.clone(), &options).0, node);
+        }
+    }
+
+    #[test]
+    fn expanding_a_partial_graph_keeps_its_frontier() {
+        let game = GameTestState::new(LOPSIDED);
+        let options = ExplorationOptions { max_nodes: Some(20), ..reduced(ExplorationOptions::default()) };
+        let mut partial = start_graph(&game, &options);
+        assert!(matches!(populate_all(&mut partial, &game.shared, &options), PopulateResult::BudgetExhausted(_)));
+
+        let start = UniqueNode::from_game_state(game.game_state.clone(), &game.shared);
+        let expanded = expand_symmetric_graph(&partial, &game.shared, &start, &options);
+        assert!(!expanded.unvisited.is_empty());
+        assert_eq!(expanded.iter_frontier().count(), expanded.unvisited.len());
+        for &id in &expanded.unvisited {
+            let (representative, _) = canonicalize(&game.shared, expanded.get_state(id).unwrap().clone(), &options);
+            let reduced_id = partial.nodes.get_id(&representative).unwrap();
+            assert!(partial.unvisited.contains(&reduced_id));
+        }
+    }
+
+    #[test]
+    fn graph_files_record_the_reduction() {
+        let game = GameTestState::new(MIRRORED);
+        for (options, reduce_symmetry) in [(ExplorationOptions::default(), false), (reduced(ExplorationOptions::default()), true)] {
+            let graph = explore(&game, &options);
+            assert_eq!(graph.reduce_symmetry, reduce_symmetry);
+
+            let mut bytes = Vec::new();
+            write_state_graph(&mut bytes, &graph, &game.shared, 0).unwrap();
+            assert_eq!(read_state_graph(bytes.as_slice()).unwrap().graph.reduce_symmetry, reduce_symmetry);
+        }
+
+        // the file holds the representative of the start, which still matches the level
+        let lopsided = GameTestState::new(LOPSIDED);
+        let mut bytes = Vec::new();
+        write_state_graph(&mut bytes, &explore(&lopsided, &reduced(ExplorationOptions::default())), &lopsided.shared, 0).unwrap();
+        assert!(read_state_graph(bytes.as_slice()).unwrap().check_level(&lopsided.shared, &lopsided.game_state).is_ok());
+
+        // nothing is reduced without symmetries
+        let asymmetric = GameTestState::new(ROOM);
+        assert!(!explore(&asymmetric, &reduced(ExplorationOptions::default())).reduce_symmetry);
+    }
+}
@@ -1,7 +1,13 @@
 ﻿use bevy::math::IVec2;
 pub use dissimilar::diff as __diff;
-use crate::console_interface::{parse_level, render_game_to_string};
+use crate::console_interface::{parse_level_with_validation, render_game_to_string, LevelValidation};
 use crate::core::{step, Direction, GameState, GameUpdate, SharedGameState, UserAction};
+#[cfg(test)]
+use std::collections::HashSet;
+#[cfg(test)]
+use crate::core::{DeadlockDetector, DetectorStage, ExplorationOptions, PushedState};
+#[cfg(test)]
+use crate::state_graph::{add_start, populate_all, Push, StateGraph, UniqueNode};
 
 #[macro_export]
 macro_rules! assert_eq_text {
@@ -37,6 +43,97 @@ pub fn format_diff(chunks: Vec<dissimilar::Chunk>) -> String {
     buf
 }
 
+/// Two boxes with room to move, every exploration test's first level
+#[cfg(test)]
+pub const ROOM: &str = r#"
+#######
+#     #
+# $$  #
+#@ .. #
+#######
+"#;
+
+/// Most pushes lead into a corner or against a wall
+#[cfg(test)]
+pub const WAREHOUSE: &str = r#"
+########
+# @$  .#
+# $  $ #
+# .# $ #
+#..#   #
+########
+"#;
+
+/// Symmetric left to right, the smallest level symmetry reduction shrinks
+#[cfg(test)]
+pub const MIRRORED: &str = r#"
+#######
+#.   .#
+#     #
+# $@$ #
+#     #
+#######
+"#;
+
+/// The walls of MIRRORED with boxes which are not, so the start is not its own representative
+#[cfg(test)]
+pub const LOPSIDED: &str = r#"
+#######
+#.   .#
+#   $ #
+# $ @ #
+#     #
+#######
+"#;
+
+/// A graph holding only the start of `game`, added for exploring with `options`
+#[cfg(test)]
+pub fn start_graph(game: &GameTestState, options: &ExplorationOptions) -> StateGraph {
+    let mut graph = StateGraph::new();
+    add_start(&mut graph, &game.shared, &game.game_state, options);
+    graph
+}
+
+/// The graph of `game` populated with [`populate_all`], untrimmed
+#[cfg(test)]
+pub fn explore(game: &GameTestState, options: &ExplorationOptions) -> StateGraph {
+    let mut graph = start_graph(game, options);
+    populate_all(&mut graph, &game.shared, options);
+    graph
+}
+
+#[cfg(test)]
+pub fn node_set(graph: &StateGraph) -> HashSet<UniqueNode> {
+    graph.nodes.values().cloned().collect()
+}
+
+/// Edges by the nodes they connect, so graphs with different ids compare equal
+#[cfg(test)]
+pub fn edge_set(graph: &StateGraph) -> HashSet<(UniqueNode, UniqueNode, Push, u16)> {
+    graph.edges.iter()
+        .map(|edge| (graph.get_state(edge.from).unwrap().clone(), graph.get_state(edge.to).unwrap().clone(), edge.push, edge.walk))
+        .collect()
+}
+
+/// A detector pruning every push, to tell apart states the detectors see from states they prune
+#[cfg(test)]
+pub struct PruneEveryPush;
+
+#[cfg(test)]
+impl DeadlockDetector for PruneEveryPush {
+    fn name(&self) -> &'static str {
+        "every-push"
+    }
+
+    fn stage(&self) -> DetectorStage {
+        DetectorStage::Push
+    }
+
+    fn is_push_deadlocked(&self, _shared: &SharedGameState, _pushed: &PushedState) -> bool {
+        true
+    }
+}
+
 pub struct GameTestState {
     pub game_state: GameState,
     pub shared: SharedGameState,
@@ -44,7 +141,8 @@ pub struct GameTestState {
 
 impl GameTestState {
     pub fn new(level: &str) -> Self {
-        let (game_state, shared) = parse_level(level);
+        let (game_state, shared) = parse_level_with_validation(level, LevelValidation::PERMISSIVE)
+            .unwrap_or_else(|e| panic!("Could not parse test level: {}", e));
         Self { game_state, shared }
     }
 
@@ -72,6 +170,22 @@ impl GameTestState {
         update
     }
 
+    /// Plays a move string in LURD notation, checking uppercase moves push a box and lowercase moves do not
+    pub fn assert_lurd(&mut self, moves: &str) {
+        for c in moves.chars() {
+            let direction = match c.to_ascii_lowercase() {
+                'u' => Direction::Up,
+                'd' => Direction::Down,
+                'l' => Direction::Left,
+                'r' => Direction::Right,
+                other => panic!("'{}' is not a LURD move", other),
+            };
+            let update = self.assert_move(direction);
+            let GameUpdate::NextState(_, change_type) = update else { unreachable!() };
+            assert_eq!(c.is_ascii_uppercase(), change_type.did_box_move(), "move '{}' in {}", c, moves);
+        }
+    }
+
     pub fn try_step(&mut self, action: UserAction) -> GameUpdate {
         let update = step(&self.shared, &self.game_state, action);
         if let GameUpdate::NextState(new_state, _change_type) = &update {